@@ -19,6 +19,15 @@ pub struct BindFeature {
     required: Option<Required>,
 }
 
+impl BindFeature {
+    /// Creates a bind feature, which may be required.
+    pub fn new(required: bool) -> BindFeature {
+        BindFeature {
+            required: if required { Some(Required) } else { None },
+        }
+    }
+}
+
 /// Notes that bind is required.
 #[derive(FromXml, AsXml, Debug, Clone, PartialEq)]
 #[xml(namespace = ns::BIND, name = "required")]
@@ -44,6 +53,11 @@ impl BindQuery {
     pub fn new(resource: Option<String>) -> BindQuery {
         BindQuery { resource }
     }
+
+    /// The resource requested by the client, if any.
+    pub fn resource(&self) -> Option<&str> {
+        self.resource.as_deref()
+    }
 }
 
 impl IqSetPayload for BindQuery {}
@@ -61,6 +75,12 @@ pub struct BindResponse {
 
 impl IqResultPayload for BindResponse {}
 
+impl From<FullJid> for BindResponse {
+    fn from(jid: FullJid) -> BindResponse {
+        BindResponse { jid }
+    }
+}
+
 impl From<BindResponse> for FullJid {
     fn from(bind: BindResponse) -> FullJid {
         bind.jid
@@ -125,6 +145,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serialise() {
+        let elem: Element = BindFeature::new(true).into();
+        assert!(elem.has_child("required", ns::BIND));
+
+        let bind = BindQuery::new(Some(String::from("Hello")));
+        assert_eq!(bind.resource(), Some("Hello"));
+
+        let jid = FullJid::new("coucou@linkmauve.fr/Hello").unwrap();
+        let elem: Element = BindResponse::from(jid.clone()).into();
+        assert_eq!(FullJid::from(BindResponse::try_from(elem).unwrap()), jid);
+    }
+
     #[cfg(not(feature = "disable-validation"))]
     #[test]
     fn test_invalid_resource() {
@@ -62,6 +62,18 @@ impl Conference {
             },
         )
     }
+
+    /// Turns a XEP-0402 "Bookmarks2" Conference element, along with the room JID, into a XEP-0048
+    /// Conference element.  Its extensions are lost, as they can't be represented here.
+    pub fn from_bookmarks2(jid: BareJid, conference: bookmarks2::Conference) -> Conference {
+        Conference {
+            autojoin: conference.autojoin,
+            jid,
+            name: conference.name,
+            nick: conference.nick,
+            password: conference.password,
+        }
+    }
 }
 
 /// An URL bookmark.
@@ -162,4 +174,15 @@ mod tests {
         );
         assert_eq!(storage.conferences[0].clone().password.unwrap(), "secret");
     }
+
+    #[test]
+    fn bookmarks2_round_trip() {
+        let elem: Element = "<conference xmlns='storage:bookmarks' autojoin='true' jid='test-muc@muc.localhost' name='Test MUC'><nick>Coucou</nick><password>secret</password></conference>".parse().unwrap();
+        let conference = Conference::try_from(elem).unwrap();
+        let (jid, conference2) = conference.clone().into_bookmarks2();
+        assert_eq!(jid, BareJid::new("test-muc@muc.localhost").unwrap());
+        assert_eq!(conference2.autojoin, true);
+        assert_eq!(conference2.name, Some(String::from("Test MUC")));
+        assert_eq!(Conference::from_bookmarks2(jid, conference2), conference);
+    }
 }
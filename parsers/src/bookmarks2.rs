@@ -16,10 +16,11 @@ use xso::{AsXml, FromXml};
 
 use crate::jid::ResourcePart;
 use crate::ns;
+use crate::pubsub::PubSubPayload;
 use minidom::Element;
 
 /// Potential extensions in a conference.
-#[derive(FromXml, AsXml, Debug, Clone, Default)]
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone, Default)]
 #[xml(namespace = ns::BOOKMARKS2, name = "extensions")]
 pub struct Extensions {
     /// Extension elements.
@@ -28,7 +29,7 @@ pub struct Extensions {
 }
 
 /// A conference bookmark.
-#[derive(FromXml, AsXml, Debug, Clone, Default)]
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone, Default)]
 #[xml(namespace = ns::BOOKMARKS2, name = "conference")]
 pub struct Conference {
     /// Whether a conference bookmark should be joined automatically.
@@ -59,6 +60,8 @@ impl Conference {
     }
 }
 
+impl PubSubPayload for Conference {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
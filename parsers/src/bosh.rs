@@ -0,0 +1,190 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{AsXml, FromXml};
+
+use crate::ns;
+use jid::Jid;
+use minidom::Element;
+
+generate_attribute!(
+    /// The type of a body, when it isn’t a regular one.
+    Type, "type", {
+        /// The session is being terminated, by either side.
+        Terminate => "terminate",
+
+        /// A recoverable error, used by legacy connection managers.
+        Error => "error",
+    }
+);
+
+/// The HTTP body wrapping every request and response of a BOSH session,
+/// along with the XEP-0206 attributes used for XMPP.
+///
+/// Every attribute is optional, as each one is only meaningful in some
+/// requests or responses.  The attributes which aren’t useful to XMPP, such
+/// as `accept` or `charsets`, are ignored.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::BOSH, name = "body", on_unknown_attribute = Discard)]
+pub struct Body {
+    /// The value of the HTTP Content-Type header to use.
+    #[xml(attribute(default))]
+    pub content: Option<String>,
+
+    /// The JID of the client, or the domain of the connection manager.
+    #[xml(attribute(default))]
+    pub from: Option<Jid>,
+
+    /// The domain the client wants to connect to.
+    #[xml(attribute(default))]
+    pub to: Option<Jid>,
+
+    /// The maximum number of requests the connection manager may keep
+    /// waiting at any one time.
+    #[xml(attribute(default))]
+    pub hold: Option<u32>,
+
+    /// The id of this request, incremented by one for each new request.
+    #[xml(attribute(default))]
+    pub rid: Option<u64>,
+
+    /// The id of the session, assigned by the connection manager.
+    #[xml(attribute(default))]
+    pub sid: Option<String>,
+
+    /// The longest time in seconds the connection manager may wait before
+    /// responding to a request.
+    #[xml(attribute(default))]
+    pub wait: Option<u32>,
+
+    /// The highest version of BOSH supported.
+    #[xml(attribute(default))]
+    pub ver: Option<String>,
+
+    /// The maximum number of simultaneous requests the client may make.
+    #[xml(attribute(default))]
+    pub requests: Option<u32>,
+
+    /// The shortest time in seconds between two empty requests.
+    #[xml(attribute(default))]
+    pub polling: Option<u32>,
+
+    /// The longest time in seconds the client may stay without any request
+    /// pending.
+    #[xml(attribute(default))]
+    pub inactivity: Option<u32>,
+
+    /// The highest rid received in order, acknowledging all the previous
+    /// ones.
+    #[xml(attribute(default))]
+    pub ack: Option<u64>,
+
+    /// The default language of the session.
+    #[xml(attribute(default, name = "xml:lang"))]
+    pub lang: Option<String>,
+
+    /// Set when terminating the session.
+    #[xml(attribute(default, name = "type"))]
+    pub type_: Option<Type>,
+
+    /// Why the connection manager terminated the session.
+    #[xml(attribute(default))]
+    pub condition: Option<String>,
+
+    /// The version of XMPP in use, `"1.0"` for XEP-0206.
+    #[xml(attribute(default, namespace = ns::XBOSH, name = "version"))]
+    pub xmpp_version: Option<String>,
+
+    /// Set by the client to restart the XMPP stream, such as after SASL.
+    #[xml(attribute(default, namespace = ns::XBOSH, name = "restart"))]
+    pub restart: Option<bool>,
+
+    /// The stanzas and nonzas carried by this body.
+    #[xml(element(n = ..))]
+    pub payloads: Vec<Element>,
+}
+
+impl Body {
+    /// Whether this body terminates the session.
+    pub fn is_terminate(&self) -> bool {
+        self.type_ == Some(Type::Terminate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Type, 1);
+        assert_size!(Body, 192);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Type, 1);
+        assert_size!(Body, 312);
+    }
+
+    #[test]
+    fn test_session_creation() {
+        let elem: Element = "<body xmlns='http://jabber.org/protocol/httpbind' xmlns:xmpp='urn:xmpp:xbosh' content='text/xml; charset=utf-8' from='user@example.com' hold='1' rid='1573741820' to='example.com' ver='1.6' wait='60' xml:lang='en' xmpp:version='1.0'/>"
+            .parse()
+            .unwrap();
+        let body = Body::try_from(elem).unwrap();
+        assert_eq!(body.rid, Some(1573741820));
+        assert_eq!(body.hold, Some(1));
+        assert_eq!(body.wait, Some(60));
+        assert_eq!(body.from, Some(Jid::new("user@example.com").unwrap()));
+        assert_eq!(body.to, Some(Jid::new("example.com").unwrap()));
+        assert_eq!(body.lang.as_deref(), Some("en"));
+        assert_eq!(body.xmpp_version.as_deref(), Some("1.0"));
+        assert_eq!(body.restart, None);
+        assert!(!body.is_terminate());
+        assert!(body.payloads.is_empty());
+    }
+
+    #[test]
+    fn test_session_response() {
+        let elem: Element = "<body xmlns='http://jabber.org/protocol/httpbind' xmlns:xmpp='urn:xmpp:xbosh' xmlns:stream='http://etherx.jabber.org/streams' wait='60' inactivity='30' polling='5' requests='2' hold='1' from='example.com' accept='deflate,gzip' sid='SomeSID' secure='true' charsets='ISO_8859-1 ISO-2022-JP' xmpp:version='1.0'><stream:features><mechanisms xmlns='urn:ietf:params:xml:ns:xmpp-sasl'><mechanism>SCRAM-SHA-1</mechanism></mechanisms></stream:features></body>"
+            .parse()
+            .unwrap();
+        let body = Body::try_from(elem).unwrap();
+        assert_eq!(body.sid.as_deref(), Some("SomeSID"));
+        assert_eq!(body.requests, Some(2));
+        assert_eq!(body.polling, Some(5));
+        assert_eq!(body.inactivity, Some(30));
+        assert_eq!(body.payloads.len(), 1);
+        assert!(body.payloads[0].is("features", ns::STREAM));
+    }
+
+    #[test]
+    fn test_restart() {
+        let body = Body {
+            rid: Some(1573741824),
+            sid: Some(String::from("SomeSID")),
+            restart: Some(true),
+            ..Default::default()
+        };
+        let elem: Element = body.into();
+        assert_eq!(elem.attr("rid"), Some("1573741824"));
+        let body = Body::try_from(elem).unwrap();
+        assert_eq!(body.restart, Some(true));
+    }
+
+    #[test]
+    fn test_terminate() {
+        let elem: Element = "<body xmlns='http://jabber.org/protocol/httpbind' type='terminate' condition='host-unknown'/>"
+            .parse()
+            .unwrap();
+        let body = Body::try_from(elem).unwrap();
+        assert!(body.is_terminate());
+        assert_eq!(body.condition.as_deref(), Some("host-unknown"));
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{AsXml, FromXml};
+
+use crate::message::MessagePayload;
+use crate::ns;
+
+/// Notes that the sender of this message would like to know when its
+/// recipient has received, displayed or acknowledged it.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::CHAT_MARKERS, name = "markable")]
+pub struct Markable;
+
+impl MessagePayload for Markable {}
+
+/// Notes that a previous markable message has been received by a client.
+///
+/// Newer clients use XEP-0184 receipts instead.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::CHAT_MARKERS, name = "received")]
+pub struct Received {
+    /// The 'id' attribute of the received message.
+    #[xml(attribute)]
+    pub id: String,
+}
+
+impl MessagePayload for Received {}
+
+/// Notes that a previous markable message, and every message before it,
+/// has been displayed to the user.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::CHAT_MARKERS, name = "displayed")]
+pub struct Displayed {
+    /// The 'id' attribute of the displayed message.
+    #[xml(attribute)]
+    pub id: String,
+}
+
+impl MessagePayload for Displayed {}
+
+/// Notes that a previous markable message has been acknowledged by the
+/// user, e.g. by replying to it.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::CHAT_MARKERS, name = "acknowledged")]
+pub struct Acknowledged {
+    /// The 'id' attribute of the acknowledged message.
+    #[xml(attribute)]
+    pub id: String,
+}
+
+impl MessagePayload for Acknowledged {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidom::Element;
+    use xso::error::{Error, FromElementError};
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Markable, 0);
+        assert_size!(Received, 12);
+        assert_size!(Displayed, 12);
+        assert_size!(Acknowledged, 12);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Markable, 0);
+        assert_size!(Received, 24);
+        assert_size!(Displayed, 24);
+        assert_size!(Acknowledged, 24);
+    }
+
+    #[test]
+    fn test_simple() {
+        let elem: Element = "<markable xmlns='urn:xmpp:chat-markers:0'/>"
+            .parse()
+            .unwrap();
+        Markable::try_from(elem).unwrap();
+
+        let elem: Element = "<received xmlns='urn:xmpp:chat-markers:0' id='coucou'/>"
+            .parse()
+            .unwrap();
+        let received = Received::try_from(elem).unwrap();
+        assert_eq!(received.id, "coucou");
+
+        let elem: Element = "<displayed xmlns='urn:xmpp:chat-markers:0' id='coucou'/>"
+            .parse()
+            .unwrap();
+        let displayed = Displayed::try_from(elem).unwrap();
+        assert_eq!(displayed.id, "coucou");
+
+        let elem: Element = "<acknowledged xmlns='urn:xmpp:chat-markers:0' id='coucou'/>"
+            .parse()
+            .unwrap();
+        let acknowledged = Acknowledged::try_from(elem).unwrap();
+        assert_eq!(acknowledged.id, "coucou");
+    }
+
+    #[test]
+    fn test_missing_id() {
+        let elem: Element = "<displayed xmlns='urn:xmpp:chat-markers:0'/>"
+            .parse()
+            .unwrap();
+        let error = Displayed::try_from(elem).unwrap_err();
+        let message = match error {
+            FromElementError::Invalid(Error::Other(string)) => string,
+            _ => panic!(),
+        };
+        assert_eq!(
+            message,
+            "Required attribute field 'id' on Displayed element missing."
+        );
+    }
+
+    #[test]
+    fn test_serialise() {
+        let elem: Element = Markable.into();
+        assert!(elem.is("markable", ns::CHAT_MARKERS));
+        assert_eq!(elem.attrs().count(), 0);
+
+        let displayed = Displayed {
+            id: String::from("coucou"),
+        };
+        let elem: Element = displayed.into();
+        assert!(elem.is("displayed", ns::CHAT_MARKERS));
+        assert_eq!(elem.attr("id"), Some("coucou"));
+    }
+}
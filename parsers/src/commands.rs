@@ -0,0 +1,304 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{AsXml, FromXml};
+
+use crate::data_forms::DataForm;
+use crate::iq::{IqResultPayload, IqSetPayload};
+use crate::ns;
+use minidom::Element;
+
+generate_attribute!(
+    /// What the requester wants to do with a command.
+    Action, "action", {
+        /// Start the command, or proceed with its default action.
+        Execute => "execute",
+
+        /// Cancel the command.
+        Cancel => "cancel",
+
+        /// Go back to the previous stage.
+        Prev => "prev",
+
+        /// Proceed to the next stage.
+        Next => "next",
+
+        /// Finish the command with the data provided so far.
+        Complete => "complete",
+    }
+);
+
+generate_attribute!(
+    /// The state of a command session.
+    Status, "status", {
+        /// The command is waiting for more input from the requester.
+        Executing => "executing",
+
+        /// The command has finished.
+        Completed => "completed",
+
+        /// The command has been cancelled.
+        Canceled => "canceled",
+    }
+);
+
+generate_attribute!(
+    /// How important a note is.
+    NoteType, "type", {
+        /// Informational note.
+        Info => "info",
+
+        /// The command succeeded, but the requester should be warned.
+        Warn => "warn",
+
+        /// The command failed.
+        Error => "error",
+    }, Default = Info
+);
+
+/// The actions the requester may take at the current stage, besides
+/// cancelling.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::COMMANDS, name = "actions")]
+pub struct Actions {
+    /// The action to use when the requester only executes the command.
+    #[xml(attribute(default))]
+    pub execute: Option<Action>,
+
+    /// Whether the requester may go back to the previous stage.
+    #[xml(flag)]
+    pub prev: bool,
+
+    /// Whether the requester may go on to the next stage.
+    #[xml(flag)]
+    pub next: bool,
+
+    /// Whether the requester may complete the command.
+    #[xml(flag)]
+    pub complete: bool,
+}
+
+/// A message from the responder about the command.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
+#[xml(namespace = ns::COMMANDS, name = "note")]
+pub struct Note {
+    /// How important this note is.
+    #[xml(attribute(default, name = "type"))]
+    pub type_: NoteType,
+
+    /// The text of this note.
+    #[xml(text)]
+    pub text: String,
+}
+
+impl Note {
+    /// Create a note of the given type.
+    pub fn new<S: Into<String>>(type_: NoteType, text: S) -> Note {
+        Note {
+            type_,
+            text: text.into(),
+        }
+    }
+}
+
+/// A request to execute a command or to proceed with it, or the response of
+/// the entity providing it.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
+#[xml(namespace = ns::COMMANDS, name = "command")]
+pub struct Command {
+    /// The node identifying the command.
+    #[xml(attribute)]
+    pub node: String,
+
+    /// The session, assigned by the responder on the first response.
+    #[xml(attribute(default))]
+    pub sessionid: Option<String>,
+
+    /// What the requester wants to do, in a request.
+    #[xml(attribute(default))]
+    pub action: Option<Action>,
+
+    /// The state of the session, in a response.
+    #[xml(attribute(default))]
+    pub status: Option<Status>,
+
+    /// The actions available at this stage, in a response.
+    #[xml(child(default))]
+    pub actions: Option<Actions>,
+
+    /// The notes attached to a response.
+    #[xml(child(n = ..))]
+    pub notes: Vec<Note>,
+
+    /// The form to fill, the form filled, or the result of the command.
+    #[xml(child(default))]
+    pub form: Option<DataForm>,
+
+    /// Any other payload.
+    #[xml(element(n = ..))]
+    pub payloads: Vec<Element>,
+}
+
+impl Command {
+    /// Request the execution of a command.
+    pub fn new<N: Into<String>>(node: N) -> Command {
+        Command {
+            node: node.into(),
+            sessionid: None,
+            action: None,
+            status: None,
+            actions: None,
+            notes: Vec::new(),
+            form: None,
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Create the response to a request in this session.
+    pub fn response<N: Into<String>, S: Into<String>>(
+        node: N,
+        sessionid: S,
+        status: Status,
+    ) -> Command {
+        Command {
+            sessionid: Some(sessionid.into()),
+            status: Some(status),
+            ..Command::new(node)
+        }
+    }
+
+    /// Set the session of this request.
+    pub fn with_sessionid<S: Into<String>>(mut self, sessionid: S) -> Command {
+        self.sessionid = Some(sessionid.into());
+        self
+    }
+
+    /// Set the action of this request.
+    pub fn with_action(mut self, action: Action) -> Command {
+        self.action = Some(action);
+        self
+    }
+
+    /// Set the actions available in this response.
+    pub fn with_actions(mut self, actions: Actions) -> Command {
+        self.actions = Some(actions);
+        self
+    }
+
+    /// Add a note to this response.
+    pub fn with_note(mut self, note: Note) -> Command {
+        self.notes.push(note);
+        self
+    }
+
+    /// Attach a form.
+    pub fn with_form(mut self, form: DataForm) -> Command {
+        self.form = Some(form);
+        self
+    }
+}
+
+impl IqSetPayload for Command {}
+impl IqResultPayload for Command {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_forms::DataFormType;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Action, 1);
+        assert_size!(Status, 1);
+        assert_size!(NoteType, 1);
+        assert_size!(Actions, 4);
+        assert_size!(Note, 16);
+        assert_size!(Command, 108);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Action, 1);
+        assert_size!(Status, 1);
+        assert_size!(NoteType, 1);
+        assert_size!(Actions, 4);
+        assert_size!(Note, 32);
+        assert_size!(Command, 208);
+    }
+
+    #[test]
+    fn test_execute() {
+        let elem: Element =
+            "<command xmlns='http://jabber.org/protocol/commands' node='list' action='execute'/>"
+                .parse()
+                .unwrap();
+        let command = Command::try_from(elem.clone()).unwrap();
+        assert_eq!(command.node, "list");
+        assert_eq!(command.action, Some(Action::Execute));
+        assert_eq!(command.sessionid, None);
+        assert_eq!(command.status, None);
+        assert_eq!(
+            Element::from(Command::new("list").with_action(Action::Execute)),
+            elem
+        );
+    }
+
+    #[test]
+    fn test_stage() {
+        let elem: Element = "<command xmlns='http://jabber.org/protocol/commands' sessionid='config:20020923T213616Z-700' node='config' status='executing'><actions execute='next'><next/></actions><x xmlns='jabber:x:data' type='form'><title>Configure Service</title><field var='service' label='Service' type='list-single'><option><value>httpd</value></option><option><value>jabberd</value></option></field></x></command>"
+            .parse()
+            .unwrap();
+        let command = Command::try_from(elem).unwrap();
+        assert_eq!(command.status, Some(Status::Executing));
+        assert_eq!(
+            command.sessionid.as_deref(),
+            Some("config:20020923T213616Z-700")
+        );
+        let actions = command.actions.unwrap();
+        assert_eq!(actions.execute, Some(Action::Next));
+        assert!(actions.next);
+        assert!(!actions.prev);
+        assert!(!actions.complete);
+        let form = command.form.unwrap();
+        assert_eq!(form.type_, DataFormType::Form);
+        assert_eq!(form.fields.len(), 1);
+        assert!(command.payloads.is_empty());
+    }
+
+    #[test]
+    fn test_completed() {
+        let elem: Element = "<command xmlns='http://jabber.org/protocol/commands' sessionid='config:20020923T213616Z-700' node='config' status='completed'><note type='info'>Service 'httpd' has been configured.</note><note>Done.</note></command>"
+            .parse()
+            .unwrap();
+        let command = Command::try_from(elem).unwrap();
+        assert_eq!(command.status, Some(Status::Completed));
+        assert_eq!(command.notes.len(), 2);
+        assert_eq!(command.notes[0].type_, NoteType::Info);
+        assert_eq!(
+            command.notes[0].text,
+            "Service 'httpd' has been configured."
+        );
+        assert_eq!(command.notes[1].type_, NoteType::Info);
+
+        let response =
+            Command::response("config", "config:20020923T213616Z-700", Status::Completed)
+                .with_note(Note::new(NoteType::Warn, "Careful."));
+        let elem: Element = response.into();
+        let note = elem.get_child("note", ns::COMMANDS).unwrap();
+        assert_eq!(note.attr("type"), Some("warn"));
+    }
+
+    #[test]
+    fn test_payload() {
+        let elem: Element = "<command xmlns='http://jabber.org/protocol/commands' node='x' status='completed' sessionid='s'><foo xmlns='urn:example'/></command>"
+            .parse()
+            .unwrap();
+        let command = Command::try_from(elem).unwrap();
+        assert_eq!(command.payloads.len(), 1);
+    }
+}
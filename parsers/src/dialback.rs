@@ -0,0 +1,255 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{AsXml, FromXml};
+
+use crate::ns;
+use jid::DomainPart;
+use minidom::Element;
+
+generate_attribute!(
+    /// The outcome of a dialback request.
+    Type, "type", {
+        /// The key is valid, the originating server is authorised to send
+        /// stanzas from its domain.
+        Valid => "valid",
+
+        /// The key is invalid.
+        Invalid => "invalid",
+
+        /// The request couldn’t be processed, e.g. because the domain isn’t
+        /// served here.
+        Error => "error",
+    }
+);
+
+/// Sent by the originating server to request authorisation to send stanzas
+/// from its domain, and by the receiving server to report the outcome.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
+#[xml(namespace = ns::DIALBACK, name = "result")]
+pub struct DialbackResult {
+    /// The domain of the originating server.
+    #[xml(attribute)]
+    pub from: DomainPart,
+
+    /// The domain of the receiving server.
+    #[xml(attribute)]
+    pub to: DomainPart,
+
+    /// The outcome, absent in the request.
+    #[xml(attribute(default, name = "type"))]
+    pub type_: Option<Type>,
+
+    /// The dialback key, empty in the outcome.
+    #[xml(text)]
+    pub key: String,
+
+    /// The details of the error, if the type is [`Type::Error`].
+    #[xml(element(n = ..))]
+    pub payloads: Vec<Element>,
+}
+
+impl DialbackResult {
+    /// Request authorisation to send stanzas from `from` to `to`, using the
+    /// given dialback key.
+    pub fn new(from: DomainPart, to: DomainPart, key: String) -> DialbackResult {
+        DialbackResult {
+            from,
+            to,
+            type_: None,
+            key,
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Report the outcome of a request, swapping the domains.
+    pub fn reply(&self, type_: Type) -> DialbackResult {
+        DialbackResult {
+            from: self.to.clone(),
+            to: self.from.clone(),
+            type_: Some(type_),
+            key: String::new(),
+            payloads: Vec::new(),
+        }
+    }
+}
+
+/// Sent by the receiving server to the authoritative server of the
+/// originating domain to check a dialback key, and by the authoritative
+/// server to report whether it is valid.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
+#[xml(namespace = ns::DIALBACK, name = "verify")]
+pub struct DialbackVerify {
+    /// The sender of this element.
+    #[xml(attribute)]
+    pub from: DomainPart,
+
+    /// The recipient of this element.
+    #[xml(attribute)]
+    pub to: DomainPart,
+
+    /// The id of the stream on which the key has been received.
+    #[xml(attribute)]
+    pub id: String,
+
+    /// The outcome, absent in the request.
+    #[xml(attribute(default, name = "type"))]
+    pub type_: Option<Type>,
+
+    /// The dialback key to check, empty in the outcome.
+    #[xml(text)]
+    pub key: String,
+
+    /// The details of the error, if the type is [`Type::Error`].
+    #[xml(element(n = ..))]
+    pub payloads: Vec<Element>,
+}
+
+impl DialbackVerify {
+    /// Ask whether `key`, received on the stream `id`, is valid.
+    pub fn new(from: DomainPart, to: DomainPart, id: String, key: String) -> DialbackVerify {
+        DialbackVerify {
+            from,
+            to,
+            id,
+            type_: None,
+            key,
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Report whether the key is valid, swapping the domains.
+    pub fn reply(&self, type_: Type) -> DialbackVerify {
+        DialbackVerify {
+            from: self.to.clone(),
+            to: self.from.clone(),
+            id: self.id.clone(),
+            type_: Some(type_),
+            key: String::new(),
+            payloads: Vec::new(),
+        }
+    }
+}
+
+/// Stream feature advertising support for dialback.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::DIALBACK_FEATURE, name = "dialback")]
+pub struct DialbackFeature {
+    /// Whether dialback errors are supported.
+    #[xml(flag(name = "errors"))]
+    pub errors: bool,
+}
+
+/// Enum which allows parsing/serialising any dialback element.
+#[derive(FromXml, AsXml, Debug, Clone)]
+#[xml()]
+pub enum Nonza {
+    /// Request or outcome of an authorisation
+    #[xml(transparent)]
+    Result(DialbackResult),
+
+    /// Request or outcome of a key verification
+    #[xml(transparent)]
+    Verify(DialbackVerify),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Type, 1);
+        assert_size!(DialbackResult, 52);
+        assert_size!(DialbackVerify, 64);
+        assert_size!(DialbackFeature, 1);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Type, 1);
+        assert_size!(DialbackResult, 104);
+        assert_size!(DialbackVerify, 128);
+        assert_size!(DialbackFeature, 1);
+    }
+
+    #[test]
+    fn test_result() {
+        let elem: Element = "<result xmlns='jabber:server:dialback' from='example.com' to='example.net'>b4835385f37fe2895af6c196b59097b16862406db80559900d96bf6fa7d23df3</result>"
+            .parse()
+            .unwrap();
+        let result = DialbackResult::try_from(elem).unwrap();
+        assert_eq!(result.from.as_str(), "example.com");
+        assert_eq!(result.to.as_str(), "example.net");
+        assert_eq!(result.type_, None);
+        assert_eq!(
+            result.key,
+            "b4835385f37fe2895af6c196b59097b16862406db80559900d96bf6fa7d23df3"
+        );
+
+        let reply = result.reply(Type::Valid);
+        let elem: Element =
+            "<result xmlns='jabber:server:dialback' from='example.net' to='example.com' type='valid'/>"
+                .parse()
+                .unwrap();
+        assert_eq!(DialbackResult::try_from(elem).unwrap(), reply);
+    }
+
+    #[test]
+    fn test_verify() {
+        let elem: Element = "<verify xmlns='jabber:server:dialback' from='example.net' to='example.com' id='D60000229F'>b4835385f37fe2895af6c196b59097b16862406db80559900d96bf6fa7d23df3</verify>"
+            .parse()
+            .unwrap();
+        let verify = DialbackVerify::try_from(elem).unwrap();
+        assert_eq!(verify.id, "D60000229F");
+        assert_eq!(verify.type_, None);
+
+        let reply = verify.reply(Type::Invalid);
+        assert_eq!(reply.from.as_str(), "example.com");
+        assert_eq!(reply.to.as_str(), "example.net");
+        let elem: Element = reply.into();
+        assert_eq!(elem.attr("type"), Some("invalid"));
+        assert_eq!(elem.attr("id"), Some("D60000229F"));
+        assert_eq!(elem.text(), "");
+    }
+
+    #[test]
+    fn test_error() {
+        let elem: Element = "<result xmlns='jabber:server:dialback' from='example.net' to='example.com' type='error'><error xmlns='jabber:server' type='cancel'><item-not-found xmlns='urn:ietf:params:xml:ns:xmpp-stanzas'/></error></result>"
+            .parse()
+            .unwrap();
+        let result = DialbackResult::try_from(elem).unwrap();
+        assert_eq!(result.type_, Some(Type::Error));
+        assert_eq!(result.payloads.len(), 1);
+    }
+
+    #[test]
+    fn test_nonza() {
+        let elem: Element =
+            "<verify xmlns='jabber:server:dialback' from='a.example' to='b.example' id='x'>k</verify>"
+                .parse()
+                .unwrap();
+        match Nonza::try_from(elem).unwrap() {
+            Nonza::Verify(verify) => assert_eq!(verify.key, "k"),
+            other => panic!("unexpected nonza: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_feature() {
+        let elem: Element = "<dialback xmlns='urn:xmpp:features:dialback'><errors/></dialback>"
+            .parse()
+            .unwrap();
+        let feature = DialbackFeature::try_from(elem).unwrap();
+        assert!(feature.errors);
+
+        let elem: Element = DialbackFeature::default().into();
+        assert!(elem.is("dialback", ns::DIALBACK_FEATURE));
+        assert!(!elem.has_child("errors", ns::DIALBACK_FEATURE));
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{text::EmptyAsNone, AsXml, FromXml};
+
+use crate::iq::{IqGetPayload, IqResultPayload};
+use crate::ns;
+
+/// Represents a query for the last activity of an entity: the idle time of
+/// a client, the uptime of a server, or the time since an account was last
+/// logged out.
+///
+/// It should only be used in an `<iq type='get'/>`, as it can only
+/// represent the request, and not a result.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::LAST_ACTIVITY, name = "query")]
+pub struct LastActivityQuery;
+
+impl IqGetPayload for LastActivityQuery {}
+
+/// Represents the answer about the last activity of an entity.
+///
+/// It should only be used in an `<iq type='result'/>`, as it can only
+/// represent the result, and not a request.
+#[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
+#[xml(namespace = ns::LAST_ACTIVITY, name = "query")]
+pub struct LastActivityResult {
+    /// The number of seconds since the last activity.
+    #[xml(attribute)]
+    pub seconds: u64,
+
+    /// The last status message of an offline account.
+    #[xml(text(codec = EmptyAsNone))]
+    pub status: Option<String>,
+}
+
+impl IqResultPayload for LastActivityResult {}
+
+impl LastActivityResult {
+    /// Create a result without any status message.
+    pub fn new(seconds: u64) -> LastActivityResult {
+        LastActivityResult {
+            seconds,
+            status: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidom::Element;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(LastActivityQuery, 0);
+        assert_size!(LastActivityResult, 20);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(LastActivityQuery, 0);
+        assert_size!(LastActivityResult, 32);
+    }
+
+    #[test]
+    fn test_query() {
+        let elem: Element = "<query xmlns='jabber:iq:last'/>".parse().unwrap();
+        LastActivityQuery::try_from(elem).unwrap();
+    }
+
+    #[test]
+    fn test_result() {
+        let elem: Element = "<query xmlns='jabber:iq:last' seconds='903'/>"
+            .parse()
+            .unwrap();
+        let last = LastActivityResult::try_from(elem).unwrap();
+        assert_eq!(last.seconds, 903);
+        assert_eq!(last.status, None);
+    }
+
+    #[test]
+    fn test_status() {
+        let elem: Element = "<query xmlns='jabber:iq:last' seconds='903'>Heading Home</query>"
+            .parse()
+            .unwrap();
+        let last = LastActivityResult::try_from(elem).unwrap();
+        assert_eq!(last.status.as_deref(), Some("Heading Home"));
+    }
+
+    #[test]
+    fn test_serialise() {
+        let elem: Element = LastActivityResult::new(0).into();
+        let expected: Element = "<query xmlns='jabber:iq:last' seconds='0'/>"
+            .parse()
+            .unwrap();
+        assert_eq!(elem, expected);
+    }
+}
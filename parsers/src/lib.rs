@@ -74,6 +74,9 @@ pub mod websocket;
 /// XEP-0004: Data Forms
 pub mod data_forms;
 
+/// XEP-0012: Last Activity
+pub mod last_activity;
+
 /// XEP-0030: Service Discovery
 pub mod disco;
 
@@ -89,6 +92,9 @@ pub mod bookmarks;
 /// XEP-0049: Private XML storage
 pub mod private;
 
+/// XEP-0050: Ad-Hoc Commands
+pub mod commands;
+
 /// XEP-0054: vcard-temp
 pub mod vcard;
 
@@ -134,6 +140,9 @@ pub mod tune;
 /// XEP-0122: Data Forms Validation
 pub mod data_forms_validate;
 
+/// XEP-0124: Bidirectional-streams Over Synchronous HTTP (BOSH)
+pub mod bosh;
+
 ///XEP-0153: vCard-Based Avatars
 pub mod vcard_update;
 
@@ -176,6 +185,9 @@ pub mod delay;
 /// XEP-0215: External Service Discovery
 pub mod extdisco;
 
+/// XEP-0220: Server Dialback
+pub mod dialback;
+
 /// XEP-0221: Data Forms Media Element
 pub mod media_element;
 
@@ -233,6 +245,9 @@ pub mod jingle_dtls_srtp;
 /// XEP-0328: JID Prep
 pub mod jid_prep;
 
+/// XEP-0333: Displayed Markers
+pub mod chat_markers;
+
 /// XEP-0338: Jingle Grouping Framework
 pub mod jingle_grouping;
 
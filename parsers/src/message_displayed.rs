@@ -7,6 +7,7 @@
 use xso::{AsXml, FromXml};
 
 use crate::ns;
+use crate::pubsub::PubSubPayload;
 use crate::stanza_id::StanzaId;
 
 /// Mention that a particular message has been displayed by at least one client.
@@ -17,3 +18,5 @@ pub struct Displayed {
     #[xml(child)]
     pub stanza_id: StanzaId,
 }
+
+impl PubSubPayload for Displayed {}
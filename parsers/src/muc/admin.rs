@@ -0,0 +1,177 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{text::EmptyAsNone, AsXml, FromXml};
+
+use crate::iq::{IqGetPayload, IqResultPayload, IqSetPayload};
+use crate::muc::user::{Affiliation, Role};
+use crate::ns;
+use jid::Jid;
+
+/// An item in an administrative request, either changing the role of an
+/// occupant by nickname or the affiliation of a user by JID, or listing the
+/// users having a given affiliation or role.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::MUC_ADMIN, name = "item")]
+pub struct Item {
+    /// The affiliation to set or to list.
+    // The codec makes sure "none" is serialised, as it is meaningful here.
+    #[xml(attribute(default, codec = EmptyAsNone))]
+    pub affiliation: Option<Affiliation>,
+
+    /// The role to set or to list.
+    #[xml(attribute(default, codec = EmptyAsNone))]
+    pub role: Option<Role>,
+
+    /// The JID of the affected user.
+    #[xml(attribute(default))]
+    pub jid: Option<Jid>,
+
+    /// The nickname of the affected occupant.
+    #[xml(attribute(default))]
+    pub nick: Option<String>,
+
+    /// Why this change has been made.
+    #[xml(extract(default, fields(text(type_ = String))))]
+    pub reason: Option<String>,
+}
+
+impl Item {
+    /// Change the role of the occupant using this nickname.
+    pub fn with_role<N: Into<String>>(nick: N, role: Role) -> Item {
+        Item {
+            role: Some(role),
+            nick: Some(nick.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Change the affiliation of this user.
+    pub fn with_affiliation<J: Into<Jid>>(jid: J, affiliation: Affiliation) -> Item {
+        Item {
+            affiliation: Some(affiliation),
+            jid: Some(jid.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Set a reason for this change.
+    pub fn with_reason<S: Into<String>>(mut self, reason: S) -> Item {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// An administrative request or its result, sent to a room.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::MUC_ADMIN, name = "query")]
+pub struct AdminQuery {
+    /// The changes to make, the lists to request, or the requested list.
+    #[xml(child(n = ..))]
+    pub items: Vec<Item>,
+}
+
+impl AdminQuery {
+    /// Request the list of users with this affiliation.
+    pub fn list_affiliation(affiliation: Affiliation) -> AdminQuery {
+        AdminQuery {
+            items: vec![Item {
+                affiliation: Some(affiliation),
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Request the list of occupants with this role.
+    pub fn list_role(role: Role) -> AdminQuery {
+        AdminQuery {
+            items: vec![Item {
+                role: Some(role),
+                ..Default::default()
+            }],
+        }
+    }
+}
+
+impl IqGetPayload for AdminQuery {}
+impl IqSetPayload for AdminQuery {}
+impl IqResultPayload for AdminQuery {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidom::Element;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Item, 44);
+        assert_size!(AdminQuery, 12);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Item, 88);
+        assert_size!(AdminQuery, 24);
+    }
+
+    #[test]
+    fn test_kick() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#admin'><item nick='pistol' role='none'><reason>Avaunt, you cullion!</reason></item></query>"
+            .parse()
+            .unwrap();
+        let query = AdminQuery::try_from(elem.clone()).unwrap();
+        assert_eq!(query.items.len(), 1);
+        assert_eq!(query.items[0].role, Some(Role::None));
+        assert_eq!(query.items[0].affiliation, None);
+        assert_eq!(query.items[0].nick.as_deref(), Some("pistol"));
+        assert_eq!(
+            query.items[0].reason.as_deref(),
+            Some("Avaunt, you cullion!")
+        );
+
+        let query = AdminQuery {
+            items: vec![Item::with_role("pistol", Role::None).with_reason("Avaunt, you cullion!")],
+        };
+        assert_eq!(Element::from(query), elem);
+    }
+
+    #[test]
+    fn test_ban() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#admin'><item affiliation='outcast' jid='earlofcambridge@shakespeare.lit'/></query>"
+            .parse()
+            .unwrap();
+        let jid = Jid::new("earlofcambridge@shakespeare.lit").unwrap();
+        let query = AdminQuery {
+            items: vec![Item::with_affiliation(jid, Affiliation::Outcast)],
+        };
+        assert_eq!(Element::from(query), elem);
+    }
+
+    #[test]
+    fn test_list() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#admin'><item affiliation='member'/></query>"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            AdminQuery::try_from(elem).unwrap(),
+            AdminQuery::list_affiliation(Affiliation::Member)
+        );
+
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#admin'><item affiliation='member' jid='hag66@shakespeare.lit' nick='thirdwitch' role='participant'/><item affiliation='member' jid='wiccarocks@shakespeare.lit'/></query>"
+            .parse()
+            .unwrap();
+        let query = AdminQuery::try_from(elem).unwrap();
+        assert_eq!(query.items.len(), 2);
+        assert_eq!(query.items[0].nick.as_deref(), Some("thirdwitch"));
+        assert_eq!(query.items[0].role, Some(Role::Participant));
+        assert_eq!(
+            query.items[1].jid,
+            Some(Jid::new("wiccarocks@shakespeare.lit").unwrap())
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xso::{AsXml, FromXml};
+
+use crate::data_forms::DataForm;
+use crate::iq::{IqGetPayload, IqResultPayload, IqSetPayload};
+use crate::ns;
+use jid::BareJid;
+
+/// Request to destroy a room, sent by one of its owners.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::MUC_OWNER, name = "destroy")]
+pub struct Destroy {
+    /// An alternate room the occupants should join instead.
+    #[xml(attribute(default))]
+    pub jid: Option<BareJid>,
+
+    /// The password of the alternate room.
+    #[xml(extract(default, fields(text(type_ = String))))]
+    pub password: Option<String>,
+
+    /// Why this room is being destroyed.
+    #[xml(extract(default, fields(text(type_ = String))))]
+    pub reason: Option<String>,
+}
+
+/// An owner request or its result, sent to a room.
+///
+/// When empty, this requests the configuration form of the room.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone, Default)]
+#[xml(namespace = ns::MUC_OWNER, name = "query")]
+pub struct OwnerQuery {
+    /// The configuration form, as received from the room, or to be
+    /// submitted to it.
+    #[xml(child(default))]
+    pub form: Option<DataForm>,
+
+    /// Destroy the room.
+    #[xml(child(default))]
+    pub destroy: Option<Destroy>,
+}
+
+impl OwnerQuery {
+    /// Submit or cancel a configuration form.
+    pub fn config(form: DataForm) -> OwnerQuery {
+        OwnerQuery {
+            form: Some(form),
+            destroy: None,
+        }
+    }
+
+    /// Destroy a room.
+    pub fn destroy(destroy: Destroy) -> OwnerQuery {
+        OwnerQuery {
+            form: None,
+            destroy: Some(destroy),
+        }
+    }
+}
+
+impl IqGetPayload for OwnerQuery {}
+impl IqSetPayload for OwnerQuery {}
+impl IqResultPayload for OwnerQuery {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_forms::DataFormType;
+    use minidom::Element;
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn test_size() {
+        assert_size!(Destroy, 40);
+        assert_size!(OwnerQuery, 92);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_size() {
+        assert_size!(Destroy, 80);
+        assert_size!(OwnerQuery, 184);
+    }
+
+    #[test]
+    fn test_destroy() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#owner'><destroy jid='coven@chat.shakespeare.lit'><password>cauldronburn</password><reason>Macbeth doth come.</reason></destroy></query>"
+            .parse()
+            .unwrap();
+        let query = OwnerQuery::try_from(elem.clone()).unwrap();
+        let destroy = query.destroy.clone().unwrap();
+        assert_eq!(
+            destroy.jid,
+            Some(BareJid::new("coven@chat.shakespeare.lit").unwrap())
+        );
+        assert_eq!(destroy.password.as_deref(), Some("cauldronburn"));
+        assert_eq!(destroy.reason.as_deref(), Some("Macbeth doth come."));
+        assert_eq!(Element::from(query), elem);
+    }
+
+    #[test]
+    fn test_empty() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#owner'/>"
+            .parse()
+            .unwrap();
+        let query = OwnerQuery::try_from(elem).unwrap();
+        assert_eq!(query.form, None);
+        assert_eq!(query.destroy, None);
+    }
+
+    #[test]
+    fn test_config() {
+        let elem: Element = "<query xmlns='http://jabber.org/protocol/muc#owner'><x xmlns='jabber:x:data' type='form'><title>Configuration for coven Room</title><field type='hidden' var='FORM_TYPE'><value>http://jabber.org/protocol/muc#roomconfig</value></field><field label='Make Room Persistent?' type='boolean' var='muc#roomconfig_persistentroom'><value>0</value></field></x></query>"
+            .parse()
+            .unwrap();
+        let query = OwnerQuery::try_from(elem).unwrap();
+        let form = query.form.unwrap();
+        assert_eq!(form.type_, DataFormType::Form);
+        assert_eq!(form.form_type.as_deref(), Some(ns::MUC_ROOMCONFIG));
+        assert_eq!(form.fields.len(), 1);
+        assert_eq!(
+            form.fields[0].var.as_deref(),
+            Some("muc#roomconfig_persistentroom")
+        );
+
+        let cancel = DataForm {
+            type_: DataFormType::Cancel,
+            form_type: None,
+            title: None,
+            instructions: None,
+            fields: vec![],
+        };
+        let elem: Element = OwnerQuery::config(cancel).into();
+        let reference: Element = "<query xmlns='http://jabber.org/protocol/muc#owner'><x xmlns='jabber:x:data' type='cancel'/></query>"
+            .parse()
+            .unwrap();
+        assert_eq!(elem, reference);
+    }
+}
@@ -11,7 +11,7 @@ use crate::message::MessagePayload;
 use crate::ns;
 use crate::presence::PresencePayload;
 
-use jid::{FullJid, Jid};
+use jid::{BareJid, FullJid, Jid};
 
 generate_attribute_enum!(
 /// Lists all of the possible status codes used in MUC presences.
@@ -283,6 +283,19 @@ pub struct Decline {
     pub reason: Option<String>,
 }
 
+/// Notification that a room has been destroyed, sent to its occupants.
+#[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
+#[xml(namespace = ns::MUC_USER, name = "destroy")]
+pub struct Destroy {
+    /// An alternate room the occupants should join instead.
+    #[xml(attribute(default))]
+    pub jid: Option<BareJid>,
+
+    /// Why this room has been destroyed.
+    #[xml(extract(default, fields(text(type_ = String))))]
+    pub reason: Option<String>,
+}
+
 /// The main muc#user element.
 #[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
 #[xml(namespace = ns::MUC_USER, name = "x")]
@@ -302,6 +315,10 @@ pub struct MucUser {
     /// A mediated invite rejection
     #[xml(child(default))]
     pub decline: Option<Decline>,
+
+    /// The room has been destroyed
+    #[xml(child(default))]
+    pub destroy: Option<Destroy>,
 }
 
 impl Default for MucUser {
@@ -318,6 +335,7 @@ impl MucUser {
             items: vec![],
             invite: None,
             decline: None,
+            destroy: None,
         }
     }
 
@@ -398,6 +416,7 @@ mod tests {
             items: vec![],
             invite: None,
             decline: None,
+            destroy: None,
         };
         let elem2 = muc.into();
         assert_eq!(elem, elem2);
@@ -770,6 +789,25 @@ mod tests {
         assert_eq!(serialized, reference);
     }
 
+    #[test]
+    fn test_destroy() {
+        let elem: Element = "<x xmlns='http://jabber.org/protocol/muc#user'>
+                <item affiliation='none' role='none'/>
+                <destroy jid='coven@chat.shakespeare.lit'>
+                    <reason>Macbeth doth come.</reason>
+                </destroy>
+            </x>"
+            .parse()
+            .unwrap();
+        let muc_user = MucUser::try_from(elem).unwrap();
+        let destroy = muc_user.destroy.unwrap();
+        assert_eq!(
+            destroy.jid,
+            Some(BareJid::new("coven@chat.shakespeare.lit").unwrap())
+        );
+        assert_eq!(destroy.reason.as_deref(), Some("Macbeth doth come."));
+    }
+
     #[test]
     fn presence_payload() {
         let elem: Element = "<x xmlns='http://jabber.org/protocol/muc#user'/>"
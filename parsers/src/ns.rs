@@ -17,16 +17,24 @@ pub const TLS: &str = "urn:ietf:params:xml:ns:xmpp-tls";
 pub const SASL: &str = "urn:ietf:params:xml:ns:xmpp-sasl";
 /// RFC 6120: Extensible Messaging and Presence Protocol (XMPP): Core
 pub const BIND: &str = "urn:ietf:params:xml:ns:xmpp-bind";
+/// RFC 6120: Extensible Messaging and Presence Protocol (XMPP): Core
+pub const JABBER_SERVER: &str = "jabber:server";
 
 /// RFC 6121: Extensible Messaging and Presence Protocol (XMPP): Instant Messaging and Presence
 pub const ROSTER: &str = "jabber:iq:roster";
 
+/// RFC 6121: Extensible Messaging and Presence Protocol (XMPP): Instant Messaging and Presence
+pub const ROSTER_VER: &str = "urn:xmpp:features:rosterver";
+
 /// RFC 7395: An Extensible Messaging and Presence Protocol (XMPP) Subprotocol for WebSocket
 pub const WEBSOCKET: &str = "urn:ietf:params:xml:ns:xmpp-framing";
 
 /// XEP-0004: Data Forms
 pub const DATA_FORMS: &str = "jabber:x:data";
 
+/// XEP-0012: Last Activity
+pub const LAST_ACTIVITY: &str = "jabber:iq:last";
+
 /// XEP-0030: Service Discovery
 pub const DISCO_INFO: &str = "http://jabber.org/protocol/disco#info";
 /// XEP-0030: Service Discovery
@@ -36,6 +44,12 @@ pub const DISCO_ITEMS: &str = "http://jabber.org/protocol/disco#items";
 pub const MUC: &str = "http://jabber.org/protocol/muc";
 /// XEP-0045: Multi-User Chat
 pub const MUC_USER: &str = "http://jabber.org/protocol/muc#user";
+/// XEP-0045: Multi-User Chat
+pub const MUC_ADMIN: &str = "http://jabber.org/protocol/muc#admin";
+/// XEP-0045: Multi-User Chat
+pub const MUC_OWNER: &str = "http://jabber.org/protocol/muc#owner";
+/// XEP-0045: Multi-User Chat
+pub const MUC_ROOMCONFIG: &str = "http://jabber.org/protocol/muc#roomconfig";
 
 /// XEP-0047: In-Band Bytestreams
 pub const IBB: &str = "http://jabber.org/protocol/ibb";
@@ -46,6 +60,9 @@ pub const BOOKMARKS: &str = "storage:bookmarks";
 /// XEP-0049: Private XML Storage
 pub const PRIVATE: &str = "jabber:iq:private";
 
+/// XEP-0050: Ad-Hoc Commands
+pub const COMMANDS: &str = "http://jabber.org/protocol/commands";
+
 /// XEP-0054: vcard-temp
 pub const VCARD: &str = "vcard-temp";
 
@@ -62,6 +79,8 @@ pub const PUBSUB_EVENT: &str = "http://jabber.org/protocol/pubsub#event";
 pub const PUBSUB_OWNER: &str = "http://jabber.org/protocol/pubsub#owner";
 /// XEP-0060: Publish-Subscribe node configuration
 pub const PUBSUB_CONFIGURE: &str = "http://jabber.org/protocol/pubsub#node_config";
+/// XEP-0060: Publish-Subscribe publish options
+pub const PUBSUB_PUBLISH_OPTIONS: &str = "http://jabber.org/protocol/pubsub#publish-options";
 
 /// XEP-0066: Out of Band Data
 pub const OOB: &str = "jabber:x:oob";
@@ -103,6 +122,9 @@ pub const TUNE: &str = "http://jabber.org/protocol/tune";
 /// XEP-0122: Data Forms Validation
 pub const XDATA_VALIDATE: &str = "http://jabber.org/protocol/xdata-validate";
 
+/// XEP-0124: Bidirectional-streams Over Synchronous HTTP (BOSH)
+pub const BOSH: &str = "http://jabber.org/protocol/httpbind";
+
 /// XEP-0153: vCard-Based Avatars
 pub const VCARD_UPDATE: &str = "vcard-temp:x:update";
 
@@ -148,9 +170,17 @@ pub const TIME: &str = "urn:xmpp:time";
 /// XEP-0203: Delayed Delivery
 pub const DELAY: &str = "urn:xmpp:delay";
 
+/// XEP-0206: XMPP Over BOSH
+pub const XBOSH: &str = "urn:xmpp:xbosh";
+
 /// XEP-0215: External Service Discovery
 pub const EXT_DISCO: &str = "urn:xmpp:extdisco:2";
 
+/// XEP-0220: Server Dialback
+pub const DIALBACK: &str = "jabber:server:dialback";
+/// XEP-0220: Server Dialback
+pub const DIALBACK_FEATURE: &str = "urn:xmpp:features:dialback";
+
 /// XEP-0221: Data Forms Media Element
 pub const MEDIA_ELEMENT: &str = "urn:xmpp:media-element";
 
@@ -225,6 +255,9 @@ pub const JINGLE_DTLS: &str = "urn:xmpp:jingle:apps:dtls:0";
 /// XEP-0328: JID Prep
 pub const JID_PREP: &str = "urn:xmpp:jidprep:0";
 
+/// XEP-0333: Displayed Markers
+pub const CHAT_MARKERS: &str = "urn:xmpp:chat-markers:0";
+
 /// XEP-0338: Jingle Grouping Framework
 pub const JINGLE_GROUPING: &str = "urn:xmpp:jingle:apps:grouping:0";
 
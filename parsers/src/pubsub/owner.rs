@@ -32,13 +32,13 @@ pub struct Affiliations {
 #[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
 #[xml(namespace = ns::PUBSUB_OWNER, name = "affiliation")]
 pub struct Affiliation {
-    /// The node this affiliation pertains to.
+    /// The entity this affiliation pertains to.
     #[xml(attribute)]
-    jid: Jid,
+    pub jid: Jid,
 
-    /// The affiliation you currently have on this node.
+    /// The affiliation this entity currently has on this node.
     #[xml(attribute)]
-    affiliation: AffiliationAttribute,
+    pub affiliation: AffiliationAttribute,
 }
 
 /// Request to configure a node.
@@ -156,18 +156,27 @@ impl TryFrom<Element> for PubSubOwner {
 
         let mut payload = None;
         for child in elem.children() {
-            if child.is("configure", ns::PUBSUB_OWNER) {
-                if payload.is_some() {
-                    return Err(Error::Other(
-                        "Payload is already defined in pubsub owner element.",
-                    )
-                    .into());
-                }
-                let configure = Configure::try_from(child.clone())?;
-                payload = Some(PubSubOwner::Configure(configure));
+            if payload.is_some() {
+                return Err(
+                    Error::Other("Payload is already defined in pubsub owner element.").into(),
+                );
+            }
+            let child = child.clone();
+            payload = Some(if child.is("affiliations", ns::PUBSUB_OWNER) {
+                PubSubOwner::Affiliations(Affiliations::try_from(child)?)
+            } else if child.is("configure", ns::PUBSUB_OWNER) {
+                PubSubOwner::Configure(Configure::try_from(child)?)
+            } else if child.is("default", ns::PUBSUB_OWNER) {
+                PubSubOwner::Default(Default::try_from(child)?)
+            } else if child.is("delete", ns::PUBSUB_OWNER) {
+                PubSubOwner::Delete(Delete::try_from(child)?)
+            } else if child.is("purge", ns::PUBSUB_OWNER) {
+                PubSubOwner::Purge(Purge::try_from(child)?)
+            } else if child.is("subscriptions", ns::PUBSUB_OWNER) {
+                PubSubOwner::Subscriptions(Subscriptions::try_from(child)?)
             } else {
                 return Err(Error::Other("Unknown child in pubsub element.").into());
-            }
+            });
         }
         payload.ok_or(Error::Other("No payload in pubsub element.").into())
     }
@@ -220,6 +229,50 @@ mod tests {
         assert_eq!(elem1, elem2);
     }
 
+    #[test]
+    fn parse_affiliations() {
+        let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub#owner'><affiliations node='foo'><affiliation jid='hamlet@denmark.lit' affiliation='owner'/></affiliations></pubsub>"
+        .parse()
+        .unwrap();
+        let elem1 = elem.clone();
+        let pubsub = PubSubOwner::try_from(elem).unwrap();
+        match pubsub.clone() {
+            PubSubOwner::Affiliations(Affiliations { node, affiliations }) => {
+                assert_eq!(node.0, "foo");
+                assert_eq!(affiliations.len(), 1);
+                assert_eq!(
+                    affiliations[0].jid,
+                    Jid::from(BareJid::from_str("hamlet@denmark.lit").unwrap())
+                );
+                assert_eq!(affiliations[0].affiliation, AffiliationAttribute::Owner);
+            }
+            _ => panic!(),
+        }
+
+        let elem2 = Element::from(pubsub);
+        assert_eq!(elem1, elem2);
+    }
+
+    #[test]
+    fn parse_delete() {
+        let elem: Element =
+            "<pubsub xmlns='http://jabber.org/protocol/pubsub#owner'><delete node='foo'/></pubsub>"
+                .parse()
+                .unwrap();
+        match PubSubOwner::try_from(elem).unwrap() {
+            PubSubOwner::Delete(Delete { node, redirect }) => {
+                assert_eq!(node.0, "foo");
+                assert!(redirect.is_none());
+            }
+            _ => panic!(),
+        }
+
+        let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub#owner'><delete node='foo'/><purge node='foo'/></pubsub>"
+        .parse()
+        .unwrap();
+        PubSubOwner::try_from(elem).unwrap_err();
+    }
+
     #[test]
     fn configure() {
         let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub#owner'><configure node='foo'><x xmlns='jabber:x:data' type='submit'><field var='FORM_TYPE' type='hidden'><value>http://jabber.org/protocol/pubsub#node_config</value></field><field var='pubsub#access_model' type='list-single'><value>whitelist</value></field></x></configure></pubsub>"
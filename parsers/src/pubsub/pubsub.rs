@@ -15,6 +15,7 @@ use crate::ns;
 use crate::pubsub::{
     AffiliationAttribute, ItemId, NodeName, PubSubPayload, Subscription, SubscriptionId,
 };
+use crate::rsm::{SetQuery, SetResult};
 use jid::Jid;
 use minidom::Element;
 
@@ -208,7 +209,7 @@ pub struct Retract {
 pub struct SubscribeOptions {
     /// If `true`, the configuration is actually required.
     #[xml(flag)]
-    required: bool,
+    pub required: bool,
 }
 
 /// A request to subscribe a JID to a node.
@@ -243,23 +244,23 @@ pub struct Subscriptions {
 pub struct SubscriptionElem {
     /// The JID affected by this subscription.
     #[xml(attribute)]
-    jid: Jid,
+    pub jid: Jid,
 
     /// The node affected by this subscription.
     #[xml(attribute(default))]
-    node: Option<NodeName>,
+    pub node: Option<NodeName>,
 
     /// The subscription identifier for this subscription.
     #[xml(attribute(default))]
-    subid: Option<SubscriptionId>,
+    pub subid: Option<SubscriptionId>,
 
     /// The state of the subscription.
     #[xml(attribute(default))]
-    subscription: Option<Subscription>,
+    pub subscription: Option<Subscription>,
 
     /// The options related to this subscription.
     #[xml(child(default))]
-    subscribe_options: Option<SubscribeOptions>,
+    pub subscribe_options: Option<SubscribeOptions>,
 }
 
 /// An unsubscribe request.
@@ -268,15 +269,15 @@ pub struct SubscriptionElem {
 pub struct Unsubscribe {
     /// The JID affected by this request.
     #[xml(attribute)]
-    jid: Jid,
+    pub jid: Jid,
 
     /// The node affected by this request.
     #[xml(attribute)]
-    node: Option<NodeName>,
+    pub node: Option<NodeName>,
 
     /// The subscription identifier for this subscription.
     #[xml(attribute)]
-    subid: Option<SubscriptionId>,
+    pub subid: Option<SubscriptionId>,
 }
 
 /// Main payload used to communicate with a PubSub service.
@@ -317,8 +318,18 @@ pub enum PubSub {
     /// Request for a default node configuration.
     Default(Default),
 
-    /// A request for a list of items.
-    Items(Items),
+    /// A request for a list of items, or the items returned.
+    Items {
+        /// The items request.
+        items: Items,
+
+        /// The page requested using Result Set Management (XEP-0059).
+        set_query: Option<SetQuery>,
+
+        /// Which page has been returned, using Result Set Management
+        /// (XEP-0059).
+        set_result: Option<SetResult>,
+    },
 
     /// A request to retract some items from a node.
     Retract(Retract),
@@ -459,7 +470,33 @@ impl TryFrom<Element> for PubSub {
                     );
                 }
                 let items = Items::try_from(child.clone())?;
-                payload = Some(PubSub::Items(items));
+                payload = Some(PubSub::Items {
+                    items,
+                    set_query: None,
+                    set_result: None,
+                });
+            } else if child.is("set", ns::RSM) {
+                if let Some(PubSub::Items {
+                    items,
+                    set_query: None,
+                    set_result: None,
+                }) = payload
+                {
+                    // Both share the same element, only their children differ.
+                    let (set_query, set_result) = match SetQuery::try_from(child.clone()) {
+                        Ok(set_query) => (Some(set_query), None),
+                        Err(_) => (None, Some(SetResult::try_from(child.clone())?)),
+                    };
+                    payload = Some(PubSub::Items {
+                        items,
+                        set_query,
+                        set_result,
+                    });
+                } else {
+                    return Err(
+                        Error::Other("Set is only allowed after items in pubsub element.").into(),
+                    );
+                }
             } else if child.is("retract", ns::PUBSUB) {
                 if payload.is_some() {
                     return Err(
@@ -533,7 +570,20 @@ impl From<PubSub> for Element {
                 }
                 PubSub::Affiliations(affiliations) => vec![Element::from(affiliations)],
                 PubSub::Default(default) => vec![Element::from(default)],
-                PubSub::Items(items) => vec![Element::from(items)],
+                PubSub::Items {
+                    items,
+                    set_query,
+                    set_result,
+                } => {
+                    let mut elems = vec![Element::from(items)];
+                    if let Some(set_query) = set_query {
+                        elems.push(Element::from(set_query));
+                    }
+                    if let Some(set_result) = set_result {
+                        elems.push(Element::from(set_result));
+                    }
+                    elems
+                }
                 PubSub::Retract(retract) => vec![Element::from(retract)],
                 PubSub::Subscription(subscription) => vec![Element::from(subscription)],
                 PubSub::Subscriptions(subscriptions) => vec![Element::from(subscriptions)],
@@ -726,6 +776,57 @@ mod tests {
         assert_eq!(elem1, elem2);
     }
 
+    #[test]
+    fn items_with_set_query() {
+        let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub'><items node='urn:xmpp:bookmarks:1'/><set xmlns='http://jabber.org/protocol/rsm'><max>10</max><after>foo</after></set></pubsub>".parse().unwrap();
+        let elem1 = elem.clone();
+        let pubsub = PubSub::try_from(elem).unwrap();
+        match pubsub.clone() {
+            PubSub::Items {
+                items,
+                set_query,
+                set_result,
+            } => {
+                assert_eq!(items.node.0, "urn:xmpp:bookmarks:1");
+                let set_query = set_query.unwrap();
+                assert_eq!(set_query.max, Some(10));
+                assert_eq!(set_query.after.as_deref(), Some("foo"));
+                assert!(set_result.is_none());
+            }
+            _ => panic!(),
+        }
+
+        let elem2 = Element::from(pubsub);
+        assert_eq!(elem1, elem2);
+    }
+
+    #[test]
+    fn items_with_set_result() {
+        let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub'><items node='urn:xmpp:bookmarks:1'><item id='a'/></items><set xmlns='http://jabber.org/protocol/rsm'><first index='0'>a</first><last>a</last><count>3</count></set></pubsub>".parse().unwrap();
+        let elem1 = elem.clone();
+        let pubsub = PubSub::try_from(elem).unwrap();
+        match pubsub.clone() {
+            PubSub::Items {
+                items,
+                set_query,
+                set_result,
+            } => {
+                assert_eq!(items.items.len(), 1);
+                assert!(set_query.is_none());
+                let set_result = set_result.unwrap();
+                assert_eq!(set_result.last.as_deref(), Some("a"));
+                assert_eq!(set_result.count, Some(3));
+            }
+            _ => panic!(),
+        }
+
+        let elem2 = Element::from(pubsub);
+        assert_eq!(elem1, elem2);
+
+        let elem: Element = "<pubsub xmlns='http://jabber.org/protocol/pubsub'><set xmlns='http://jabber.org/protocol/rsm'/></pubsub>".parse().unwrap();
+        PubSub::try_from(elem).unwrap_err();
+    }
+
     #[test]
     fn test_serialize_options() {
         let reference: Element = "<options xmlns='http://jabber.org/protocol/pubsub' jid='juliet@capulet.lit/balcony'><x xmlns='jabber:x:data' type='submit'/></options>"
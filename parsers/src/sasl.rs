@@ -35,9 +35,29 @@ generate_attribute!(
         /// of channel binding.
         ScramSha256Plus => "SCRAM-SHA-256-PLUS",
 
+        /// Same as [ScramSha1](#structfield.ScramSha1), but using SHA-512
+        /// instead of SHA-1 as the hash function.
+        ScramSha512 => "SCRAM-SHA-512",
+
+        /// Same as [ScramSha512](#structfield.ScramSha512), with the addition
+        /// of channel binding.
+        ScramSha512Plus => "SCRAM-SHA-512-PLUS",
+
+        /// Same as [ScramSha1](#structfield.ScramSha1), but using SHA3-512
+        /// instead of SHA-1 as the hash function.
+        ScramSha3_512 => "SCRAM-SHA3-512",
+
+        /// Same as [ScramSha3_512](#structfield.ScramSha3_512), with the
+        /// addition of channel binding.
+        ScramSha3_512Plus => "SCRAM-SHA3-512-PLUS",
+
         /// Creates a temporary JID on login, which will be destroyed on
         /// disconnect.
         Anonymous => "ANONYMOUS",
+
+        /// Uses credentials established outside of SASL, typically a TLS
+        /// client certificate, as defined in RFC 4422 appendix A.
+        External => "EXTERNAL",
     }
 );
 
@@ -234,6 +254,17 @@ mod tests {
         assert!(auth.data.is_empty());
     }
 
+    #[test]
+    fn test_external() {
+        let elem: Element =
+            "<auth xmlns='urn:ietf:params:xml:ns:xmpp-sasl' mechanism='EXTERNAL'>anVsaWV0QGNhcHVsZXQubGl0</auth>"
+                .parse()
+                .unwrap();
+        let auth = Auth::try_from(elem).unwrap();
+        assert_eq!(auth.mechanism, Mechanism::External);
+        assert_eq!(auth.data, b"juliet@capulet.lit");
+    }
+
     #[test]
     fn section_6_5_1() {
         let elem: Element =
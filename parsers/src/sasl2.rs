@@ -183,6 +183,47 @@ pub struct TaskData {
     pub payloads: Vec<Element>,
 }
 
+/// Enum which allows parsing/serialising any SASL2 element.
+#[derive(FromXml, AsXml, Debug, Clone)]
+#[xml()]
+pub enum Nonza {
+    /// Initiation of SASL2 transaction
+    #[xml(transparent)]
+    Authenticate(Authenticate),
+
+    /// Challenge sent by the server to the client
+    #[xml(transparent)]
+    Challenge(Challenge),
+
+    /// Response sent by the client to the server
+    #[xml(transparent)]
+    Response(Response),
+
+    /// Success of SASL2 transaction
+    #[xml(transparent)]
+    Success(Success),
+
+    /// Failure of SASL2 transaction
+    #[xml(transparent)]
+    Failure(Failure),
+
+    /// Abortion of SASL2 transaction
+    #[xml(transparent)]
+    Abort(Abort),
+
+    /// Request for additional tasks
+    #[xml(transparent)]
+    Continue(Continue),
+
+    /// Selection of an additional task
+    #[xml(transparent)]
+    Next(Next),
+
+    /// Data exchanged while performing a task
+    #[xml(transparent)]
+    TaskData(TaskData),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,6 +286,25 @@ mod tests {
         assert_eq!(response.sasl_data, b"abc");
     }
 
+    #[test]
+    fn test_nonza() {
+        let elem: Element = "<challenge xmlns='urn:xmpp:sasl:2'>AAAA</challenge>"
+            .parse()
+            .unwrap();
+        match Nonza::try_from(elem).unwrap() {
+            Nonza::Challenge(challenge) => assert_eq!(challenge.sasl_data, b"\0\0\0"),
+            other => panic!("unexpected nonza: {other:?}"),
+        }
+
+        let elem: Element = "<failure xmlns='urn:xmpp:sasl:2'><aborted xmlns='urn:ietf:params:xml:ns:xmpp-sasl'/></failure>"
+            .parse()
+            .unwrap();
+        match Nonza::try_from(elem).unwrap() {
+            Nonza::Failure(failure) => assert_eq!(failure.payloads.len(), 1),
+            other => panic!("unexpected nonza: {other:?}"),
+        }
+    }
+
     // XEP-0388 Example 2
     #[test]
     fn test_auth() {
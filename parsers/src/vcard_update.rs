@@ -15,6 +15,7 @@
 use xso::{text::FixedHex, AsXml, FromXml};
 
 use crate::ns;
+use crate::presence::PresencePayload;
 
 /// The presence payload for an avatar VCard update
 #[derive(FromXml, AsXml, Debug, PartialEq, Clone)]
@@ -25,6 +26,8 @@ pub struct VCardUpdate {
     pub photo: Option<Photo>,
 }
 
+impl PresencePayload for VCardUpdate {}
+
 /// The photo element containing the avatar metadata
 #[derive(FromXml, AsXml, PartialEq, Debug, Clone)]
 #[xml(namespace = ns::VCARD_UPDATE, name = "photo")]
@@ -0,0 +1,53 @@
+//! Provides the SASL "EXTERNAL" mechanism.
+
+use crate::client::{Mechanism, MechanismError};
+use crate::common::{Credentials, Identity};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A struct for the SASL EXTERNAL mechanism, authenticating using
+/// credentials established outside of SASL, such as a TLS client
+/// certificate.
+pub struct External {
+    authzid: Option<String>,
+}
+
+impl External {
+    /// Constructs a new struct for authenticating using the SASL EXTERNAL mechanism, letting
+    /// the server derive the identity from the external credentials.
+    ///
+    /// It is recommended that instead you use a `Credentials` struct and turn it into the
+    /// requested mechanism using `from_credentials`.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> External {
+        External { authzid: None }
+    }
+
+    /// Request to be authorized as the given identity, e.g. if the external credentials are
+    /// valid for more than one identity.
+    pub fn with_authzid<N: Into<String>>(mut self, authzid: N) -> External {
+        self.authzid = Some(authzid.into());
+        self
+    }
+}
+
+impl Mechanism for External {
+    fn name(&self) -> &str {
+        "EXTERNAL"
+    }
+
+    /// The username of the credentials, if any, is used as the authzid.
+    fn from_credentials(credentials: Credentials) -> Result<External, MechanismError> {
+        Ok(match credentials.identity {
+            Identity::Username(username) => External::new().with_authzid(username),
+            Identity::None => External::new(),
+        })
+    }
+
+    fn initial(&mut self) -> Vec<u8> {
+        match self.authzid {
+            Some(ref authzid) => authzid.as_bytes().to_vec(),
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Provides the SASL "HT-*" mechanisms, as used by XEP-0484 (FAST).
+
+use crate::client::{Mechanism, MechanismError};
+use crate::common::scram::ScramProvider;
+use crate::common::{ChannelBinding, Credentials, Identity, Password, Secret};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A struct for the SASL HT-* mechanisms, authenticating using a token
+/// previously issued by the server.
+///
+/// The mechanism name depends on the channel binding: `HT-*-NONE` without
+/// channel binding, `HT-*-UNIQ` for tls-unique and `HT-*-EXPR` for
+/// tls-exporter.
+pub struct HashedToken<S: ScramProvider> {
+    name: String,
+    username: String,
+    token: String,
+    channel_binding: ChannelBinding,
+    _marker: PhantomData<S>,
+}
+
+impl<S: ScramProvider> HashedToken<S> {
+    /// Constructs a new struct for authenticating using the SASL HT-*
+    /// mechanisms, depending on the passed channel binding.
+    ///
+    /// It is recommended that instead you use a `Credentials` struct and turn it into the
+    /// requested mechanism using `from_credentials`.
+    pub fn new<N: Into<String>, T: Into<String>>(
+        username: N,
+        token: T,
+        channel_binding: ChannelBinding,
+    ) -> HashedToken<S> {
+        let binding = match channel_binding {
+            ChannelBinding::None | ChannelBinding::Unsupported => "NONE",
+            ChannelBinding::TlsUnique(_) => "UNIQ",
+            ChannelBinding::TlsExporter(_) => "EXPR",
+            ChannelBinding::TlsServerEndPoint(_) => "ENDP",
+        };
+        HashedToken {
+            name: format!("HT-{}-{}", S::name(), binding),
+            username: username.into(),
+            token: token.into(),
+            channel_binding,
+            _marker: PhantomData,
+        }
+    }
+
+    fn hashed_token(&self, label: &[u8]) -> Result<Vec<u8>, MechanismError> {
+        let mut data = Vec::with_capacity(label.len() + self.channel_binding.data().len());
+        data.extend_from_slice(label);
+        data.extend_from_slice(self.channel_binding.data());
+        Ok(S::hmac(&data, self.token.as_bytes())?)
+    }
+}
+
+impl<S: ScramProvider> Mechanism for HashedToken<S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The token is expected as the plaintext password of the credentials.
+    fn from_credentials(credentials: Credentials) -> Result<HashedToken<S>, MechanismError> {
+        if let Secret::Password(Password::Plain(token)) = credentials.secret {
+            if let Identity::Username(username) = credentials.identity {
+                Ok(HashedToken::new(
+                    username,
+                    token,
+                    credentials.channel_binding,
+                ))
+            } else {
+                Err(MechanismError::HashedTokenRequiresUsername)
+            }
+        } else {
+            Err(MechanismError::HashedTokenRequiresToken)
+        }
+    }
+
+    fn initial(&mut self) -> Vec<u8> {
+        // The key is the token, which cannot have an invalid length for HMAC.
+        let hashed_token = self.hashed_token(b"Initiator").unwrap();
+        let mut auth = Vec::with_capacity(self.username.len() + 1 + hashed_token.len());
+        auth.extend(self.username.bytes());
+        auth.push(0);
+        auth.extend(hashed_token);
+        auth
+    }
+
+    fn success(&mut self, data: &[u8]) -> Result<(), MechanismError> {
+        if data.is_empty() {
+            return Err(MechanismError::NoSignatureInSuccessResponse);
+        }
+        if data == self.hashed_token(b"Responder")? {
+            Ok(())
+        } else {
+            Err(MechanismError::InvalidSignatureInSuccessResponse)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::mechanisms::HashedToken;
+    use crate::client::Mechanism;
+    use crate::common::scram::{ScramProvider, Sha256};
+    use crate::common::ChannelBinding;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn ht_sha256_works() {
+        let mut mechanism =
+            HashedToken::<Sha256>::new("juliet", "WXZzciBwYmFmIHZ6IGJhIGYU", ChannelBinding::None);
+        assert_eq!(mechanism.name(), "HT-SHA-256-NONE");
+
+        let init = mechanism.initial();
+        let (username, hashed_token) = init.split_at(6);
+        assert_eq!(username, b"juliet");
+        assert_eq!(hashed_token[0], 0);
+        let expected = Sha256::hmac(b"Initiator", b"WXZzciBwYmFmIHZ6IGJhIGYU").unwrap();
+        assert_eq!(&hashed_token[1..], &expected[..]);
+
+        let responder = Sha256::hmac(b"Responder", b"WXZzciBwYmFmIHZ6IGJhIGYU").unwrap();
+        mechanism.success(&responder).unwrap();
+        assert!(mechanism.success(&expected).is_err());
+    }
+
+    #[test]
+    fn ht_sha256_channel_binding() {
+        let cb = ChannelBinding::TlsExporter(b"exporter".to_vec());
+        let mut mechanism = HashedToken::<Sha256>::new("juliet", "token", cb);
+        assert_eq!(mechanism.name(), "HT-SHA-256-EXPR");
+
+        let init = mechanism.initial();
+        let mut data = Vec::new();
+        data.extend_from_slice(b"Initiator");
+        data.extend_from_slice(b"exporter");
+        let expected = Sha256::hmac(&data, b"token").unwrap();
+        assert_eq!(&init[7..], &expected[..]);
+    }
+}
@@ -1,13 +1,19 @@
 //! Provides a few SASL mechanisms.
 
 mod anonymous;
+mod external;
 mod plain;
 
+#[cfg(feature = "scram")]
+mod hashed_token;
 #[cfg(feature = "scram")]
 mod scram;
 
 pub use self::anonymous::Anonymous;
+pub use self::external::External;
 pub use self::plain::Plain;
 
+#[cfg(feature = "scram")]
+pub use self::hashed_token::HashedToken;
 #[cfg(feature = "scram")]
 pub use self::scram::Scram;
@@ -107,7 +107,9 @@ impl<S: ScramProvider> Mechanism for Scram<S> {
         // TODO: this is quite the workaround…
         match self.channel_binding {
             ChannelBinding::None | ChannelBinding::Unsupported => &self.name,
-            ChannelBinding::TlsUnique(_) | ChannelBinding::TlsExporter(_) => &self.name_plus,
+            ChannelBinding::TlsUnique(_)
+            | ChannelBinding::TlsExporter(_)
+            | ChannelBinding::TlsServerEndPoint(_) => &self.name_plus,
         }
     }
 
@@ -228,9 +230,11 @@ impl<S: ScramProvider> Mechanism for Scram<S> {
 mod tests {
     use crate::client::mechanisms::Scram;
     use crate::client::Mechanism;
-    use crate::common::scram::{Sha1, Sha256};
+    use crate::common::scram::{DeriveError, ScramProvider, Sha1, Sha256};
+    use crate::common::Password;
     use alloc::borrow::ToOwned;
     use alloc::string::String;
+    use base64::{engine::general_purpose::STANDARD as Base64, Engine};
 
     #[test]
     fn scram_sha1_works() {
@@ -332,4 +336,41 @@ mod tests {
             core::str::from_utf8(client_final).unwrap()
         ); // again, depends on ordering…
     }
+
+    #[test]
+    fn scram_sha256_pbkdf2_works() {
+        // Source: RFC 7677, with the password hashed beforehand
+        let username = "user";
+        let salt = Base64.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let data = Sha256::derive(&Password::Plain("pencil".to_owned()), &salt, 4096).unwrap();
+        let password = Password::Pbkdf2 {
+            method: "SHA-256".to_owned(),
+            salt: salt.clone(),
+            iterations: 4096,
+            data: data.clone(),
+        };
+        assert_eq!(Sha256::derive(&password, &salt, 4096).unwrap(), data);
+        assert!(matches!(
+            Sha256::derive(&password, b"another salt", 4096),
+            Err(DeriveError::IncorrectSalt)
+        ));
+        assert!(matches!(
+            Sha256::derive(&password, &salt, 8192),
+            Err(DeriveError::IncompatibleIterationCount(4096, 8192))
+        ));
+
+        let client_nonce = "rOprNGfwEbeRWgbNEkqO";
+        let server_init = b"r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        let client_final = b"c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+        let server_final = b"v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+        let mut mechanism =
+            Scram::<Sha256>::new_with_nonce(username, password, client_nonce.to_owned());
+        mechanism.initial();
+        let resp = mechanism.response(&server_init[..]).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&resp).unwrap(),
+            core::str::from_utf8(client_final).unwrap()
+        );
+        mechanism.success(&server_final[..]).unwrap();
+    }
 }
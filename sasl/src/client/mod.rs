@@ -19,6 +19,9 @@ pub enum MechanismError {
     ScramRequiresUsername,
     ScramRequiresPassword,
 
+    HashedTokenRequiresUsername,
+    HashedTokenRequiresToken,
+
     CannotDecodeChallenge,
     NoServerNonce,
     NoServerSalt,
@@ -65,6 +68,9 @@ impl fmt::Display for MechanismError {
                 MechanismError::ScramRequiresUsername => "SCRAM requires a username",
                 MechanismError::ScramRequiresPassword => "SCRAM requires a password",
 
+                MechanismError::HashedTokenRequiresUsername => "HT requires a username",
+                MechanismError::HashedTokenRequiresToken => "HT requires a token",
+
                 MechanismError::CannotDecodeChallenge => "can't decode challenge",
                 MechanismError::NoServerNonce => "no server nonce",
                 MechanismError::NoServerSalt => "no server salt",
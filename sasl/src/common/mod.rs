@@ -170,6 +170,8 @@ pub enum ChannelBinding {
     TlsUnique(Vec<u8>),
     /// p=tls-exporter channel binding data (for TLS 1.3).
     TlsExporter(Vec<u8>),
+    /// p=tls-server-end-point channel binding data, the hash of the server certificate.
+    TlsServerEndPoint(Vec<u8>),
 }
 
 impl ChannelBinding {
@@ -180,6 +182,7 @@ impl ChannelBinding {
             ChannelBinding::Unsupported => b"y,,",
             ChannelBinding::TlsUnique(_) => b"p=tls-unique,,",
             ChannelBinding::TlsExporter(_) => b"p=tls-exporter,,",
+            ChannelBinding::TlsServerEndPoint(_) => b"p=tls-server-end-point,,",
         }
     }
 
@@ -190,6 +193,7 @@ impl ChannelBinding {
             ChannelBinding::Unsupported => &[],
             ChannelBinding::TlsUnique(ref data) => data,
             ChannelBinding::TlsExporter(ref data) => data,
+            ChannelBinding::TlsServerEndPoint(ref data) => data,
         }
     }
 
@@ -200,6 +204,17 @@ impl ChannelBinding {
             ChannelBinding::Unsupported => false,
             ChannelBinding::TlsUnique(_) => mechanism == "tls-unique",
             ChannelBinding::TlsExporter(_) => mechanism == "tls-exporter",
+            ChannelBinding::TlsServerEndPoint(_) => mechanism == "tls-server-end-point",
+        }
+    }
+
+    /// Return the IANA name of this channel binding type, if any data is present.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            ChannelBinding::None | ChannelBinding::Unsupported => None,
+            ChannelBinding::TlsUnique(_) => Some("tls-unique"),
+            ChannelBinding::TlsExporter(_) => Some("tls-exporter"),
+            ChannelBinding::TlsServerEndPoint(_) => Some("tls-server-end-point"),
         }
     }
 }
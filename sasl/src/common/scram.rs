@@ -3,10 +3,12 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt;
 use getrandom::{getrandom, Error as RngError};
-use hmac::{digest::InvalidLength, Hmac, Mac};
+use hmac::digest::{FixedOutput, InvalidLength, KeyInit, Update};
+use hmac::{Hmac, Mac};
 use pbkdf2::pbkdf2;
 use sha1::{Digest, Sha1 as Sha1_hash};
-use sha2::Sha256 as Sha256_hash;
+use sha2::{Sha256 as Sha256_hash, Sha512 as Sha512_hash};
+use sha3::Sha3_512 as Sha3_512_hash;
 
 use crate::common::Password;
 
@@ -70,6 +72,52 @@ pub trait ScramProvider {
     fn derive(data: &Password, salt: &[u8], iterations: u32) -> Result<Vec<u8>, DeriveError>;
 }
 
+/// Perform PBKDF2 key derivation with the given pseudo-random function,
+/// or reuse the already derived `password` if it matches the parameters.
+fn derive_pbkdf2<PRF: KeyInit + Update + FixedOutput + Clone + Sync>(
+    name: &str,
+    output_size: usize,
+    password: &Password,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<Vec<u8>, DeriveError> {
+    match *password {
+        Password::Plain(ref plain) => {
+            let mut result = vec![0; output_size];
+            pbkdf2::<PRF>(plain.as_bytes(), salt, iterations, &mut result)?;
+            Ok(result)
+        }
+        Password::Pbkdf2 {
+            ref method,
+            salt: ref my_salt,
+            iterations: my_iterations,
+            ref data,
+        } => {
+            if method != name {
+                Err(DeriveError::IncompatibleHashingMethod(
+                    method.to_string(),
+                    name.to_string(),
+                ))
+            } else if my_salt != salt {
+                Err(DeriveError::IncorrectSalt)
+            } else if my_iterations != iterations {
+                Err(DeriveError::IncompatibleIterationCount(
+                    my_iterations,
+                    iterations,
+                ))
+            } else {
+                Ok(data.to_vec())
+            }
+        }
+    }
+}
+
+fn compute_hmac<M: Mac + KeyInit>(data: &[u8], key: &[u8]) -> Result<Vec<u8>, InvalidLength> {
+    let mut mac = <M as Mac>::new_from_slice(key)?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
 /// A `ScramProvider` which provides SCRAM-SHA-1 and SCRAM-SHA-1-PLUS
 pub struct Sha1;
 
@@ -86,42 +134,11 @@ impl ScramProvider for Sha1 {
     }
 
     fn hmac(data: &[u8], key: &[u8]) -> Result<Vec<u8>, InvalidLength> {
-        type HmacSha1 = Hmac<Sha1_hash>;
-        let mut mac = HmacSha1::new_from_slice(key)?;
-        mac.update(data);
-        Ok(mac.finalize().into_bytes().to_vec())
+        compute_hmac::<Hmac<Sha1_hash>>(data, key)
     }
 
     fn derive(password: &Password, salt: &[u8], iterations: u32) -> Result<Vec<u8>, DeriveError> {
-        match *password {
-            Password::Plain(ref plain) => {
-                let mut result = vec![0; 20];
-                pbkdf2::<Hmac<Sha1_hash>>(plain.as_bytes(), salt, iterations, &mut result)?;
-                Ok(result)
-            }
-            Password::Pbkdf2 {
-                ref method,
-                salt: ref my_salt,
-                iterations: my_iterations,
-                ref data,
-            } => {
-                if method != Self::name() {
-                    Err(DeriveError::IncompatibleHashingMethod(
-                        method.to_string(),
-                        Self::name().to_string(),
-                    ))
-                } else if my_salt == salt {
-                    Err(DeriveError::IncorrectSalt)
-                } else if my_iterations == iterations {
-                    Err(DeriveError::IncompatibleIterationCount(
-                        my_iterations,
-                        iterations,
-                    ))
-                } else {
-                    Ok(data.to_vec())
-                }
-            }
-        }
+        derive_pbkdf2::<Hmac<Sha1_hash>>(Self::name(), 20, password, salt, iterations)
     }
 }
 
@@ -141,41 +158,58 @@ impl ScramProvider for Sha256 {
     }
 
     fn hmac(data: &[u8], key: &[u8]) -> Result<Vec<u8>, InvalidLength> {
-        type HmacSha256 = Hmac<Sha256_hash>;
-        let mut mac = HmacSha256::new_from_slice(key)?;
-        mac.update(data);
-        Ok(mac.finalize().into_bytes().to_vec())
+        compute_hmac::<Hmac<Sha256_hash>>(data, key)
     }
 
     fn derive(password: &Password, salt: &[u8], iterations: u32) -> Result<Vec<u8>, DeriveError> {
-        match *password {
-            Password::Plain(ref plain) => {
-                let mut result = vec![0; 32];
-                pbkdf2::<Hmac<Sha256_hash>>(plain.as_bytes(), salt, iterations, &mut result)?;
-                Ok(result)
-            }
-            Password::Pbkdf2 {
-                ref method,
-                salt: ref my_salt,
-                iterations: my_iterations,
-                ref data,
-            } => {
-                if method != Self::name() {
-                    Err(DeriveError::IncompatibleHashingMethod(
-                        method.to_string(),
-                        Self::name().to_string(),
-                    ))
-                } else if my_salt == salt {
-                    Err(DeriveError::IncorrectSalt)
-                } else if my_iterations == iterations {
-                    Err(DeriveError::IncompatibleIterationCount(
-                        my_iterations,
-                        iterations,
-                    ))
-                } else {
-                    Ok(data.to_vec())
-                }
-            }
-        }
+        derive_pbkdf2::<Hmac<Sha256_hash>>(Self::name(), 32, password, salt, iterations)
+    }
+}
+
+/// A `ScramProvider` which provides SCRAM-SHA-512 and SCRAM-SHA-512-PLUS
+pub struct Sha512;
+
+impl ScramProvider for Sha512 {
+    type Secret = secret::Pbkdf2Sha512;
+
+    fn name() -> &'static str {
+        "SHA-512"
+    }
+
+    fn hash(data: &[u8]) -> Vec<u8> {
+        let hash = Sha512_hash::digest(data);
+        hash.to_vec()
+    }
+
+    fn hmac(data: &[u8], key: &[u8]) -> Result<Vec<u8>, InvalidLength> {
+        compute_hmac::<Hmac<Sha512_hash>>(data, key)
+    }
+
+    fn derive(password: &Password, salt: &[u8], iterations: u32) -> Result<Vec<u8>, DeriveError> {
+        derive_pbkdf2::<Hmac<Sha512_hash>>(Self::name(), 64, password, salt, iterations)
+    }
+}
+
+/// A `ScramProvider` which provides SCRAM-SHA3-512 and SCRAM-SHA3-512-PLUS
+pub struct Sha3_512;
+
+impl ScramProvider for Sha3_512 {
+    type Secret = secret::Pbkdf2Sha3_512;
+
+    fn name() -> &'static str {
+        "SHA3-512"
+    }
+
+    fn hash(data: &[u8]) -> Vec<u8> {
+        let hash = Sha3_512_hash::digest(data);
+        hash.to_vec()
+    }
+
+    fn hmac(data: &[u8], key: &[u8]) -> Result<Vec<u8>, InvalidLength> {
+        compute_hmac::<Hmac<Sha3_512_hash>>(data, key)
+    }
+
+    fn derive(password: &Password, salt: &[u8], iterations: u32) -> Result<Vec<u8>, DeriveError> {
+        derive_pbkdf2::<Hmac<Sha3_512_hash>>(Self::name(), 64, password, salt, iterations)
     }
 }
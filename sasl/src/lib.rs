@@ -176,6 +176,19 @@
 //!     let mut server_mech = ServerScram::<Sha256, _>::new(MyValidator, ChannelBinding::Unsupported);
 //!
 //!     assert_eq!(finish(&mut client_mech, &mut server_mech), Ok(Identity::Username(USERNAME.to_owned())));
+//!
+//!     let channel_binding = ChannelBinding::TlsServerEndPoint(b"certificate hash".to_vec());
+//!     let mut client_mech = ClientScram::<Sha256>::from_credentials(creds.clone().with_channel_binding(channel_binding.clone())).unwrap();
+//!     let mut server_mech = ServerScram::<Sha256, _>::new(MyValidator, channel_binding);
+//!
+//!     assert_eq!(client_mech.name(), "SCRAM-SHA-256-PLUS");
+//!     assert_eq!(finish(&mut client_mech, &mut server_mech), Ok(Identity::Username(USERNAME.to_owned())));
+//!
+//!     let channel_binding = ChannelBinding::TlsExporter(b"exported keying material".to_vec());
+//!     let mut client_mech = ClientScram::<Sha256>::from_credentials(creds.clone().with_channel_binding(channel_binding)).unwrap();
+//!     let mut server_mech = ServerScram::<Sha256, _>::new(MyValidator, ChannelBinding::TlsServerEndPoint(b"certificate hash".to_vec()));
+//!
+//!     assert_eq!(finish(&mut client_mech, &mut server_mech), Err(MechanismError::Server(ServerMechanismError::ChannelBindingMechanismIncorrect)));
 //! }
 //! ```
 //!
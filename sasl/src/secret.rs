@@ -17,6 +17,13 @@ pub struct Plain(pub String);
 
 impl Secret for Plain {}
 
+/// The identities proven by the peer outside of SASL, usually the addresses
+/// found in its TLS client certificate, as used by the EXTERNAL mechanism.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateIdentity(pub Vec<String>);
+
+impl Secret for CertificateIdentity {}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Pbkdf2Sha1 {
     pub salt: Vec<u8>,
@@ -90,3 +97,81 @@ impl Pbkdf2Secret for Pbkdf2Sha256 {
         &self.digest
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pbkdf2Sha512 {
+    pub salt: Vec<u8>,
+    pub iterations: u32,
+    pub digest: Vec<u8>,
+}
+
+impl Pbkdf2Sha512 {
+    #[cfg(feature = "scram")]
+    pub fn derive(
+        password: &str,
+        salt: &[u8],
+        iterations: u32,
+    ) -> Result<Pbkdf2Sha512, DeriveError> {
+        use crate::common::scram::{ScramProvider, Sha512};
+        use crate::common::Password;
+        let digest = Sha512::derive(&Password::Plain(password.to_owned()), salt, iterations)?;
+        Ok(Pbkdf2Sha512 {
+            salt: salt.to_vec(),
+            iterations,
+            digest,
+        })
+    }
+}
+
+impl Secret for Pbkdf2Sha512 {}
+
+impl Pbkdf2Secret for Pbkdf2Sha512 {
+    fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+    fn iterations(&self) -> u32 {
+        self.iterations
+    }
+    fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pbkdf2Sha3_512 {
+    pub salt: Vec<u8>,
+    pub iterations: u32,
+    pub digest: Vec<u8>,
+}
+
+impl Pbkdf2Sha3_512 {
+    #[cfg(feature = "scram")]
+    pub fn derive(
+        password: &str,
+        salt: &[u8],
+        iterations: u32,
+    ) -> Result<Pbkdf2Sha3_512, DeriveError> {
+        use crate::common::scram::{ScramProvider, Sha3_512};
+        use crate::common::Password;
+        let digest = Sha3_512::derive(&Password::Plain(password.to_owned()), salt, iterations)?;
+        Ok(Pbkdf2Sha3_512 {
+            salt: salt.to_vec(),
+            iterations,
+            digest,
+        })
+    }
+}
+
+impl Secret for Pbkdf2Sha3_512 {}
+
+impl Pbkdf2Secret for Pbkdf2Sha3_512 {
+    fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+    fn iterations(&self) -> u32 {
+        self.iterations
+    }
+    fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
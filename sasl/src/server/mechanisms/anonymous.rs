@@ -25,7 +25,7 @@ impl Mechanism for Anonymous {
         }
         let mut rand = [0u8; 16];
         getrandom(&mut rand)?;
-        let username = format!("{:02x?}", rand);
+        let username = rand.iter().map(|byte| format!("{:02x}", byte)).collect();
         let ident = Identity::Username(username);
         Ok(Response::Success(ident, Vec::new()))
     }
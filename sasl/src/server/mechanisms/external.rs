@@ -0,0 +1,106 @@
+use crate::common::Identity;
+use crate::secret;
+use crate::server::{Mechanism, MechanismError, Response, Validator};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Server side of the SASL EXTERNAL mechanism.
+///
+/// The identities established outside of SASL, e.g. from the peer's TLS
+/// client certificate, are passed to the validator along with the
+/// requested identity. If the client does not request an identity, the
+/// certificate must contain exactly one.
+pub struct External<V: Validator<secret::CertificateIdentity>> {
+    validator: V,
+    certificate: secret::CertificateIdentity,
+}
+
+impl<V: Validator<secret::CertificateIdentity>> External<V> {
+    pub fn new(validator: V, certificate: secret::CertificateIdentity) -> External<V> {
+        External {
+            validator,
+            certificate,
+        }
+    }
+}
+
+impl<V: Validator<secret::CertificateIdentity>> Mechanism for External<V> {
+    fn name(&self) -> &str {
+        "EXTERNAL"
+    }
+
+    fn respond(&mut self, payload: &[u8]) -> Result<Response, MechanismError> {
+        let username = if payload.is_empty() {
+            match self.certificate.0.as_slice() {
+                [identity] => identity.clone(),
+                _ => return Err(MechanismError::NoUsernameSpecified),
+            }
+        } else {
+            String::from_utf8(payload.to_vec())
+                .map_err(|_| MechanismError::ErrorDecodingUsername)?
+        };
+        let ident = Identity::Username(username);
+        self.validator.validate(&ident, &self.certificate)?;
+        Ok(Response::Success(ident, Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::ValidatorError;
+    use alloc::borrow::ToOwned;
+    use alloc::vec;
+
+    struct MyValidator;
+
+    impl Validator<secret::CertificateIdentity> for MyValidator {
+        fn validate(
+            &self,
+            identity: &Identity,
+            value: &secret::CertificateIdentity,
+        ) -> Result<(), ValidatorError> {
+            match identity {
+                Identity::Username(username) if value.0.contains(username) => Ok(()),
+                _ => Err(ValidatorError::AuthenticationFailed),
+            }
+        }
+    }
+
+    #[test]
+    fn external_derives_identity() {
+        let certificate = secret::CertificateIdentity(vec!["juliet@capulet.lit".to_owned()]);
+        let mut mech = External::new(MyValidator, certificate);
+        assert_eq!(
+            mech.respond(b""),
+            Ok(Response::Success(
+                Identity::Username("juliet@capulet.lit".to_owned()),
+                Vec::new()
+            ))
+        );
+    }
+
+    #[test]
+    fn external_authzid() {
+        let certificate = secret::CertificateIdentity(vec![
+            "juliet@capulet.lit".to_owned(),
+            "romeo@montague.lit".to_owned(),
+        ]);
+        let mut mech = External::new(MyValidator, certificate.clone());
+        assert_eq!(mech.respond(b""), Err(MechanismError::NoUsernameSpecified));
+        assert_eq!(
+            mech.respond(b"romeo@montague.lit"),
+            Ok(Response::Success(
+                Identity::Username("romeo@montague.lit".to_owned()),
+                Vec::new()
+            ))
+        );
+        let mut mech = External::new(MyValidator, certificate);
+        assert_eq!(
+            mech.respond(b"nurse@capulet.lit"),
+            Err(MechanismError::ValidatorError(
+                ValidatorError::AuthenticationFailed
+            ))
+        );
+    }
+}
@@ -1,11 +1,13 @@
 #[cfg(feature = "anonymous")]
 mod anonymous;
+mod external;
 mod plain;
 #[cfg(feature = "scram")]
 mod scram;
 
 #[cfg(feature = "anonymous")]
 pub use self::anonymous::Anonymous;
+pub use self::external::External;
 pub use self::plain::Plain;
 #[cfg(feature = "scram")]
 pub use self::scram::Scram;
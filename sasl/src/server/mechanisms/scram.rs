@@ -34,6 +34,7 @@ where
     name: String,
     state: ScramState,
     channel_binding: ChannelBinding,
+    plus_offered: bool,
     provider: P,
     _marker: PhantomData<S>,
 }
@@ -44,15 +45,30 @@ where
     P: Provider<S::Secret>,
     S::Secret: secret::Pbkdf2Secret,
 {
+    /// Create a SCRAM mechanism, which is the -PLUS variant if
+    /// `channel_binding` contains data.
     pub fn new(provider: P, channel_binding: ChannelBinding) -> Scram<S, P> {
+        let name = match channel_binding.name() {
+            Some(_) => format!("SCRAM-{}-PLUS", S::name()),
+            None => format!("SCRAM-{}", S::name()),
+        };
         Scram {
-            name: format!("SCRAM-{}", S::name()),
+            name,
             state: ScramState::Init,
             channel_binding,
+            plus_offered: false,
             provider,
             _marker: PhantomData,
         }
     }
+
+    /// Declare whether the -PLUS variant of this mechanism was offered to
+    /// the client too, in which case a client claiming that the server
+    /// doesn’t support channel binding is rejected.
+    pub fn with_plus_offered(mut self, plus_offered: bool) -> Scram<S, P> {
+        self.plus_offered = plus_offered;
+        self
+    }
 }
 
 impl<S, P> Mechanism for Scram<S, P>
@@ -90,25 +106,28 @@ where
                 }
                 let gs2_header = payload[..idx].to_vec();
                 let rest = payload[idx..].to_vec();
-                // TODO: process gs2 header properly, not this ugly stuff
-                match self.channel_binding {
-                    ChannelBinding::None | ChannelBinding::Unsupported => {
-                        // Not supported.
-                        if gs2_header[0] != 0x79 {
-                            // ord("y")
-                            return Err(MechanismError::ChannelBindingNotSupported);
+                match (gs2_header[0], self.channel_binding.name()) {
+                    // ord("n"), the client doesn’t support channel binding.
+                    (0x6e, None) => (),
+                    // ord("y"), the client supports channel binding but thinks we don’t, which
+                    // means an attacker removed the -PLUS mechanisms we offered.
+                    (0x79, None) => {
+                        if self.plus_offered {
+                            return Err(MechanismError::ChannelBindingIsSupported);
                         }
                     }
-                    ref other => {
-                        // Supported.
-                        if gs2_header[0] == 0x79 {
-                            // ord("y")
-                            return Err(MechanismError::ChannelBindingIsSupported);
-                        } else if !other.supports("tls-unique") {
-                            // TODO: grab the data
+                    // ord("p"), the client selected a -PLUS mechanism, check the type matches.
+                    (0x70, Some(_)) => {
+                        let header = self.channel_binding.header();
+                        if !gs2_header.starts_with(&header[..header.len() - 1]) {
                             return Err(MechanismError::ChannelBindingMechanismIncorrect);
                         }
                     }
+                    (0x70, None) => return Err(MechanismError::ChannelBindingNotSupported),
+                    (0x6e | 0x79, Some(_)) => {
+                        return Err(MechanismError::ChannelBindingIsSupported)
+                    }
+                    _ => return Err(MechanismError::FailedToDecodeMessage),
                 }
                 let frame =
                     parse_frame(&rest).map_err(|_| MechanismError::CannotDecodeInitialMessage)?;
@@ -188,3 +207,128 @@ where
         Ok(ret)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mechanisms::Scram as ClientScram;
+    use crate::client::Mechanism as ClientMechanism;
+    use crate::common::scram::{Sha3_512, Sha512};
+    use crate::common::Credentials;
+    use crate::server::ProviderError;
+
+    const SALT: &[u8] = b"QSXCR+Q6sek8bf92";
+
+    struct MyProvider;
+
+    impl Provider<secret::Pbkdf2Sha512> for MyProvider {
+        fn provide(&self, identity: &Identity) -> Result<secret::Pbkdf2Sha512, ProviderError> {
+            if identity != &Identity::Username("user".to_owned()) {
+                return Err(ProviderError::AuthenticationFailed);
+            }
+            Ok(secret::Pbkdf2Sha512::derive("pencil", SALT, 4096)?)
+        }
+    }
+
+    impl_validator_using_provider!(MyProvider, secret::Pbkdf2Sha512);
+
+    impl Provider<secret::Pbkdf2Sha3_512> for MyProvider {
+        fn provide(&self, identity: &Identity) -> Result<secret::Pbkdf2Sha3_512, ProviderError> {
+            if identity != &Identity::Username("user".to_owned()) {
+                return Err(ProviderError::AuthenticationFailed);
+            }
+            Ok(secret::Pbkdf2Sha3_512::derive("pencil", SALT, 4096)?)
+        }
+    }
+
+    impl_validator_using_provider!(MyProvider, secret::Pbkdf2Sha3_512);
+
+    fn authenticate<S>(password: &str) -> Result<Identity, MechanismError>
+    where
+        S: ScramProvider,
+        MyProvider: Provider<S::Secret>,
+        S::Secret: secret::Pbkdf2Secret,
+    {
+        let creds = Credentials::default()
+            .with_username("user")
+            .with_password(password);
+        let mut client = ClientScram::<S>::from_credentials(creds).unwrap();
+        let mut server = Scram::<S, _>::new(MyProvider, ChannelBinding::Unsupported);
+        let Response::Proceed(challenge) = server.respond(&client.initial())? else {
+            panic!("expected a challenge");
+        };
+        let response = client.response(&challenge).unwrap();
+        let Response::Success(identity, additional_data) = server.respond(&response)? else {
+            panic!("expected success");
+        };
+        client.success(&additional_data).unwrap();
+        Ok(identity)
+    }
+
+    #[test]
+    fn scram_sha512_works() {
+        assert_eq!(
+            authenticate::<Sha512>("pencil"),
+            Ok(Identity::Username("user".to_owned()))
+        );
+        assert_eq!(
+            authenticate::<Sha512>("marker"),
+            Err(MechanismError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn scram_sha3_512_works() {
+        assert_eq!(
+            authenticate::<Sha3_512>("pencil"),
+            Ok(Identity::Username("user".to_owned()))
+        );
+        assert_eq!(
+            authenticate::<Sha3_512>("marker"),
+            Err(MechanismError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn scram_gs2_flags() {
+        let creds = Credentials::default()
+            .with_username("user")
+            .with_password("pencil");
+        let first_message = |channel_binding: ChannelBinding| {
+            let creds = creds.clone().with_channel_binding(channel_binding);
+            ClientScram::<Sha512>::from_credentials(creds)
+                .unwrap()
+                .initial()
+        };
+        let channel_binding = ChannelBinding::TlsExporter(b"keying material".to_vec());
+
+        let mut server = Scram::<Sha512, _>::new(MyProvider, ChannelBinding::None);
+        assert_eq!(server.name(), "SCRAM-SHA-512");
+        assert!(server.respond(&first_message(ChannelBinding::None)).is_ok());
+        let mut server = Scram::<Sha512, _>::new(MyProvider, ChannelBinding::None);
+        assert!(server
+            .respond(&first_message(ChannelBinding::Unsupported))
+            .is_ok());
+
+        let mut server =
+            Scram::<Sha512, _>::new(MyProvider, ChannelBinding::None).with_plus_offered(true);
+        assert_eq!(
+            server.respond(&first_message(ChannelBinding::Unsupported)),
+            Err(MechanismError::ChannelBindingIsSupported)
+        );
+        let mut server = Scram::<Sha512, _>::new(MyProvider, ChannelBinding::None);
+        assert_eq!(
+            server.respond(&first_message(channel_binding.clone())),
+            Err(MechanismError::ChannelBindingNotSupported)
+        );
+
+        let mut server = Scram::<Sha512, _>::new(MyProvider, channel_binding.clone());
+        assert_eq!(server.name(), "SCRAM-SHA-512-PLUS");
+        assert_eq!(
+            server.respond(&first_message(ChannelBinding::None)),
+            Err(MechanismError::ChannelBindingIsSupported)
+        );
+        let mut server = Scram::<Sha512, _>::new(MyProvider, channel_binding.clone());
+        assert!(server.respond(&first_message(channel_binding)).is_ok());
+    }
+}
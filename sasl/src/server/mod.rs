@@ -14,7 +14,7 @@ macro_rules! impl_validator_using_provider {
             fn validate(
                 &self,
                 identity: &$crate::common::Identity,
-                value: &$secret,
+                _value: &$secret,
             ) -> Result<(), $crate::server::ValidatorError> {
                 if $crate::server::Provider::<$secret>::provide(self, identity).is_ok() {
                     Ok(())
@@ -202,18 +202,22 @@ fn make_presence(caps: Caps) -> Presence {
 fn download_avatar(from: Jid) -> Iq {
     Iq::from_get(
         "coucou",
-        PubSub::Items(Items {
-            max_items: None,
-            node: NodeName(String::from(ns::AVATAR_DATA)),
-            subid: None,
-            items: Vec::new(),
-        }),
+        PubSub::Items {
+            items: Items {
+                max_items: None,
+                node: NodeName(String::from(ns::AVATAR_DATA)),
+                subid: None,
+                items: Vec::new(),
+            },
+            set_query: None,
+            set_result: None,
+        },
     )
     .with_to(from)
 }
 
 fn handle_iq_result(pubsub: PubSub, from: &Jid) {
-    if let PubSub::Items(items) = pubsub {
+    if let PubSub::Items { items, .. } = pubsub {
         if items.node.0 == ns::AVATAR_DATA {
             for item in items.items {
                 match (item.id.clone(), item.payload.clone()) {
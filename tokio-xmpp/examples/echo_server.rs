@@ -1,11 +1,7 @@
 use futures::{SinkExt, StreamExt};
 use tokio::{self, io, net::TcpSocket};
 
-use tokio_xmpp::{
-    minidom::Element,
-    parsers::stream_features::StreamFeatures,
-    xmlstream::{accept_stream, StreamHeader, Timeouts},
-};
+use tokio_xmpp::{server::ServerNegotiator, xmlstream::XmppStreamElement};
 
 #[tokio::main]
 async fn main() -> Result<(), io::Error> {
@@ -16,29 +12,37 @@ async fn main() -> Result<(), io::Error> {
 
     let listener = socket.listen(1024)?;
 
+    // Let anyone log in on localhost, without TLS.
+    let negotiator = ServerNegotiator::new("localhost".parse().unwrap()).with_anonymous();
+
     // Main loop, accepts incoming connections
     loop {
         let (stream, _addr) = listener.accept().await?;
-        let stream = accept_stream(
-            tokio::io::BufStream::new(stream),
-            tokio_xmpp::parsers::ns::DEFAULT_NS,
-            Timeouts::default(),
-        )
-        .await?;
-        let stream = stream.send_header(StreamHeader::default()).await?;
-        let mut stream = stream
-            .send_features::<Element>(&StreamFeatures::default())
-            .await?;
+        let negotiator = negotiator.clone();
 
         tokio::spawn(async move {
-            while let Some(packet) = stream.next().await {
+            let mut bound = match negotiator.accept(stream).await {
+                Ok(bound) => bound,
+                Err(e) => {
+                    eprintln!("Negotiation failed: {}", e);
+                    return;
+                }
+            };
+            println!("{} connected", bound.jid);
+            while let Some(packet) = bound.stream.next().await {
                 match packet {
-                    Ok(packet) => {
-                        println!("Received packet: {:?}", packet);
-                        stream.send(&packet).await.unwrap();
+                    Ok(XmppStreamElement::Stanza(stanza)) => {
+                        println!("Received stanza: {:?}", stanza);
+                        bound
+                            .stream
+                            .send(&XmppStreamElement::Stanza(stanza))
+                            .await
+                            .unwrap();
                     }
+                    Ok(packet) => println!("Ignoring packet: {:?}", packet),
                     Err(e) => {
                         eprintln!("Error: {:?}", e);
+                        break;
                     }
                 }
             }
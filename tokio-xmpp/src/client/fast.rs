@@ -0,0 +1,261 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Storage for XEP-0484 (Fast Authentication Streamlining Tokens) tokens.
+//!
+//! Tokens are only used once the TLS handshake has completed: none of our
+//! connectors sends TLS 0-RTT early data, since STARTTLS always starts in
+//! plaintext, so FAST saves the password hashing but not a round trip.
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+use core::str::FromStr;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use xmpp_parsers::{date::DateTime, fast, jid::BareJid};
+
+/// A token issued by the server to authenticate without a password.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastToken {
+    /// The HT-* mechanism this token was issued for.
+    pub mechanism: String,
+
+    /// The secret token.
+    pub token: String,
+
+    /// The time at which the server will stop accepting this token.
+    pub expiry: DateTime,
+
+    /// The number of times this token has been used, sent along each
+    /// authentication as XEP-0484 requires.
+    pub count: u32,
+}
+
+impl FastToken {
+    /// Create a token from the `<token/>` element sent by the server, for
+    /// the given mechanism.
+    pub fn new(mechanism: String, token: fast::Token) -> Self {
+        Self {
+            mechanism,
+            token: token.token,
+            expiry: token.expiry,
+            count: 0,
+        }
+    }
+
+    /// Whether the token has expired.
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        self.expiry.0.timestamp() <= now
+    }
+}
+
+/// Persistent storage of [`FastToken`]s, keyed by account.
+///
+/// Implementations must be cheap to call from async code, as they are
+/// called during login.
+pub trait TokenStore: fmt::Debug + Send + Sync + 'static {
+    /// Retrieve the token for the given account, if any.
+    fn load(&self, jid: &BareJid) -> io::Result<Option<FastToken>>;
+
+    /// Store the token for the given account, replacing any previous one.
+    fn store(&self, jid: &BareJid, token: &FastToken) -> io::Result<()>;
+
+    /// Forget the token for the given account.
+    fn remove(&self, jid: &BareJid) -> io::Result<()>;
+}
+
+/// A [`TokenStore`] which only keeps tokens in memory.
+///
+/// Tokens survive reconnections, but not restarts of the application.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<BTreeMap<BareJid, FastToken>>,
+}
+
+impl MemoryTokenStore {
+    /// Create an empty in-memory token store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, jid: &BareJid) -> io::Result<Option<FastToken>> {
+        Ok(self.tokens.lock().unwrap().get(jid).cloned())
+    }
+
+    fn store(&self, jid: &BareJid, token: &FastToken) -> io::Result<()> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(jid.clone(), token.clone());
+        Ok(())
+    }
+
+    fn remove(&self, jid: &BareJid) -> io::Result<()> {
+        self.tokens.lock().unwrap().remove(jid);
+        Ok(())
+    }
+}
+
+/// A [`TokenStore`] which keeps tokens in a file.
+///
+/// The file contains one token per line, as tab-separated account,
+/// mechanism, count, expiry and token. It is rewritten on every change, and
+/// should be protected from other users as it grants access to the
+/// accounts.
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileTokenStore {
+    /// Create a token store backed by the file at `path`.
+    ///
+    /// The file is created on the first write if it does not exist.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read_all(&self) -> io::Result<BTreeMap<BareJid, FastToken>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e),
+        };
+        let mut tokens = BTreeMap::new();
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            match parse_line(line) {
+                Some((jid, token)) => {
+                    tokens.insert(jid, token);
+                }
+                None => log::warn!(
+                    "Ignoring malformed line in token store {}",
+                    self.path.display()
+                ),
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn write_all(&self, tokens: &BTreeMap<BareJid, FastToken>) -> io::Result<()> {
+        let mut contents = String::new();
+        for (jid, token) in tokens {
+            contents.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                jid,
+                token.mechanism,
+                token.count,
+                token.expiry.0.to_rfc3339(),
+                token.token
+            ));
+        }
+        fs::write(&self.path, contents)
+    }
+}
+
+fn parse_line(line: &str) -> Option<(BareJid, FastToken)> {
+    let mut fields = line.splitn(5, '\t');
+    let jid = BareJid::from_str(fields.next()?).ok()?;
+    let mechanism = fields.next()?.to_owned();
+    let count = fields.next()?.parse().ok()?;
+    let expiry = DateTime::from_str(fields.next()?).ok()?;
+    let token = fields.next()?.to_owned();
+    Some((
+        jid,
+        FastToken {
+            mechanism,
+            token,
+            expiry,
+            count,
+        },
+    ))
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, jid: &BareJid) -> io::Result<Option<FastToken>> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.read_all()?.remove(jid))
+    }
+
+    fn store(&self, jid: &BareJid, token: &FastToken) -> io::Result<()> {
+        if [&token.mechanism, &token.token]
+            .iter()
+            .any(|field| field.contains(['\t', '\n']))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "token contains characters which cannot be stored",
+            ));
+        }
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read_all()?;
+        tokens.insert(jid.clone(), token.clone());
+        self.write_all(&tokens)
+    }
+
+    fn remove(&self, jid: &BareJid) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read_all()?;
+        if tokens.remove(jid).is_some() {
+            self.write_all(&tokens)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token() -> FastToken {
+        FastToken {
+            mechanism: String::from("HT-SHA-256-NONE"),
+            token: String::from("WXZzciBwYmFmIHZ6IGJhIGYU"),
+            expiry: DateTime::from_str("2024-06-30T17:13:57+02:00").unwrap(),
+            count: 3,
+        }
+    }
+
+    #[test]
+    fn file_store_roundtrip() {
+        let path =
+            std::env::temp_dir().join(format!("tokio-xmpp-fast-test-{}", std::process::id()));
+        let jid = BareJid::new("juliet@capulet.lit").unwrap();
+        let store = FileTokenStore::new(&path);
+        assert_eq!(store.load(&jid).unwrap(), None);
+
+        store.store(&jid, &token()).unwrap();
+        let reopened = FileTokenStore::new(&path);
+        assert_eq!(reopened.load(&jid).unwrap(), Some(token()));
+        assert!(reopened.load(&jid).unwrap().unwrap().is_expired());
+
+        reopened.remove(&jid).unwrap();
+        assert_eq!(store.load(&jid).unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_store_rejects_separators() {
+        let store = FileTokenStore::new(std::env::temp_dir().join("unused"));
+        let jid = BareJid::new("juliet@capulet.lit").unwrap();
+        let mut token = token();
+        token.token.push('\n');
+        assert!(store.store(&jid, &token).is_err());
+    }
+}
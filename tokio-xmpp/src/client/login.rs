@@ -1,126 +1,714 @@
 use alloc::borrow::Cow;
+use alloc::sync::Arc;
 use core::str::FromStr;
 use futures::{SinkExt, StreamExt};
-use sasl::client::mechanisms::{Anonymous, Plain, Scram};
-use sasl::client::Mechanism;
-use sasl::common::scram::{Sha1, Sha256};
-use sasl::common::Credentials;
+use sasl::client::mechanisms::{Anonymous, External, HashedToken, Plain, Scram};
+use sasl::client::{Mechanism, MechanismError};
+use sasl::common::scram::{Sha1, Sha256, Sha3_512, Sha512};
+use sasl::common::{ChannelBinding, Credentials};
 use std::collections::HashSet;
 use std::io;
 use tokio::io::{AsyncBufRead, AsyncWrite};
 use xmpp_parsers::{
-    jid::Jid,
+    bind2::{BindFeature, BindQuery, Bound},
+    carbons,
+    fast::{FastQuery, FastResponse, RequestToken, Token},
+    jid::{BareJid, Jid},
+    minidom::Element,
     ns,
-    sasl::{Auth, Mechanism as XMPPMechanism, Nonza, Response},
+    sasl::{Auth, DefinedCondition, Mechanism as XMPPMechanism, Nonza, Response},
+    sasl2::{self, Authenticate, Authentication, UserAgent},
+    sasl_cb::{self, SaslChannelBinding},
+    sm,
     stream_features::{SaslMechanisms, StreamFeatures},
 };
 
 use crate::{
+    client::fast::{FastToken, TokenStore},
+    client::policy::SaslPolicy,
     connect::ServerConnector,
     error::{AuthError, Error, ProtocolError},
+    stanzastream::{InlineBinding, ResumptionStore},
     xmlstream::{
         xmpp::XmppStreamElement, InitiatingStream, ReadError, StreamHeader, Timeouts, XmppStream,
     },
 };
 
+/// Settings controlling how a [`Client`][`crate::Client`] logs into its
+/// account.
+#[derive(Debug, Clone)]
+pub struct LoginSettings {
+    /// Information about this client, sent to the server when
+    /// authenticating using XEP-0388 (Extensible SASL Profile).
+    ///
+    /// The `id` should be stable across restarts of the same installation,
+    /// as servers may use it to identify the client.
+    pub user_agent: UserAgent,
+
+    /// Whether to use XEP-0388 (Extensible SASL Profile) with XEP-0386
+    /// (Bind 2) when the server offers it.
+    ///
+    /// This authenticates and binds a resource in a single round trip.
+    pub sasl2: bool,
+
+    /// Tag sent to the server when binding using XEP-0386 (Bind 2).
+    ///
+    /// If unset, the resource of the configured JID is used if present.
+    pub bind_tag: Option<String>,
+
+    /// Whether to enable XEP-0280 (Message Carbons) inline when binding
+    /// using XEP-0386 (Bind 2).
+    pub enable_carbons: bool,
+
+    /// Where to keep XEP-0484 (FAST) tokens.
+    ///
+    /// If set, a token is requested when authenticating with the password,
+    /// and used instead of the password on the next login. If the token is
+    /// rejected, the password is used again.
+    pub token_store: Option<Arc<dyn TokenStore>>,
+
+    /// Which SASL mechanisms may be used.
+    pub sasl_policy: SaslPolicy,
+
+    /// Where to keep the XEP-0198 (Stream Management) state.
+    ///
+    /// If set, the stream left by a previous run is resumed when starting,
+    /// if the server still keeps it.
+    pub resumption_store: Option<Arc<dyn ResumptionStore>>,
+}
+
+impl Default for LoginSettings {
+    fn default() -> Self {
+        Self {
+            user_agent: UserAgent {
+                id: uuid::Uuid::new_v4(),
+                software: Some(String::from("tokio-xmpp")),
+                device: None,
+            },
+            sasl2: true,
+            bind_tag: None,
+            enable_carbons: false,
+            token_store: None,
+            sasl_policy: SaslPolicy::default(),
+            resumption_store: None,
+        }
+    }
+}
+
+impl LoginSettings {
+    /// Create new login settings with a random user agent id.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the stable user agent id of this client installation.
+    pub fn with_user_agent_id(mut self, id: uuid::Uuid) -> Self {
+        self.user_agent.id = id;
+        self
+    }
+
+    /// Set the name of the client software.
+    pub fn with_software<S: Into<String>>(mut self, software: S) -> Self {
+        self.user_agent.software = Some(software.into());
+        self
+    }
+
+    /// Set the name of the device this client runs on.
+    pub fn with_device<S: Into<String>>(mut self, device: S) -> Self {
+        self.user_agent.device = Some(device.into());
+        self
+    }
+
+    /// Enable or disable the use of XEP-0388 (Extensible SASL Profile).
+    pub fn with_sasl2(mut self, sasl2: bool) -> Self {
+        self.sasl2 = sasl2;
+        self
+    }
+
+    /// Set the tag sent when binding using XEP-0386 (Bind 2).
+    pub fn with_bind_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.bind_tag = Some(tag.into());
+        self
+    }
+
+    /// Enable or disable XEP-0280 (Message Carbons) inline while binding.
+    pub fn with_carbons(mut self, enable_carbons: bool) -> Self {
+        self.enable_carbons = enable_carbons;
+        self
+    }
+
+    /// Set the store for XEP-0484 (FAST) tokens.
+    pub fn with_token_store<T: TokenStore>(mut self, token_store: T) -> Self {
+        self.token_store = Some(Arc::new(token_store));
+        self
+    }
+
+    /// Set the policy restricting which SASL mechanisms may be used.
+    pub fn with_sasl_policy(mut self, sasl_policy: SaslPolicy) -> Self {
+        self.sasl_policy = sasl_policy;
+        self
+    }
+
+    /// Set the store for the XEP-0198 (Stream Management) state.
+    pub fn with_resumption_store<R: ResumptionStore>(mut self, resumption_store: R) -> Self {
+        self.resumption_store = Some(Arc::new(resumption_store));
+        self
+    }
+}
+
+/// Pick the channel binding to use, out of the ones supported by the
+/// connection by order of preference.
+///
+/// The server advertises the types it supports using XEP-0440 (SASL
+/// Channel-Binding Type Capability), otherwise only tls-unique can be
+/// assumed.
+fn select_channel_binding(
+    channel_bindings: &[ChannelBinding],
+    remote_mechs: &[String],
+    sasl_cb: Option<&SaslChannelBinding>,
+) -> ChannelBinding {
+    if channel_bindings.is_empty() {
+        return ChannelBinding::None;
+    }
+    if !remote_mechs.iter().any(|mech| mech.ends_with("-PLUS")) {
+        // Let the server detect downgrades of the mechanism list.
+        return ChannelBinding::Unsupported;
+    }
+    let supported = |channel_binding: &&ChannelBinding| match sasl_cb {
+        Some(sasl_cb) => sasl_cb.types.iter().any(|type_| {
+            channel_binding.name()
+                == Some(match type_ {
+                    sasl_cb::Type::TlsUnique => "tls-unique",
+                    sasl_cb::Type::TlsServerEndPoint => "tls-server-end-point",
+                    sasl_cb::Type::TlsExporter => "tls-exporter",
+                    sasl_cb::Type::TlsUniqueForTelnet => return false,
+                })
+        }),
+        None => matches!(channel_binding, ChannelBinding::TlsUnique(_)),
+    };
+    channel_bindings
+        .iter()
+        .find(supported)
+        .cloned()
+        .unwrap_or(ChannelBinding::None)
+}
+
+/// What is known about the connection, to pick a mechanism.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Transport {
+    /// A client certificate has been presented to the server.
+    pub(crate) client_certificate: bool,
+
+    /// The connection is encrypted.
+    pub(crate) encrypted: bool,
+}
+
+type MechanismCtor<'a> = Box<dyn Fn() -> Option<Box<dyn Mechanism + Send + Sync>> + Send + 'a>;
+
+fn boxed<M: Mechanism + Send + Sync + 'static>(
+    mechanism: Result<M, MechanismError>,
+) -> Option<Box<dyn Mechanism + Send + Sync>> {
+    Some(Box::new(mechanism.ok()?))
+}
+
+/// Pick the first of our mechanisms which is also offered by the remote
+/// and allowed by the policy.
+///
+/// EXTERNAL is preferred if a client certificate has been presented to the
+/// server, then the custom mechanisms of the policy.  The SCRAM-*-PLUS
+/// mechanisms are picked if `creds` contain channel binding data.
+fn select_mechanism<'a>(
+    remote_mechs: impl IntoIterator<Item = &'a String>,
+    creds: &Credentials,
+    transport: Transport,
+    policy: &SaslPolicy,
+) -> Option<Box<dyn Mechanism + Send + Sync>> {
+    let mut local_mechs: Vec<MechanismCtor> = Vec::new();
+    if transport.client_certificate {
+        // Let the server derive our identity from the certificate.
+        local_mechs.push(Box::new(|| Some(Box::new(External::new()))));
+    }
+    for factory in &policy.custom {
+        local_mechs.push(Box::new(|| factory(creds)));
+    }
+    // Without channel binding, in case the server doesn’t offer the -PLUS
+    // variant of the mechanisms we support.
+    let unbound = || creds.clone().with_channel_binding(ChannelBinding::None);
+    let builtin: [MechanismCtor; 10] = [
+        Box::new(|| boxed(Scram::<Sha3_512>::from_credentials(creds.clone()))),
+        Box::new(|| boxed(Scram::<Sha512>::from_credentials(creds.clone()))),
+        Box::new(|| boxed(Scram::<Sha256>::from_credentials(creds.clone()))),
+        Box::new(|| boxed(Scram::<Sha1>::from_credentials(creds.clone()))),
+        Box::new(|| boxed(Scram::<Sha3_512>::from_credentials(unbound()))),
+        Box::new(|| boxed(Scram::<Sha512>::from_credentials(unbound()))),
+        Box::new(|| boxed(Scram::<Sha256>::from_credentials(unbound()))),
+        Box::new(|| boxed(Scram::<Sha1>::from_credentials(unbound()))),
+        Box::new(|| boxed(Plain::from_credentials(creds.clone()))),
+        Box::new(|| Some(Box::new(Anonymous::new()))),
+    ];
+    local_mechs.extend(builtin);
+
+    let remote_mechs: HashSet<&str> = remote_mechs.into_iter().map(String::as_str).collect();
+
+    let mechanism = local_mechs
+        .into_iter()
+        .filter_map(|local_mech| local_mech())
+        .find(|mechanism| {
+            remote_mechs.contains(mechanism.name())
+                && policy.allows(mechanism.name(), transport.encrypted)
+        });
+    if mechanism.is_none() {
+        log::warn!(
+            "None of the SASL mechanisms offered by the server is allowed: {:?}",
+            remote_mechs
+        );
+    }
+    mechanism
+}
+
+fn unexpected_element(el: XmppStreamElement) -> Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "unexpected stream element during SASL negotiation: {:?}",
+            el
+        ),
+    )
+    .into()
+}
+
 pub async fn auth<S: AsyncBufRead + AsyncWrite + Unpin>(
     mut stream: XmppStream<S>,
     sasl_mechanisms: &SaslMechanisms,
     creds: Credentials,
+    transport: Transport,
+    policy: &SaslPolicy,
 ) -> Result<InitiatingStream<S>, Error> {
-    let local_mechs: Vec<Box<dyn Fn() -> Box<dyn Mechanism + Send + Sync> + Send>> = vec![
-        Box::new(|| Box::new(Scram::<Sha256>::from_credentials(creds.clone()).unwrap())),
-        Box::new(|| Box::new(Scram::<Sha1>::from_credentials(creds.clone()).unwrap())),
-        Box::new(|| Box::new(Plain::from_credentials(creds.clone()).unwrap())),
-        Box::new(|| Box::new(Anonymous::new())),
-    ];
+    // Only the mechanisms known to xmpp-parsers can be used here.
+    let remote_mechs = sasl_mechanisms
+        .mechanisms
+        .iter()
+        .filter(|name| XMPPMechanism::from_str(name).is_ok());
+    let Some(mut mechanism) = select_mechanism(remote_mechs, &creds, transport, policy) else {
+        return Err(AuthError::NoMechanism.into());
+    };
 
-    let remote_mechs: HashSet<String> = sasl_mechanisms.mechanisms.iter().cloned().collect();
-
-    for local_mech in local_mechs {
-        let mut mechanism = local_mech();
-        if remote_mechs.contains(mechanism.name()) {
-            let initial = mechanism.initial();
-            let mechanism_name =
-                XMPPMechanism::from_str(mechanism.name()).map_err(ProtocolError::Parsers)?;
-
-            stream
-                .send(&XmppStreamElement::Sasl(Nonza::Auth(Auth {
-                    mechanism: mechanism_name,
-                    data: initial,
-                })))
-                .await?;
-
-            loop {
-                match stream.next().await {
-                    Some(Ok(XmppStreamElement::Sasl(sasl))) => match sasl {
-                        Nonza::Challenge(challenge) => {
-                            let response = mechanism
-                                .response(&challenge.data)
-                                .map_err(|e| AuthError::Sasl(e))?;
-
-                            // Send response and loop
-                            stream
-                                .send(&XmppStreamElement::Sasl(Nonza::Response(Response {
-                                    data: response,
-                                })))
-                                .await?;
-                        }
-                        Nonza::Success(_) => return Ok(stream.initiate_reset()),
-                        Nonza::Failure(failure) => {
-                            return Err(Error::Auth(AuthError::Fail(failure.defined_condition)));
-                        }
-                        _ => {
-                            // Ignore?!
-                        }
-                    },
-                    Some(Ok(el)) => {
-                        return Err(io::Error::new(
+    let initial = mechanism.initial();
+    let mechanism_name =
+        XMPPMechanism::from_str(mechanism.name()).map_err(ProtocolError::Parsers)?;
+
+    stream
+        .send(&XmppStreamElement::Sasl(Nonza::Auth(Auth {
+            mechanism: mechanism_name,
+            data: initial,
+        })))
+        .await?;
+
+    loop {
+        match stream.next().await {
+            Some(Ok(XmppStreamElement::Sasl(sasl))) => match sasl {
+                Nonza::Challenge(challenge) => {
+                    let response = mechanism
+                        .response(&challenge.data)
+                        .map_err(|e| AuthError::Sasl(e))?;
+
+                    // Send response and loop
+                    stream
+                        .send(&XmppStreamElement::Sasl(Nonza::Response(Response {
+                            data: response,
+                        })))
+                        .await?;
+                }
+                Nonza::Success(_) => return Ok(stream.initiate_reset()),
+                Nonza::Failure(failure) => {
+                    return Err(Error::Auth(AuthError::Fail(failure.defined_condition)));
+                }
+                _ => {
+                    // Ignore?!
+                }
+            },
+            Some(Ok(el)) => return Err(unexpected_element(el)),
+            Some(Err(ReadError::HardError(e))) => return Err(e.into()),
+            Some(Err(ReadError::ParseError(e))) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e).into())
+            }
+            Some(Err(ReadError::SoftTimeout)) => {
+                // We cannot do anything about soft timeouts here...
+            }
+            Some(Err(ReadError::StreamFooterReceived)) | None => return Err(Error::Disconnected),
+        }
+    }
+}
+
+/// Authenticate using XEP-0388 (Extensible SASL Profile) and bind a
+/// resource inline using XEP-0386 (Bind 2).
+///
+/// Unlike [`auth`], this does not require a stream reset: the stream is
+/// ready to exchange stanzas once this returns.
+///
+/// `payloads` are sent along the authentication request. The payloads of
+/// the server's `<success/>` are returned along with the binding.
+pub async fn auth_sasl2<S: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<S>,
+    mut mechanism: Box<dyn Mechanism + Send + Sync>,
+    mut payloads: Vec<Element>,
+    bind_feature: &BindFeature,
+    jid: &Jid,
+    settings: &LoginSettings,
+) -> Result<(InlineBinding, Vec<Element>), Error> {
+    let mut bind_payloads: Vec<Element> = Vec::new();
+    let inline_sm = bind_feature.inline_features.iter().any(|f| f == ns::SM);
+    if inline_sm {
+        bind_payloads.push(
+            sm::Enable {
+                max: None,
+                resume: true,
+            }
+            .into(),
+        );
+    }
+    if settings.enable_carbons
+        && bind_feature
+            .inline_features
+            .iter()
+            .any(|f| f == ns::CARBONS)
+    {
+        bind_payloads.push(carbons::Enable.into());
+    }
+    let tag = settings
+        .bind_tag
+        .clone()
+        .or_else(|| jid.resource().map(|r| r.as_str().to_owned()));
+
+    payloads.push(
+        BindQuery {
+            tag,
+            payloads: bind_payloads,
+        }
+        .into(),
+    );
+
+    let initial = mechanism.initial();
+    stream
+        .send(&XmppStreamElement::Sasl2(sasl2::Nonza::Authenticate(
+            Authenticate {
+                mechanism: mechanism.name().to_owned(),
+                initial_response: Some(initial),
+                user_agent: settings.user_agent.clone(),
+                payloads,
+            },
+        )))
+        .await?;
+
+    loop {
+        match stream.next().await {
+            Some(Ok(XmppStreamElement::Sasl2(sasl2))) => match sasl2 {
+                sasl2::Nonza::Challenge(challenge) => {
+                    let response = mechanism
+                        .response(&challenge.sasl_data)
+                        .map_err(AuthError::Sasl)?;
+
+                    stream
+                        .send(&XmppStreamElement::Sasl2(sasl2::Nonza::Response(
+                            sasl2::Response {
+                                sasl_data: response,
+                            },
+                        )))
+                        .await?;
+                }
+                sasl2::Nonza::Success(success) => {
+                    if let Some(data) = success.additional_data {
+                        mechanism.success(&data).map_err(AuthError::Sasl)?;
+                    }
+                    let jid = success.authorization_identifier.try_into_full().map_err(|jid| {
+                        io::Error::new(
                             io::ErrorKind::InvalidData,
-                            format!(
-                                "unexpected stream element during SASL negotiation: {:?}",
-                                el
-                            ),
+                            format!("SASL2 authorization identifier {jid} is not a full JID despite inline binding"),
                         )
-                        .into())
-                    }
-                    Some(Err(ReadError::HardError(e))) => return Err(e.into()),
-                    Some(Err(ReadError::ParseError(e))) => {
-                        return Err(io::Error::new(io::ErrorKind::InvalidData, e).into())
+                    })?;
+                    let mut sm_enabled = None;
+                    let mut payloads = Vec::new();
+                    for payload in success.payloads {
+                        match Bound::try_from(payload) {
+                            Ok(bound) => {
+                                sm_enabled = bound
+                                    .payloads
+                                    .into_iter()
+                                    .find_map(|payload| sm::Enabled::try_from(payload).ok())
+                            }
+                            Err(xso::error::FromElementError::Mismatch(payload)) => {
+                                payloads.push(payload)
+                            }
+                            Err(e) => log::warn!("Ignoring invalid <bound/> element: {e}"),
+                        }
                     }
-                    Some(Err(ReadError::SoftTimeout)) => {
-                        // We cannot do anything about soft timeouts here...
+                    if inline_sm && sm_enabled.is_none() {
+                        log::debug!("Server did not enable stream management inline, proceeding without it.");
                     }
-                    Some(Err(ReadError::StreamFooterReceived)) | None => {
-                        return Err(Error::Disconnected)
+                    return Ok((InlineBinding { jid, sm_enabled }, payloads));
+                }
+                sasl2::Nonza::Failure(failure) => {
+                    let condition = failure
+                        .payloads
+                        .into_iter()
+                        .find_map(|payload| DefinedCondition::try_from(payload).ok())
+                        .unwrap_or(DefinedCondition::NotAuthorized);
+                    return Err(Error::Auth(AuthError::Fail(condition)));
+                }
+                sasl2::Nonza::Continue(cont) => {
+                    log::warn!(
+                        "Server requested additional SASL2 tasks {:?}, which are not supported. Aborting.",
+                        cont.tasks
+                    );
+                    stream
+                        .send(&XmppStreamElement::Sasl2(sasl2::Nonza::Abort(
+                            sasl2::Abort {
+                                text: None,
+                                payloads: vec![],
+                            },
+                        )))
+                        .await?;
+                    return Err(Error::Auth(AuthError::Fail(DefinedCondition::Aborted)));
+                }
+                el => return Err(unexpected_element(XmppStreamElement::Sasl2(el))),
+            },
+            Some(Ok(el)) => return Err(unexpected_element(el)),
+            Some(Err(ReadError::HardError(e))) => return Err(e.into()),
+            Some(Err(ReadError::ParseError(e))) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e).into())
+            }
+            Some(Err(ReadError::SoftTimeout)) => {
+                // We cannot do anything about soft timeouts here...
+            }
+            Some(Err(ReadError::StreamFooterReceived)) | None => return Err(Error::Disconnected),
+        }
+    }
+}
+
+/// Construct the HT-* mechanism named `name`, if we support it with the
+/// given channel binding.
+fn hashed_token(
+    name: &str,
+    username: &str,
+    token: &str,
+    channel_binding: &ChannelBinding,
+) -> Option<HashedToken<Sha256>> {
+    [channel_binding.clone(), ChannelBinding::None]
+        .into_iter()
+        .map(|cb| HashedToken::<Sha256>::new(username, token, cb))
+        .find(|mechanism| mechanism.name() == name)
+}
+
+/// Store the token found in the payloads of a SASL2 `<success/>`, if any.
+fn store_token(store: &dyn TokenStore, account: &BareJid, mechanism: &str, payloads: Vec<Element>) {
+    let Some(token) = payloads
+        .into_iter()
+        .find_map(|payload| Token::try_from(payload).ok())
+    else {
+        log::debug!("Server did not issue a FAST token.");
+        return;
+    };
+    if let Err(e) = store.store(account, &FastToken::new(mechanism.to_owned(), token)) {
+        log::warn!("Failed to store FAST token: {e}");
+    }
+}
+
+/// Authenticate using SASL2 and Bind 2, using a XEP-0484 (FAST) token if
+/// one is available and falling back to the password otherwise.
+async fn sasl2_login<S: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<S>,
+    authentication: &Authentication,
+    bind_feature: &BindFeature,
+    creds: Credentials,
+    transport: Transport,
+    jid: &Jid,
+    settings: &LoginSettings,
+) -> Result<InlineBinding, Error> {
+    let account = jid.to_bare();
+    let username = jid.node().unwrap().as_str();
+    let fast = authentication.inline.as_ref().and_then(|inline| {
+        inline
+            .payloads
+            .iter()
+            .find_map(|payload| FastQuery::try_from(payload.clone()).ok())
+    });
+    let fast_store = settings.token_store.as_deref().zip(fast.as_ref());
+
+    if let Some((store, fast)) = fast_store {
+        let token = match store.load(&account) {
+            Ok(token) => token.filter(|token| !token.is_expired()),
+            Err(e) => {
+                log::warn!("Failed to load FAST token: {e}");
+                None
+            }
+        };
+        let token = token.and_then(|token| {
+            if !fast.mechanisms.iter().any(|m| m.0 == token.mechanism)
+                || !settings
+                    .sasl_policy
+                    .allows(&token.mechanism, transport.encrypted)
+            {
+                return None;
+            }
+            let mechanism = hashed_token(
+                &token.mechanism,
+                username,
+                &token.token,
+                &creds.channel_binding,
+            )?;
+            Some((token, mechanism))
+        });
+        if let Some((mut token, mechanism)) = token {
+            token.count = token.count.wrapping_add(1);
+            if let Err(e) = store.store(&account, &token) {
+                log::warn!("Failed to update FAST token count: {e}");
+            }
+            let payloads = vec![
+                FastResponse {
+                    count: token.count,
+                    invalidate: false,
+                }
+                .into(),
+                RequestToken {
+                    mechanism: token.mechanism.clone(),
+                }
+                .into(),
+            ];
+            match auth_sasl2(
+                stream,
+                Box::new(mechanism),
+                payloads,
+                bind_feature,
+                jid,
+                settings,
+            )
+            .await
+            {
+                Ok((binding, payloads)) => {
+                    store_token(store, &account, &token.mechanism, payloads);
+                    return Ok(binding);
+                }
+                Err(Error::Auth(AuthError::Fail(condition))) => {
+                    log::info!("FAST token was rejected ({condition:?}), falling back to password authentication.");
+                    if let Err(e) = store.remove(&account) {
+                        log::warn!("Failed to remove FAST token: {e}");
                     }
                 }
+                Err(e) => return Err(e),
             }
         }
     }
 
-    Err(AuthError::NoMechanism.into())
+    let Some(mechanism) = select_mechanism(
+        &authentication.mechanisms,
+        &creds,
+        transport,
+        &settings.sasl_policy,
+    ) else {
+        return Err(AuthError::NoMechanism.into());
+    };
+    // Request a token for the best HT-* mechanism we can use next time.
+    let requested = fast_store.and_then(|(_, fast)| {
+        fast.mechanisms
+            .iter()
+            .map(|m| m.0.as_str())
+            .filter(|name| hashed_token(name, username, "", &creds.channel_binding).is_some())
+            .filter(|name| settings.sasl_policy.allows(name, transport.encrypted))
+            .max_by_key(|name| !name.ends_with("-NONE"))
+            .map(str::to_owned)
+    });
+    let payloads = match requested {
+        Some(ref mechanism) => vec![RequestToken {
+            mechanism: mechanism.clone(),
+        }
+        .into()],
+        None => vec![],
+    };
+    let (binding, payloads) =
+        auth_sasl2(stream, mechanism, payloads, bind_feature, jid, settings).await?;
+    if let (Some((store, _)), Some(mechanism)) = (fast_store, requested) {
+        store_token(store, &account, &mechanism, payloads);
+    }
+    Ok(binding)
 }
 
-/// Authenticate to an XMPP server, but do not bind a resource.
+/// Authenticate to an XMPP server.
+///
+/// If the server supports XEP-0388 (Extensible SASL Profile) with XEP-0386
+/// (Bind 2) and `inline_bind` is true, a resource is bound as part of the
+/// authentication and returned alongside the stream. Otherwise, the stream
+/// is authenticated but not bound.
+///
+/// `inline_bind` should be false if a previous session is to be resumed
+/// using XEP-0198 (Stream Management), as binding a new resource would
+/// prevent that.
 pub async fn client_auth<C: ServerConnector>(
     server: C,
     jid: Jid,
     password: String,
     timeouts: Timeouts,
-) -> Result<(StreamFeatures, XmppStream<C::Stream>), Error> {
+    settings: &LoginSettings,
+    inline_bind: bool,
+) -> Result<(StreamFeatures, XmppStream<C::Stream>, Option<InlineBinding>), Error> {
     let username = jid.node().unwrap().as_str();
     let password = password;
 
-    let (xmpp_stream, channel_binding) = server.connect(&jid, ns::JABBER_CLIENT, timeouts).await?;
-    let (features, xmpp_stream) = xmpp_stream.recv_features().await?;
+    let transport = Transport {
+        client_certificate: server.has_client_certificate(),
+        encrypted: server.is_encrypted(),
+    };
+    let (xmpp_stream, channel_bindings) = server.connect(&jid, ns::JABBER_CLIENT, timeouts).await?;
+    let (features, mut xmpp_stream) = xmpp_stream.recv_features().await?;
 
     let creds = Credentials::default()
         .with_username(username)
-        .with_password(password)
-        .with_channel_binding(channel_binding);
+        .with_password(password);
+
+    // Only bind inline if legacy SASL is not an option, losing the
+    // previous session is better than not connecting at all.
+    let use_inline_bind = inline_bind || features.sasl_mechanisms.mechanisms.is_empty();
+    if let Some(authentication) = features.sasl2.as_ref().filter(|_| settings.sasl2) {
+        if let Some(bind_feature) = authentication
+            .inline
+            .as_ref()
+            .and_then(|inline| inline.bind2.as_ref())
+            .filter(|_| use_inline_bind)
+        {
+            let channel_binding = select_channel_binding(
+                &channel_bindings,
+                &authentication.mechanisms,
+                features.sasl_cb.as_ref(),
+            );
+            let binding = sasl2_login(
+                &mut xmpp_stream,
+                authentication,
+                bind_feature,
+                creds.with_channel_binding(channel_binding),
+                transport,
+                &jid,
+                settings,
+            )
+            .await?;
+            return Ok((features, xmpp_stream, Some(binding)));
+        }
+    }
+
+    let channel_binding = select_channel_binding(
+        &channel_bindings,
+        &features.sasl_mechanisms.mechanisms,
+        features.sasl_cb.as_ref(),
+    );
+    let creds = creds.with_channel_binding(channel_binding);
+
     // Authenticated (unspecified) stream
-    let stream = auth(xmpp_stream, &features.sasl_mechanisms, creds).await?;
+    let stream = auth(
+        xmpp_stream,
+        &features.sasl_mechanisms,
+        creds,
+        transport,
+        &settings.sasl_policy,
+    )
+    .await?;
     let stream = stream
         .send_header(StreamHeader {
             to: Some(Cow::Borrowed(jid.domain().as_str())),
@@ -128,5 +716,6 @@ pub async fn client_auth<C: ServerConnector>(
             id: None,
         })
         .await?;
-    Ok(stream.recv_features().await?)
+    let (features, stream) = stream.recv_features().await?;
+    Ok((features, stream, None))
 }
@@ -25,11 +25,16 @@ use crate::connect::TcpServerConnector;
 #[cfg(feature = "websocket")]
 use crate::connect::WebSocketServerConnector;
 
+mod fast;
 mod iq;
 pub(crate) mod login;
+mod policy;
 mod stream;
 
+pub use fast::{FastToken, FileTokenStore, MemoryTokenStore, TokenStore};
 pub use iq::{IqFailure, IqRequest, IqResponse, IqResponseToken};
+pub use login::LoginSettings;
+pub use policy::{MechanismFactory, SaslPolicy};
 
 /// XMPP client connection and state
 ///
@@ -184,9 +189,30 @@ impl Client {
         password: P,
         connector: C,
         timeouts: Timeouts,
+    ) -> Self {
+        Self::new_with_settings(jid, password, connector, timeouts, LoginSettings::default())
+    }
+
+    /// Start a new client with the given [`LoginSettings`].
+    ///
+    /// The settings control e.g. the user agent sent when authenticating
+    /// and which features are enabled while binding.
+    pub fn new_with_settings<J: Into<Jid>, P: Into<String>, C: ServerConnector>(
+        jid: J,
+        password: P,
+        connector: C,
+        timeouts: Timeouts,
+        settings: LoginSettings,
     ) -> Self {
         Self {
-            stream: StanzaStream::new_c2s(connector, jid.into(), password.into(), timeouts, 16),
+            stream: StanzaStream::new_c2s_with_settings(
+                connector,
+                jid.into(),
+                password.into(),
+                settings,
+                timeouts,
+                16,
+            ),
             bound_jid: None,
             features: None,
             iq_response_tracker: iq::IqResponseTracker::new(),
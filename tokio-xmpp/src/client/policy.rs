@@ -0,0 +1,150 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Restrictions on the SASL mechanisms used to log in.
+
+use alloc::sync::Arc;
+use core::fmt;
+
+use sasl::client::Mechanism;
+use sasl::common::Credentials;
+
+/// Creates a custom mechanism for the given credentials, or returns `None`
+/// if it can’t be used with them.
+pub type MechanismFactory =
+    Arc<dyn Fn(&Credentials) -> Option<Box<dyn Mechanism + Send + Sync>> + Send + Sync>;
+
+/// Controls which SASL mechanisms a [`Client`][`crate::Client`] may use.
+///
+/// By default, every built-in mechanism offered by the server may be used,
+/// preferring the strongest ones.
+#[derive(Clone, Default)]
+pub struct SaslPolicy {
+    /// Names of the mechanisms which must never be used, such as
+    /// `"ANONYMOUS"`.
+    pub forbidden: Vec<String>,
+
+    /// Only authenticate using mechanisms bound to the TLS session: the
+    /// SCRAM-*-PLUS and non-NONE HT-* mechanisms, as well as EXTERNAL
+    /// with a client certificate.
+    ///
+    /// This prevents an attacker able to impersonate the server from
+    /// downgrading to weaker mechanisms.
+    pub require_channel_binding: bool,
+
+    /// Never send PLAIN on connections which aren’t encrypted, see
+    /// [`ServerConnector::is_encrypted`][`crate::connect::ServerConnector::is_encrypted`].
+    pub forbid_plain_unencrypted: bool,
+
+    /// Additional mechanisms, preferred over the built-in ones.
+    pub custom: Vec<MechanismFactory>,
+}
+
+impl fmt::Debug for SaslPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("SaslPolicy")
+            .field("forbidden", &self.forbidden)
+            .field("require_channel_binding", &self.require_channel_binding)
+            .field("forbid_plain_unencrypted", &self.forbid_plain_unencrypted)
+            .field("custom", &self.custom.len())
+            .finish()
+    }
+}
+
+impl SaslPolicy {
+    /// Create a policy allowing every mechanism.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never use the mechanism named `mechanism`.
+    pub fn forbid<S: Into<String>>(mut self, mechanism: S) -> Self {
+        self.forbidden.push(mechanism.into());
+        self
+    }
+
+    /// Require or not a mechanism bound to the TLS session.
+    pub fn with_channel_binding_required(mut self, required: bool) -> Self {
+        self.require_channel_binding = required;
+        self
+    }
+
+    /// Allow or not PLAIN on connections which aren’t encrypted.
+    pub fn with_plain_unencrypted_forbidden(mut self, forbidden: bool) -> Self {
+        self.forbid_plain_unencrypted = forbidden;
+        self
+    }
+
+    /// Add a custom mechanism, created from the credentials using
+    /// [`Mechanism::from_credentials`].
+    pub fn with_mechanism<M: Mechanism + Send + Sync + 'static>(self) -> Self {
+        self.with_mechanism_factory(|creds: &Credentials| {
+            M::from_credentials(creds.clone())
+                .ok()
+                .map(|mechanism| Box::new(mechanism) as Box<dyn Mechanism + Send + Sync>)
+        })
+    }
+
+    /// Add a custom mechanism, created from the credentials by `factory`.
+    pub fn with_mechanism_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&Credentials) -> Option<Box<dyn Mechanism + Send + Sync>> + Send + Sync + 'static,
+    {
+        self.custom.push(Arc::new(factory));
+        self
+    }
+
+    /// Whether the mechanism named `mechanism` may be used, on a
+    /// connection which is `encrypted` or not.
+    pub fn allows(&self, mechanism: &str, encrypted: bool) -> bool {
+        if self
+            .forbidden
+            .iter()
+            .any(|forbidden| forbidden == mechanism)
+        {
+            return false;
+        }
+        if self.forbid_plain_unencrypted && !encrypted && mechanism == "PLAIN" {
+            return false;
+        }
+        if self.require_channel_binding && !is_channel_bound(mechanism) {
+            return false;
+        }
+        true
+    }
+}
+
+fn is_channel_bound(mechanism: &str) -> bool {
+    mechanism.ends_with("-PLUS")
+        || (mechanism.starts_with("HT-") && !mechanism.ends_with("-NONE"))
+        || mechanism == "EXTERNAL"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_allows() {
+        let policy = SaslPolicy::new();
+        assert!(policy.allows("PLAIN", false));
+        assert!(policy.allows("ANONYMOUS", true));
+
+        let policy = SaslPolicy::new()
+            .forbid("ANONYMOUS")
+            .with_plain_unencrypted_forbidden(true);
+        assert!(!policy.allows("PLAIN", false));
+        assert!(policy.allows("PLAIN", true));
+        assert!(!policy.allows("ANONYMOUS", true));
+
+        let policy = SaslPolicy::new().with_channel_binding_required(true);
+        assert!(policy.allows("SCRAM-SHA-256-PLUS", true));
+        assert!(policy.allows("HT-SHA-256-EXPR", true));
+        assert!(!policy.allows("HT-SHA-256-NONE", true));
+        assert!(!policy.allows("SCRAM-SHA-256", true));
+        assert!(!policy.allows("PLAIN", true));
+    }
+}
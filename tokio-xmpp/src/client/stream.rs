@@ -53,10 +53,17 @@ impl Stream for Client {
                         resumed: false,
                     })
                 }
-                Some(StanzaStreamEvent::Stream(StreamEvent::Resumed)) => Some(Event::Online {
-                    bound_jid: self.bound_jid.as_ref().unwrap().clone(),
-                    resumed: true,
-                }),
+                Some(StanzaStreamEvent::Stream(StreamEvent::Resumed {
+                    bound_jid,
+                    features,
+                })) => {
+                    self.features = Some(features);
+                    self.bound_jid = Some(bound_jid.clone());
+                    Some(Event::Online {
+                        bound_jid,
+                        resumed: true,
+                    })
+                }
                 Some(StanzaStreamEvent::Stream(StreamEvent::Suspended)) => continue,
             });
         }
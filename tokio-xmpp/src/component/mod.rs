@@ -84,13 +84,13 @@ impl Component<WebSocketServerConnector> {
     pub async fn new_websocket(
         jid: &str,
         password: &str,
-        dns_config: DnsConfig,
+        host_addr: String,
         timeouts: Timeouts,
     ) -> Result<Self, Error> {
         Component::new_with_connector(
             jid,
             password,
-            WebSocketServerConnector::from(dns_config),
+            WebSocketServerConnector::from(host_addr),
             timeouts,
         )
         .await
@@ -0,0 +1,684 @@
+//! `bosh::BoshServerConnector` provides a `ServerConnector` for BOSH (XEP-0124/XEP-0206) connections
+//!
+//! Each `<body/>` received from the connection manager is unwrapped, and
+//! its children are handed to the XML stream as if they had been received
+//! on a regular TCP connection, after a synthesized stream header.  In the
+//! other direction, the bytes written by the XML stream are parsed back
+//! into elements, which are then sent in as few requests as possible.
+
+use alloc::collections::VecDeque;
+use core::{error::Error as StdError, fmt, time::Duration};
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures::{
+    future::{BoxFuture, FutureExt},
+    stream::{FuturesOrdered, StreamExt},
+};
+use rand::Rng;
+use rxml::{error::EndOrError, Parse, RawEvent, RawParser};
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf},
+    sync::mpsc,
+};
+use xmpp_parsers::{
+    bosh::{Body, Type},
+    jid::{BareJid, Jid},
+    minidom::{tree_builder::TreeBuilder, Element},
+    ns,
+};
+
+use crate::{
+    connect::{ChannelBinding, ServerConnector, ServerConnectorError},
+    xmlstream::{initiate_stream, PendingFeaturesRecv, StreamHeader, Timeouts},
+    Error,
+};
+
+/// The BOSH version we implement.
+const BOSH_VERSION: &str = "1.6";
+
+/// How much longer than `wait` we give the connection manager to answer
+/// before considering a request lost.
+const REQUEST_GRACE: Duration = Duration::from_secs(10);
+
+/// Connect via BOSH to an XMPP server
+///
+/// This is useful when only HTTP is allowed out of the network, for
+/// instance behind some corporate proxies.
+#[derive(Debug, Clone)]
+pub struct BoshServerConnector {
+    url: String,
+    hold: u32,
+    wait: u32,
+    client: reqwest::Client,
+}
+
+impl From<String> for BoshServerConnector {
+    fn from(url: String) -> Self {
+        Self::new(url)
+    }
+}
+
+impl BoshServerConnector {
+    /// Connect to the connection manager at `url`, such as
+    /// `https://example.org/http-bind`.
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            hold: 1,
+            wait: 60,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Set the maximum number of requests the connection manager may keep
+    /// waiting, 1 by default.
+    ///
+    /// Setting this to 0 makes the session a polling one, which is only
+    /// useful if the proxy forbids long-lived requests.
+    pub fn with_hold(mut self, hold: u32) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// Set the longest time in seconds the connection manager may wait
+    /// before answering a request, 60 by default.
+    pub fn with_wait(mut self, wait: u32) -> Self {
+        self.wait = wait;
+        self
+    }
+}
+
+impl ServerConnector for BoshServerConnector {
+    type Stream = BoshStream;
+
+    async fn connect(
+        &self,
+        jid: &Jid,
+        ns: &'static str,
+        timeouts: Timeouts,
+    ) -> Result<(PendingFeaturesRecv<Self::Stream>, Vec<ChannelBinding>), Error> {
+        let domain = jid.domain();
+        let mut session = Session {
+            client: self.client.clone(),
+            url: self.url.clone(),
+            sid: None,
+            // The rid must stay below 2^53 for the whole session.
+            rid: rand::thread_rng().gen_range(1..(1 << 52)),
+            hold: self.hold,
+            requests: self.hold + 1,
+            wait: self.wait,
+            polling: 0,
+        };
+
+        let response = session
+            .request(Body {
+                content: Some(String::from("text/xml; charset=utf-8")),
+                to: Some(Jid::from(BareJid::from_parts(None, domain))),
+                hold: Some(self.hold),
+                wait: Some(self.wait),
+                ver: Some(String::from(BOSH_VERSION)),
+                xmpp_version: Some(String::from("1.0")),
+                ..Default::default()
+            })
+            .await?;
+        if response.type_.is_some() {
+            return Err(BoshError::Terminated(response.condition).into());
+        }
+        let Some(sid) = response.sid.clone() else {
+            return Err(BoshError::MissingSid.into());
+        };
+        session.sid = Some(sid.clone());
+        session.hold = response.hold.unwrap_or(self.hold);
+        session.requests = response.requests.unwrap_or(session.hold + 1).max(1);
+        session.wait = response.wait.unwrap_or(self.wait);
+        session.polling = response.polling.unwrap_or(0);
+
+        let from = match response.from {
+            Some(ref from) => from.to_string(),
+            None => domain.to_string(),
+        };
+        let header = stream_header(ns, &from, &sid);
+
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        let mut data = header.clone();
+        write_payloads(&mut data, response.payloads)?;
+        // Cannot fail, the receiver is still alive.
+        let _ = incoming_tx.send(Ok(data));
+        tokio::spawn(session.run(header, outgoing_rx, incoming_tx));
+
+        let stream = BoshStream {
+            incoming: incoming_rx,
+            read_buf: Vec::new(),
+            read_pos: 0,
+            outgoing: outgoing_tx,
+            parser: OutgoingParser::default(),
+        };
+        Ok((
+            initiate_stream(
+                stream,
+                ns,
+                StreamHeader {
+                    to: Some(domain.as_str().to_owned().into()),
+                    from: None,
+                    id: None,
+                },
+                timeouts,
+            )
+            .await?,
+            Vec::new(),
+        ))
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.url.starts_with("https://")
+    }
+}
+
+/// Build the header we hand to the XML stream in place of the one which
+/// would have been received over TCP.
+///
+/// It contains no XML declaration, so that it can be emitted again after
+/// a stream restart.
+fn stream_header(ns: &str, from: &str, id: &str) -> Vec<u8> {
+    format!(
+        "<stream:stream xmlns='{}' xmlns:stream='{}' from='{}' id='{}' version='1.0'>",
+        ns,
+        ns::STREAM,
+        escape(from),
+        escape(id)
+    )
+    .into_bytes()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('\'', "&apos;")
+}
+
+fn write_payloads(data: &mut Vec<u8>, payloads: Vec<Element>) -> io::Result<()> {
+    for payload in payloads {
+        payload
+            .write_to(data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+    Ok(())
+}
+
+/// What the XML stream asked to send.
+#[derive(Debug, PartialEq)]
+enum Outgoing {
+    /// A top-level element.
+    Payload(Element),
+
+    /// A new stream header, e.g. after SASL.
+    Restart,
+
+    /// The stream footer.
+    Terminate,
+}
+
+/// Splits the bytes written by the XML stream into top-level elements and
+/// stream events.
+#[derive(Default)]
+struct OutgoingParser {
+    parser: RawParser,
+    builder: TreeBuilder,
+    /// Bytes which could be the start of an XML declaration.
+    pending: Vec<u8>,
+    /// Whether the first stream header has been seen.
+    opened: bool,
+}
+
+impl OutgoingParser {
+    const DECLARATION: &'static [u8] = b"<?xml";
+
+    fn feed(&mut self, data: &[u8]) -> io::Result<Vec<Outgoing>> {
+        let mut out = Vec::new();
+        let mut data = {
+            let mut pending = core::mem::take(&mut self.pending);
+            pending.extend_from_slice(data);
+            pending
+        };
+
+        // Every stream header starts a new document, which is the only place
+        // an XML declaration can appear in what the XML stream writes: the
+        // encoder escapes every `<` in text and attributes.
+        let mut rest = &data[..];
+        loop {
+            if rest.starts_with(Self::DECLARATION) {
+                if core::mem::replace(&mut self.opened, true) {
+                    out.push(Outgoing::Restart);
+                }
+                self.parser = RawParser::default();
+                self.builder = TreeBuilder::new();
+            }
+            let (len, done) = match find(&rest[1.min(rest.len())..], Self::DECLARATION) {
+                Some(pos) => (pos + 1, false),
+                None => (rest.len() - partial_suffix(rest, Self::DECLARATION), true),
+            };
+            self.parse(&rest[..len], &mut out)?;
+            rest = &rest[len..];
+            if done {
+                break;
+            }
+        }
+
+        let kept = rest.len();
+        data.drain(..data.len() - kept);
+        self.pending = data;
+        Ok(out)
+    }
+
+    fn parse(&mut self, mut data: &[u8], out: &mut Vec<Outgoing>) -> io::Result<()> {
+        loop {
+            let event = match self.parser.parse(&mut data, false) {
+                Ok(Some(event)) => event,
+                Ok(None) | Err(EndOrError::NeedMoreData) => return Ok(()),
+                Err(EndOrError::Error(e)) => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, e))
+                }
+            };
+            let is_foot = matches!(event, RawEvent::ElementFoot(_));
+            self.builder
+                .process_event(event)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if !is_foot {
+                continue;
+            }
+            if self.builder.depth() == 1 {
+                if let Some(elem) = self.builder.unshift_child() {
+                    out.push(Outgoing::Payload(elem));
+                }
+            } else if self.builder.root.take().is_some() {
+                out.push(Outgoing::Terminate);
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Length of the longest suffix of `data` which is a proper prefix of
+/// `needle`.
+fn partial_suffix(data: &[u8], needle: &[u8]) -> usize {
+    (1..needle.len())
+        .rev()
+        .find(|&len| data.ends_with(&needle[..len]))
+        .unwrap_or(0)
+}
+
+/// The kind of request, which determines how its response is handled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RequestKind {
+    Regular,
+    Restart,
+    Terminate,
+}
+
+type PendingResponse = BoxFuture<'static, (RequestKind, Result<Body, BoshError>)>;
+
+/// State of a BOSH session, owned by the task driving it once created.
+struct Session {
+    client: reqwest::Client,
+    url: String,
+    sid: Option<String>,
+    rid: u64,
+    hold: u32,
+    requests: u32,
+    wait: u32,
+    polling: u32,
+}
+
+impl Session {
+    fn request(
+        &mut self,
+        mut body: Body,
+    ) -> impl core::future::Future<Output = Result<Body, BoshError>> + Send + 'static {
+        body.rid = Some(self.rid);
+        body.sid = self.sid.clone();
+        self.rid += 1;
+
+        let mut data = Vec::new();
+        let serialized = Element::from(body).write_to(&mut data);
+        let request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "text/xml; charset=utf-8")
+            .timeout(Duration::from_secs(self.wait.into()) + REQUEST_GRACE)
+            .body(data);
+        async move {
+            serialized.map_err(BoshError::Xml)?;
+            let response = request.send().await?.error_for_status()?;
+            let bytes = response.bytes().await?;
+            let elem = Element::from_reader(&bytes[..]).map_err(BoshError::Xml)?;
+            Body::try_from(elem).map_err(|_| BoshError::InvalidBody)
+        }
+    }
+
+    fn send(
+        &mut self,
+        in_flight: &mut FuturesOrdered<PendingResponse>,
+        kind: RequestKind,
+        body: Body,
+    ) {
+        let response = self.request(body);
+        in_flight.push_back(response.map(move |result| (kind, result)).boxed());
+    }
+
+    /// Send the next request, if we are allowed to.
+    ///
+    /// Returns true if the terminating request has been sent.
+    fn send_next(
+        &mut self,
+        in_flight: &mut FuturesOrdered<PendingResponse>,
+        queue: &mut VecDeque<Outgoing>,
+    ) -> bool {
+        if in_flight.len() >= self.requests as usize {
+            return false;
+        }
+        match queue.front() {
+            Some(Outgoing::Restart) => {
+                queue.pop_front();
+                self.send(
+                    in_flight,
+                    RequestKind::Restart,
+                    Body {
+                        restart: Some(true),
+                        ..Default::default()
+                    },
+                );
+                false
+            }
+            Some(_) => {
+                let mut body = Body::default();
+                let mut kind = RequestKind::Regular;
+                while let Some(Outgoing::Payload(_)) = queue.front() {
+                    let Some(Outgoing::Payload(payload)) = queue.pop_front() else {
+                        unreachable!();
+                    };
+                    body.payloads.push(payload);
+                }
+                if let Some(Outgoing::Terminate) = queue.front() {
+                    queue.clear();
+                    body.type_ = Some(Type::Terminate);
+                    kind = RequestKind::Terminate;
+                }
+                self.send(in_flight, kind, body);
+                kind == RequestKind::Terminate
+            }
+            None => {
+                // Keep as many requests waiting on the connection manager as
+                // it allows, so that it can push stanzas to us at any time.
+                if in_flight.len() < self.hold as usize {
+                    self.send(in_flight, RequestKind::Regular, Body::default());
+                }
+                false
+            }
+        }
+    }
+
+    async fn run(
+        mut self,
+        header: Vec<u8>,
+        mut outgoing: mpsc::UnboundedReceiver<Outgoing>,
+        incoming: mpsc::UnboundedSender<io::Result<Vec<u8>>>,
+    ) {
+        let mut in_flight = FuturesOrdered::new();
+        let mut queue = VecDeque::new();
+        let mut closed = false;
+        let mut terminating = false;
+        let mut poll = None;
+
+        loop {
+            if !terminating {
+                while in_flight.len() < self.requests as usize {
+                    let before = in_flight.len();
+                    terminating = self.send_next(&mut in_flight, &mut queue);
+                    if terminating || in_flight.len() == before {
+                        break;
+                    }
+                }
+                // Polling sessions must wait between two empty requests.
+                if self.hold == 0 && in_flight.is_empty() && queue.is_empty() && poll.is_none() {
+                    poll = Some(Box::pin(tokio::time::sleep(Duration::from_secs(
+                        self.polling.into(),
+                    ))));
+                }
+            }
+
+            tokio::select! {
+                msg = outgoing.recv(), if !closed && !terminating => match msg {
+                    Some(msg) => {
+                        queue.push_back(msg);
+                        while let Ok(msg) = outgoing.try_recv() {
+                            queue.push_back(msg);
+                        }
+                    }
+                    None => {
+                        // The stream has been dropped without closing it.
+                        closed = true;
+                        queue.push_back(Outgoing::Terminate);
+                    }
+                },
+                Some((kind, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                    let body = match result {
+                        Ok(body) => body,
+                        Err(e) => {
+                            let _ = incoming.send(Err(io::Error::other(e)));
+                            return;
+                        }
+                    };
+                    if kind == RequestKind::Terminate {
+                        let _ = incoming.send(Ok(b"</stream:stream>".to_vec()));
+                        return;
+                    }
+                    if body.type_.is_some() {
+                        let _ = incoming.send(match body.condition {
+                            None => Ok(b"</stream:stream>".to_vec()),
+                            condition => Err(io::Error::new(
+                                io::ErrorKind::ConnectionAborted,
+                                BoshError::Terminated(condition),
+                            )),
+                        });
+                        return;
+                    }
+                    let mut data = Vec::new();
+                    if kind == RequestKind::Restart {
+                        data.extend_from_slice(&header);
+                    }
+                    if let Err(e) = write_payloads(&mut data, body.payloads) {
+                        let _ = incoming.send(Err(e));
+                        return;
+                    }
+                    if !data.is_empty() && incoming.send(Ok(data)).is_err() {
+                        closed = true;
+                    }
+                },
+                _ = async { poll.as_mut().unwrap().await }, if poll.is_some() => {
+                    poll = None;
+                    self.send(&mut in_flight, RequestKind::Regular, Body::default());
+                },
+            }
+        }
+    }
+}
+
+/// The transport returned by [`BoshServerConnector`], which the XML stream
+/// reads from and writes to as if it was a TCP connection.
+pub struct BoshStream {
+    incoming: mpsc::UnboundedReceiver<io::Result<Vec<u8>>>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+    parser: OutgoingParser,
+}
+
+impl AsyncBufRead for BoshStream {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        while this.read_pos == this.read_buf.len() {
+            match ready!(this.incoming.poll_recv(cx)) {
+                Some(Ok(data)) => {
+                    this.read_buf = data;
+                    this.read_pos = 0;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(e)),
+                None => break,
+            }
+        }
+        Poll::Ready(Ok(&this.read_buf[this.read_pos..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.read_pos = (this.read_pos + amt).min(this.read_buf.len());
+    }
+}
+
+impl AsyncRead for BoshStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for BoshStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        for msg in this.parser.feed(buf)? {
+            if this.outgoing.send(msg).is_err() {
+                return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// BOSH specific errors
+#[derive(Debug)]
+pub enum BoshError {
+    /// HTTP error
+    Http(reqwest::Error),
+    /// The connection manager sent invalid XML
+    Xml(xmpp_parsers::minidom::Error),
+    /// The connection manager sent something else than a `<body/>`
+    InvalidBody,
+    /// The connection manager didn’t assign a session id
+    MissingSid,
+    /// The connection manager terminated the session, with the given
+    /// condition
+    Terminated(Option<String>),
+}
+
+impl fmt::Display for BoshError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Http(e) => write!(fmt, "HTTP error: {}", e),
+            Self::Xml(e) => write!(fmt, "XML error: {}", e),
+            Self::InvalidBody => write!(fmt, "invalid BOSH body"),
+            Self::MissingSid => write!(fmt, "no session id assigned"),
+            Self::Terminated(Some(condition)) => {
+                write!(fmt, "session terminated: {}", condition)
+            }
+            Self::Terminated(None) => write!(fmt, "session terminated"),
+        }
+    }
+}
+
+impl ServerConnectorError for BoshError {}
+impl StdError for BoshError {}
+
+impl From<reqwest::Error> for BoshError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"<?xml version='1.0'?><stream:stream xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams' to='example.org' version='1.0'>";
+
+    fn payload(msg: &Outgoing) -> &Element {
+        match msg {
+            Outgoing::Payload(elem) => elem,
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn split_elements() {
+        let mut parser = OutgoingParser::default();
+        assert_eq!(parser.feed(HEADER).unwrap(), Vec::new());
+        let out = parser
+            .feed(b"<auth xmlns='urn:ietf:params:xml:ns:xmpp-sasl' mechanism='PLAIN'>AGEAYg==</auth> <iq type='get' id='a'><ping xmlns='urn:xmpp:ping'/></iq>")
+            .unwrap();
+        assert_eq!(out.len(), 2);
+        assert!(payload(&out[0]).is("auth", ns::SASL));
+        let iq = payload(&out[1]);
+        assert!(iq.is("iq", ns::JABBER_CLIENT));
+        assert!(iq.has_child("ping", ns::PING));
+    }
+
+    #[test]
+    fn split_writes() {
+        let mut parser = OutgoingParser::default();
+        let mut out = Vec::new();
+        let data = [HEADER, b"<presence/><?xml version='1.0'?><stream:stream xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams' version='1.0'><message/></stream:stream>"].concat();
+        for byte in data.chunks(1) {
+            out.extend(parser.feed(byte).unwrap());
+        }
+        assert_eq!(out.len(), 4);
+        assert!(payload(&out[0]).is("presence", ns::JABBER_CLIENT));
+        assert_eq!(out[1], Outgoing::Restart);
+        assert!(payload(&out[2]).is("message", ns::JABBER_CLIENT));
+        assert_eq!(out[3], Outgoing::Terminate);
+    }
+
+    #[test]
+    fn restart() {
+        let mut parser = OutgoingParser::default();
+        let out = parser.feed(&[HEADER, HEADER].concat()).unwrap();
+        assert_eq!(out, vec![Outgoing::Restart]);
+    }
+
+    #[test]
+    fn synthesized_header() {
+        let header = stream_header(ns::JABBER_CLIENT, "example.org", "a'b");
+        assert_eq!(
+            header,
+            b"<stream:stream xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams' from='example.org' id='a&apos;b' version='1.0'>"
+        );
+    }
+}
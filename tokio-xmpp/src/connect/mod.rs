@@ -10,7 +10,7 @@ use crate::Error;
 #[cfg(feature = "starttls")]
 pub mod starttls;
 #[cfg(feature = "starttls")]
-pub use starttls::StartTlsServerConnector;
+pub use starttls::{ClientCertificate, StartTlsServerConnector};
 
 #[cfg(feature = "insecure-tcp")]
 pub mod tcp;
@@ -22,6 +22,11 @@ pub mod websocket;
 #[cfg(feature = "websocket")]
 pub use websocket::WebSocketServerConnector;
 
+#[cfg(feature = "bosh")]
+pub mod bosh;
+#[cfg(feature = "bosh")]
+pub use bosh::BoshServerConnector;
+
 mod dns;
 pub use dns::DnsConfig;
 
@@ -37,12 +42,31 @@ pub trait ServerConnector: Clone + core::fmt::Debug + Send + Unpin + 'static {
     /// The type of Stream this ServerConnector produces
     type Stream: AsyncReadAndWrite;
     /// This must return the connection ready to login, ie if starttls is involved, after TLS has been started, and then after the <stream headers are exchanged
+    ///
+    /// Along with the stream, every channel binding which can be computed
+    /// for this connection is returned, by order of preference.
     fn connect(
         &self,
         jid: &Jid,
         ns: &'static str,
         timeouts: Timeouts,
     ) -> impl core::future::Future<
-        Output = Result<(PendingFeaturesRecv<Self::Stream>, ChannelBinding), Error>,
+        Output = Result<(PendingFeaturesRecv<Self::Stream>, Vec<ChannelBinding>), Error>,
     > + Send;
+
+    /// Whether the streams returned by [`connect`][`Self::connect`] carry a
+    /// TLS client certificate, allowing to authenticate using the SASL
+    /// EXTERNAL mechanism.
+    fn has_client_certificate(&self) -> bool {
+        false
+    }
+
+    /// Whether the streams returned by [`connect`][`Self::connect`] are
+    /// encrypted, see [`SaslPolicy::forbid_plain_unencrypted`][`crate::SaslPolicy::forbid_plain_unencrypted`].
+    ///
+    /// Connectors must only return true if every stream they return is
+    /// encrypted.
+    fn is_encrypted(&self) -> bool {
+        false
+    }
 }
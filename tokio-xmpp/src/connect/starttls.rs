@@ -16,8 +16,9 @@ use futures::{sink::SinkExt, stream::StreamExt};
 #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
 use {
     alloc::sync::Arc,
+    sha2::{Digest, Sha256, Sha384, Sha512},
     tokio_rustls::{
-        rustls::pki_types::ServerName,
+        rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName},
         rustls::{ClientConfig, RootCertStore},
         TlsConnector,
     },
@@ -35,7 +36,7 @@ type TlsStream<S> = ktls::KtlsStream<S>;
 
 #[cfg(feature = "tls-native")]
 use {
-    native_tls::TlsConnector as NativeTlsConnector,
+    native_tls::{Identity, TlsConnector as NativeTlsConnector},
     tokio_native_tls::{TlsConnector, TlsStream},
 };
 
@@ -63,13 +64,70 @@ use crate::{
 #[deprecated(since = "5.0.0", note = "use tokio_xmpp::Client instead")]
 pub type StartTlsClient = Client;
 
+/// A certificate presented to the server during the TLS handshake, to
+/// authenticate using the SASL EXTERNAL mechanism.
+#[derive(Clone)]
+pub struct ClientCertificate {
+    #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+    chain: Vec<CertificateDer<'static>>,
+    #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+    key: Arc<PrivateKeyDer<'static>>,
+    #[cfg(feature = "tls-native")]
+    identity: Identity,
+}
+
+impl ClientCertificate {
+    /// Create a client certificate from its chain, starting with the
+    /// certificate of the client itself, and its private key.
+    #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+    pub fn new(chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Self {
+        Self {
+            chain,
+            key: Arc::new(key),
+        }
+    }
+
+    /// Create a client certificate from an identity containing both the
+    /// certificate chain and its private key.
+    #[cfg(feature = "tls-native")]
+    pub fn new(identity: Identity) -> Self {
+        Self { identity }
+    }
+}
+
+impl fmt::Debug for ClientCertificate {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        // Never print the private key.
+        fmt.debug_struct("ClientCertificate")
+            .finish_non_exhaustive()
+    }
+}
+
 /// Connect via TCP+StartTLS to an XMPP server
 #[derive(Debug, Clone)]
-pub struct StartTlsServerConnector(pub DnsConfig);
+pub struct StartTlsServerConnector {
+    /// How to find the server
+    pub dns_config: DnsConfig,
+
+    /// Certificate to present to the server, if any
+    pub client_certificate: Option<ClientCertificate>,
+}
+
+impl StartTlsServerConnector {
+    /// Present the given certificate to the server, and authenticate using
+    /// SASL EXTERNAL when the server offers it.
+    pub fn with_client_certificate(mut self, client_certificate: ClientCertificate) -> Self {
+        self.client_certificate = Some(client_certificate);
+        self
+    }
+}
 
 impl From<DnsConfig> for StartTlsServerConnector {
     fn from(dns_config: DnsConfig) -> StartTlsServerConnector {
-        Self(dns_config)
+        Self {
+            dns_config,
+            client_certificate: None,
+        }
     }
 }
 
@@ -81,8 +139,8 @@ impl ServerConnector for StartTlsServerConnector {
         jid: &Jid,
         ns: &'static str,
         timeouts: Timeouts,
-    ) -> Result<(PendingFeaturesRecv<Self::Stream>, ChannelBinding), Error> {
-        let tcp_stream = tokio::io::BufStream::new(self.0.resolve().await?);
+    ) -> Result<(PendingFeaturesRecv<Self::Stream>, Vec<ChannelBinding>), Error> {
+        let tcp_stream = tokio::io::BufStream::new(self.dns_config.resolve().await?);
 
         // Unencryped XmppStream
         let xmpp_stream = initiate_stream(
@@ -100,8 +158,12 @@ impl ServerConnector for StartTlsServerConnector {
 
         if features.can_starttls() {
             // TlsStream
-            let (tls_stream, channel_binding) =
-                starttls(xmpp_stream, jid.domain().as_str()).await?;
+            let (tls_stream, channel_bindings) = starttls(
+                xmpp_stream,
+                jid.domain().as_str(),
+                self.client_certificate.as_ref(),
+            )
+            .await?;
             // Encrypted XmppStream
             Ok((
                 initiate_stream(
@@ -115,36 +177,53 @@ impl ServerConnector for StartTlsServerConnector {
                     timeouts,
                 )
                 .await?,
-                channel_binding,
+                channel_bindings,
             ))
         } else {
             Err(crate::Error::Protocol(ProtocolError::NoTls).into())
         }
     }
+
+    fn has_client_certificate(&self) -> bool {
+        self.client_certificate.is_some()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        // Connecting fails if the server doesn’t offer StartTLS.
+        true
+    }
 }
 
 #[cfg(feature = "tls-native")]
 async fn get_tls_stream<S: AsyncRead + AsyncWrite + Unpin>(
     xmpp_stream: XmppStream<BufStream<S>>,
     domain: &str,
-) -> Result<(TlsStream<S>, ChannelBinding), Error> {
+    client_certificate: Option<&ClientCertificate>,
+) -> Result<(TlsStream<S>, Vec<ChannelBinding>), Error> {
     let domain = domain.to_owned();
     let stream = xmpp_stream.into_inner().into_inner();
-    let tls_stream = TlsConnector::from(NativeTlsConnector::builder().build().unwrap())
+    let mut builder = NativeTlsConnector::builder();
+    if let Some(client_certificate) = client_certificate {
+        builder.identity(client_certificate.identity.clone());
+    }
+    let tls_stream = TlsConnector::from(builder.build().map_err(StartTlsError::Tls)?)
         .connect(&domain, stream)
         .await
         .map_err(|e| StartTlsError::Tls(e))?;
-    log::warn!(
-        "tls-native doesn’t support channel binding, please use tls-rust if you want this feature!"
-    );
-    Ok((tls_stream, ChannelBinding::None))
+    let channel_bindings = match tls_stream.get_ref().tls_server_end_point() {
+        Ok(Some(data)) => vec![ChannelBinding::TlsServerEndPoint(data)],
+        Ok(None) => Vec::new(),
+        Err(e) => return Err(StartTlsError::Tls(e).into()),
+    };
+    Ok((tls_stream, channel_bindings))
 }
 
 #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
 async fn get_tls_stream<S: AsyncRead + AsyncWrite + Unpin + AsRawFd>(
     xmpp_stream: XmppStream<BufStream<S>>,
     domain: &str,
-) -> Result<(TlsStream<S>, ChannelBinding), Error> {
+    client_certificate: Option<&ClientCertificate>,
+) -> Result<(TlsStream<S>, Vec<ChannelBinding>), Error> {
     let domain = ServerName::try_from(domain.to_owned()).map_err(StartTlsError::DnsNameError)?;
     let stream = xmpp_stream.into_inner().into_inner();
     let mut root_store = RootCertStore::empty();
@@ -157,9 +236,18 @@ async fn get_tls_stream<S: AsyncRead + AsyncWrite + Unpin + AsRawFd>(
         root_store.add_parsable_certificates(rustls_native_certs::load_native_certs()?);
     }
     #[allow(unused_mut, reason = "This config is mutable when using ktls")]
-    let mut config = ClientConfig::builder()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
+    let mut config = {
+        let builder = ClientConfig::builder().with_root_certificates(root_store);
+        match client_certificate {
+            Some(client_certificate) => builder
+                .with_client_auth_cert(
+                    client_certificate.chain.clone(),
+                    client_certificate.key.clone_key(),
+                )
+                .map_err(StartTlsError::Tls)?,
+            None => builder.with_no_client_auth(),
+        }
+    };
     #[cfg(feature = "tls-rust-ktls")]
     let stream = {
         config.enable_secret_extraction = true;
@@ -172,31 +260,114 @@ async fn get_tls_stream<S: AsyncRead + AsyncWrite + Unpin + AsRawFd>(
 
     // Extract the channel-binding information before we hand the stream over to ktls.
     let (_, connection) = tls_stream.get_ref();
-    let channel_binding = match connection.protocol_version() {
-        // TODO: Add support for TLS 1.2 and earlier.
-        Some(tokio_rustls::rustls::ProtocolVersion::TLSv1_3) => {
-            let data = vec![0u8; 32];
-            let data = connection
-                .export_keying_material(data, b"EXPORTER-Channel-Binding", None)
-                .map_err(|e| StartTlsError::Tls(e))?;
-            ChannelBinding::TlsExporter(data)
-        }
-        _ => ChannelBinding::None,
-    };
+    let mut channel_bindings = Vec::new();
+    // tls-exporter is only secure with TLS 1.3, and rustls doesn’t expose
+    // what tls-unique would require with TLS 1.2.
+    if let Some(tokio_rustls::rustls::ProtocolVersion::TLSv1_3) = connection.protocol_version() {
+        let data = vec![0u8; 32];
+        let data = connection
+            .export_keying_material(data, b"EXPORTER-Channel-Binding", None)
+            .map_err(|e| StartTlsError::Tls(e))?;
+        channel_bindings.push(ChannelBinding::TlsExporter(data));
+    }
+    if let Some(data) = connection
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .and_then(|certificate| tls_server_end_point(certificate))
+    {
+        channel_bindings.push(ChannelBinding::TlsServerEndPoint(data));
+    }
 
     #[cfg(feature = "tls-rust-ktls")]
     let tls_stream = ktls::config_ktls_client(tls_stream)
         .await
         .map_err(StartTlsError::KtlsError)?;
-    Ok((tls_stream, channel_binding))
+    Ok((tls_stream, channel_bindings))
+}
+
+/// Split the contents of the DER element of type `tag` at the start of
+/// `data` from the data following it.
+#[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+fn der_element(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&first, data) = data.split_first()?;
+    if first != tag {
+        return None;
+    }
+    let (&len, data) = data.split_first()?;
+    let (len, data) = if len < 0x80 {
+        (len as usize, data)
+    } else {
+        let num_bytes = (len & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || data.len() < num_bytes {
+            return None;
+        }
+        let (len, data) = data.split_at(num_bytes);
+        let len = len
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+        (len, data)
+    };
+    if data.len() < len {
+        return None;
+    }
+    Some(data.split_at(len))
+}
+
+/// Compute the tls-server-end-point channel binding data from the DER
+/// encoded certificate of the server, as defined in RFC 5929.
+///
+/// This is the hash of the certificate, using the hash function of its
+/// signature algorithm, or SHA-256 if it is MD5 or SHA-1.  Other signature
+/// algorithms, such as RSASSA-PSS or EdDSA, aren’t supported.
+#[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+fn tls_server_end_point(certificate: &[u8]) -> Option<Vec<u8>> {
+    // 1.2.840.113549.1.1
+    const PKCS1: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01];
+    // 1.2.840.10045.4
+    const ECDSA: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04];
+
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signatureValue }
+    let (contents, _) = der_element(certificate, 0x30)?;
+    let (_, rest) = der_element(contents, 0x30)?;
+    let (algorithm, _) = der_element(rest, 0x30)?;
+    let (oid, _) = der_element(algorithm, 0x06)?;
+
+    let sha256 = |data: &[u8]| Sha256::digest(data).to_vec();
+    let sha384 = |data: &[u8]| Sha384::digest(data).to_vec();
+    let sha512 = |data: &[u8]| Sha512::digest(data).to_vec();
+
+    let hash: fn(&[u8]) -> Vec<u8> = if let Some(suffix) = oid.strip_prefix(PKCS1) {
+        match suffix {
+            // md5WithRSAEncryption, sha1WithRSAEncryption, sha256WithRSAEncryption
+            [4] | [5] | [11] => sha256,
+            [12] => sha384,
+            [13] => sha512,
+            _ => return None,
+        }
+    } else if let Some(suffix) = oid.strip_prefix(ECDSA) {
+        match suffix {
+            // ecdsa-with-SHA1, ecdsa-with-SHA256
+            [1] | [3, 2] => sha256,
+            [3, 3] => sha384,
+            [3, 4] => sha512,
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+    Some(hash(certificate))
 }
 
 /// Performs `<starttls/>` on an XmppStream and returns a binary
-/// TlsStream.
+/// TlsStream, presenting `client_certificate` to the server if set.
+///
+/// Every channel binding which can be computed for the TLS session is
+/// returned along with it, by order of preference.
 pub async fn starttls<S: AsyncRead + AsyncWrite + Unpin + AsRawFd>(
     mut stream: XmppStream<BufStream<S>>,
     domain: &str,
-) -> Result<(TlsStream<S>, ChannelBinding), Error> {
+    client_certificate: Option<&ClientCertificate>,
+) -> Result<(TlsStream<S>, Vec<ChannelBinding>), Error> {
     stream
         .send(&XmppStreamElement::Starttls(starttls::Nonza::Request(
             Request,
@@ -220,7 +391,7 @@ pub async fn starttls<S: AsyncRead + AsyncWrite + Unpin + AsRawFd>(
         }
     }
 
-    get_tls_stream(stream, domain).await
+    get_tls_stream(stream, domain, client_certificate).await
 }
 
 /// StartTLS ServerConnector Error
@@ -264,3 +435,42 @@ impl From<InvalidDnsNameError> for StartTlsError {
         Self::DnsNameError(e)
     }
 }
+
+#[cfg(all(test, feature = "tls-rust", not(feature = "tls-native")))]
+mod tests {
+    use super::*;
+
+    fn certificate(oid: &[u8]) -> Vec<u8> {
+        let mut algorithm = vec![0x30, oid.len() as u8 + 2, 0x06, oid.len() as u8];
+        algorithm.extend_from_slice(oid);
+        let mut contents = vec![0x30, 0x00];
+        contents.extend(algorithm);
+        contents.extend([0x03, 0x01, 0x00]);
+        let mut certificate = vec![0x30, contents.len() as u8];
+        certificate.extend(contents);
+        certificate
+    }
+
+    #[test]
+    fn server_end_point_hash() {
+        // sha384WithRSAEncryption
+        let cert = certificate(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c]);
+        assert_eq!(
+            tls_server_end_point(&cert),
+            Some(Sha384::digest(&cert).to_vec())
+        );
+
+        // ecdsa-with-SHA1 is upgraded to SHA-256.
+        let cert = certificate(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x01]);
+        assert_eq!(
+            tls_server_end_point(&cert),
+            Some(Sha256::digest(&cert).to_vec())
+        );
+
+        // Ed25519 has no defined hash function.
+        let cert = certificate(&[0x2b, 0x65, 0x70]);
+        assert_eq!(tls_server_end_point(&cert), None);
+
+        assert_eq!(tls_server_end_point(&cert[..cert.len() - 1]), None);
+    }
+}
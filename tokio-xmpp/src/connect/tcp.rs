@@ -37,7 +37,7 @@ impl ServerConnector for TcpServerConnector {
         jid: &xmpp_parsers::jid::Jid,
         ns: &'static str,
         timeouts: Timeouts,
-    ) -> Result<(PendingFeaturesRecv<Self::Stream>, ChannelBinding), Error> {
+    ) -> Result<(PendingFeaturesRecv<Self::Stream>, Vec<ChannelBinding>), Error> {
         let stream = BufStream::new(self.0.resolve().await?);
         Ok((
             initiate_stream(
@@ -51,7 +51,7 @@ impl ServerConnector for TcpServerConnector {
                 timeouts,
             )
             .await?,
-            ChannelBinding::None,
+            Vec::new(),
         ))
     }
 }
@@ -119,7 +119,7 @@ impl ServerConnector for WebSocketServerConnector {
         jid: &xmpp_parsers::jid::Jid,
         ns: &'static str,
         timeouts: Timeouts,
-    ) -> Result<(PendingFeaturesRecv<Self::Stream>, ChannelBinding), Error> {
+    ) -> Result<(PendingFeaturesRecv<Self::Stream>, Vec<ChannelBinding>), Error> {
         let stream = BufStream::new(self.get_socket().await);
         Ok((
             initiate_stream(
@@ -133,9 +133,14 @@ impl ServerConnector for WebSocketServerConnector {
                 timeouts,
             )
             .await?,
-            ChannelBinding::None,
+            Vec::new(),
         ))
     }
+
+    fn is_encrypted(&self) -> bool {
+        // Always connects using wss://.
+        true
+    }
 }
 
 /// WebSocket specific errors
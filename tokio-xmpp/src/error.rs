@@ -211,6 +211,8 @@ pub enum AuthError {
     Fail(SaslDefinedCondition),
     /// Component authentication failure
     ComponentFail,
+    /// Server dialback failure
+    DialbackFail,
 }
 
 impl StdError for AuthError {}
@@ -222,6 +224,7 @@ impl fmt::Display for AuthError {
             AuthError::Sasl(s) => write!(fmt, "local SASL implementation error: {}", s),
             AuthError::Fail(c) => write!(fmt, "failure from the server: {:?}", c),
             AuthError::ComponentFail => write!(fmt, "component authentication failure"),
+            AuthError::DialbackFail => write!(fmt, "server dialback failure"),
         }
     }
 }
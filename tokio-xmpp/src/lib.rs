@@ -13,7 +13,7 @@
 //! Supported implementations:
 //! - [x] Clients
 //! - [x] Components
-//! - [ ] Servers
+//! - [x] Servers, only the negotiation of client and server-to-server streams, see [`server::ServerNegotiator`] and [`server::S2sNegotiator`]
 //!
 //! Supported transports:
 //! - [x] Plaintext TCP (IPv4/IPv6)
@@ -58,13 +58,17 @@ pub mod connect;
 /// Detailed error types
 pub mod error;
 mod event;
+pub mod server;
 pub mod stanzastream;
 pub mod xmlstream;
 
 #[doc(inline)]
 /// Generic tokio_xmpp Error
 pub use crate::error::Error;
-pub use client::{Client, IqFailure, IqRequest, IqResponse, IqResponseToken};
+pub use client::{
+    Client, FastToken, FileTokenStore, IqFailure, IqRequest, IqResponse, IqResponseToken,
+    LoginSettings, MechanismFactory, MemoryTokenStore, SaslPolicy, TokenStore,
+};
 #[cfg(feature = "insecure-tcp")]
 pub use component::Component;
 pub use event::{Event, Stanza};
@@ -0,0 +1,338 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Accepting side of [XEP-0114](https://xmpp.org/extensions/xep-0114.html)
+//! (Jabber Component Protocol).
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+use core::pin::Pin;
+use core::str::FromStr;
+use core::task::{Context, Poll};
+use std::collections::HashMap;
+
+use futures::{Sink, SinkExt, Stream};
+use tokio::io::{AsyncRead, AsyncWrite, BufStream};
+use xmpp_parsers::{
+    component::Handshake,
+    jid::{DomainPart, DomainRef, Jid},
+    ns,
+    stream_error::{DefinedCondition, StreamError},
+};
+
+use super::{addresses, poll_closing, recv, stream_error, Closing};
+use crate::{
+    error::{AuthError, Error},
+    stanzastream::XmppStream as BoxedXmppStream,
+    xmlstream::{accept_stream, StreamHeader, Timeouts, XmppStreamElement},
+    Stanza,
+};
+
+/// Lookup of the secrets shared with the components allowed to connect.
+pub trait ComponentSecrets: Send + Sync + 'static {
+    /// The secret of the component serving `domain`, or `None` if no such
+    /// component may connect.
+    fn secret(&self, domain: &DomainRef) -> Option<String>;
+}
+
+impl<F> ComponentSecrets for F
+where
+    F: Fn(&DomainRef) -> Option<String> + Send + Sync + 'static,
+{
+    fn secret(&self, domain: &DomainRef) -> Option<String> {
+        self(domain)
+    }
+}
+
+impl ComponentSecrets for BTreeMap<DomainPart, String> {
+    fn secret(&self, domain: &DomainRef) -> Option<String> {
+        self.get(domain).cloned()
+    }
+}
+
+impl ComponentSecrets for HashMap<DomainPart, String> {
+    fn secret(&self, domain: &DomainRef) -> Option<String> {
+        self.get(domain).cloned()
+    }
+}
+
+/// Accepts incoming component connections, authenticating them with the
+/// `<handshake/>` of XEP-0114.
+#[derive(Clone)]
+pub struct ComponentAcceptor {
+    secrets: Arc<dyn ComponentSecrets>,
+    timeouts: Timeouts,
+}
+
+impl fmt::Debug for ComponentAcceptor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ComponentAcceptor")
+            .field("timeouts", &self.timeouts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ComponentAcceptor {
+    /// Create an acceptor for the components whose secret is found in
+    /// `secrets`.
+    pub fn new<S: ComponentSecrets>(secrets: S) -> Self {
+        Self {
+            secrets: Arc::new(secrets),
+            timeouts: Timeouts::default(),
+        }
+    }
+
+    /// Use the given timeouts on the accepted streams.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Accept a component connection on `io`, and wait for it to
+    /// authenticate.
+    pub async fn accept<Io>(&self, io: Io) -> Result<AcceptedComponent, Error>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut stream = accept_stream(BufStream::new(io), ns::COMPONENT, self.timeouts).await?;
+        let header = stream.take_header();
+        let domain = header
+            .to
+            .as_deref()
+            .and_then(|to| DomainPart::from_str(to).ok());
+        let secret = domain
+            .as_ref()
+            .and_then(|domain| self.secrets.secret(domain));
+        let id = uuid::Uuid::new_v4().to_string();
+        let stream = stream
+            .send_header(StreamHeader {
+                from: header.to,
+                to: None,
+                id: Some(id.as_str().into()),
+            })
+            .await?;
+        let mut stream = stream.skip_features::<XmppStreamElement>();
+        let (Some(domain), Some(secret)) = (domain, secret) else {
+            return Err(stream_error(&mut stream, DefinedCondition::HostUnknown).await);
+        };
+
+        let expected = Handshake::from_password_and_stream_id(&secret, &id);
+        match recv(&mut stream).await? {
+            XmppStreamElement::ComponentHandshake(handshake) if handshake == expected => (),
+            XmppStreamElement::ComponentHandshake(_) => {
+                stream_error(&mut stream, DefinedCondition::NotAuthorized).await;
+                return Err(AuthError::ComponentFail.into());
+            }
+            _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+        }
+        stream
+            .send(&XmppStreamElement::ComponentHandshake(Handshake::new()))
+            .await?;
+
+        Ok(AcceptedComponent {
+            jid: Jid::from(domain),
+            stream: stream.box_stream(),
+            closing: None,
+        })
+    }
+}
+
+/// Component connection accepted by a [`ComponentAcceptor`].
+///
+/// Like [`Component`][`crate::Component`], this is a `Stream`/`Sink` of
+/// stanzas.
+///
+/// Received stanzas must be sent from the domain of the component;
+/// otherwise, the stream is closed with an `invalid-from` stream error, and
+/// ends.
+pub struct AcceptedComponent {
+    /// The domain served by the component.
+    pub jid: Jid,
+    stream: BoxedXmppStream,
+    closing: Option<Closing>,
+}
+
+impl fmt::Debug for AcceptedComponent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("AcceptedComponent")
+            .field("jid", &self.jid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AcceptedComponent {
+    /// Send stanza
+    pub async fn send_stanza(&mut self, mut stanza: Stanza) -> Result<(), Error> {
+        stanza.ensure_id();
+        self.send(stanza).await
+    }
+
+    /// End connection
+    pub async fn send_end(&mut self) -> Result<(), Error> {
+        self.close().await
+    }
+}
+
+impl Stream for AcceptedComponent {
+    type Item = Stanza;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.closing.is_some() {
+                let this = &mut *self;
+                return poll_closing(&mut this.closing, &mut this.stream, cx);
+            }
+            let stanza = match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(XmppStreamElement::Stanza(stanza)))) => stanza,
+                // Anything else is unexpected, or an error.
+                Poll::Ready(_) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let (from, _) = addresses(&stanza);
+            if from.is_some_and(|from| from.domain() == self.jid.domain()) {
+                return Poll::Ready(Some(stanza));
+            }
+            log::warn!(
+                "Closing the stream from {}, which sent a stanza from another domain: {:?}",
+                self.jid,
+                stanza
+            );
+            self.closing = Some(Closing::SendError(StreamError {
+                condition: DefinedCondition::InvalidFrom,
+                text: None,
+                application_specific: vec![],
+            }));
+        }
+    }
+}
+
+impl Sink<Stanza> for AcceptedComponent {
+    type Error = Error;
+
+    fn start_send(mut self: Pin<&mut Self>, item: Stanza) -> Result<(), Self::Error> {
+        Pin::new(&mut self.stream)
+            .start_send(&item)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <BoxedXmppStream as Sink<&XmppStreamElement>>::poll_ready(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <BoxedXmppStream as Sink<&XmppStreamElement>>::poll_flush(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <BoxedXmppStream as Sink<&XmppStreamElement>>::poll_close(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::StreamExt;
+    use tokio::io::DuplexStream;
+    use xmpp_parsers::message::Message;
+
+    use crate::xmlstream::{initiate_stream, XmppStream};
+
+    type ComponentStream = XmppStream<BufStream<DuplexStream>>;
+
+    async fn connect(
+        secret: &'static str,
+        from: &'static str,
+    ) -> (
+        Result<Option<Message>, Error>,
+        Result<ComponentStream, Error>,
+    ) {
+        let (component, server) = tokio::io::duplex(65536);
+        let secrets: BTreeMap<DomainPart, String> = [(
+            "plays.shakespeare.lit".parse().unwrap(),
+            String::from("ensemble"),
+        )]
+        .into();
+        let acceptor = ComponentAcceptor::new(secrets).with_timeouts(Timeouts::tight());
+        let server = tokio::spawn(async move {
+            let mut component = acceptor.accept(server).await?;
+            assert_eq!(component.jid, Jid::new("plays.shakespeare.lit").unwrap());
+            match component.next().await {
+                Some(Stanza::Message(message)) => Ok(Some(message)),
+                None => Ok(None),
+                other => panic!("unexpected stanza: {:?}", other),
+            }
+        });
+
+        let component = async move {
+            let mut stream = initiate_stream(
+                BufStream::new(component),
+                ns::COMPONENT,
+                StreamHeader {
+                    to: Some("plays.shakespeare.lit".into()),
+                    from: None,
+                    id: None,
+                },
+                Timeouts::tight(),
+            )
+            .await?;
+            let id = stream.take_header().id.unwrap();
+            let mut stream = stream.skip_features::<XmppStreamElement>();
+            let handshake = Handshake::from_password_and_stream_id(secret, &id);
+            stream
+                .send(&XmppStreamElement::ComponentHandshake(handshake))
+                .await?;
+            match recv(&mut stream).await? {
+                XmppStreamElement::ComponentHandshake(handshake) if handshake.data.is_none() => (),
+                _ => return Err(AuthError::ComponentFail.into()),
+            }
+            let mut message = Message::new(Jid::new("juliet@capulet.lit").ok());
+            message.from = Some(Jid::new(from).unwrap());
+            stream
+                .send(&XmppStreamElement::Stanza(Stanza::Message(message)))
+                .await?;
+            Ok(stream)
+        };
+        let component = component.await;
+        (server.await.unwrap(), component)
+    }
+
+    #[tokio::test]
+    async fn accept_component() {
+        let (server, component) = connect("ensemble", "romeo@plays.shakespeare.lit").await;
+        component.unwrap();
+        assert_eq!(
+            server.unwrap().unwrap().to,
+            Some(Jid::new("juliet@capulet.lit").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn spoofed_from() {
+        let (server, component) = connect("ensemble", "romeo@montague.lit").await;
+        assert_eq!(server.unwrap(), None);
+        match recv(&mut component.unwrap()).await.unwrap() {
+            XmppStreamElement::StreamError(error) => {
+                assert_eq!(error.0.condition, DefinedCondition::InvalidFrom)
+            }
+            other => panic!("unexpected element: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_secret() {
+        let (server, component) = connect("solo", "romeo@plays.shakespeare.lit").await;
+        match server {
+            Err(Error::Auth(AuthError::ComponentFail)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(component.is_err());
+    }
+}
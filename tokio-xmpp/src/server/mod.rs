@@ -0,0 +1,757 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! # Server-side negotiation of client-to-server streams
+//!
+//! [`accept_stream`][`crate::xmlstream::accept_stream`] only handles the
+//! stream header. A [`ServerNegotiator`] goes the rest of the way for
+//! client-to-server streams: it offers and performs STARTTLS, authenticates
+//! the client using the [`sasl::server`] mechanisms it has been configured
+//! with, and binds a resource, before handing back a [`BoundStream`] ready
+//! to exchange stanzas.
+//!
+//! It doesn't route stanzas or keep track of sessions, making it suitable
+//! for test servers or lightweight embedded servers.
+//!
+//! With the `component` feature, a [`ComponentAcceptor`] similarly accepts
+//! connections from [XEP-0114](https://xmpp.org/extensions/xep-0114.html)
+//! components.
+//!
+//! An [`S2sNegotiator`] opens and accepts server-to-server streams for a
+//! set of local domains, authenticating them with dialback or SASL
+//! EXTERNAL.
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io;
+
+use futures::{Sink, SinkExt, StreamExt};
+use sasl::common::scram::ScramProvider;
+use sasl::common::{ChannelBinding, Identity};
+use sasl::secret;
+use sasl::server::mechanisms::{Anonymous, Plain, Scram};
+use sasl::server::{Mechanism, MechanismError, Provider, Response, Validator};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, BufStream};
+use xmpp_parsers::{
+    bind::{BindFeature, BindQuery, BindResponse},
+    iq::{Iq, IqType},
+    jid::{BareJid, DomainPart, FullJid, Jid, NodePart},
+    ns,
+    sasl::{Challenge, DefinedCondition as SaslDefinedCondition, Failure, Nonza, Success},
+    sasl_cb::{self, SaslChannelBinding},
+    stream_error::{DefinedCondition, SentStreamError, StreamError},
+    stream_features::{SaslMechanisms, StreamFeatures},
+};
+
+#[cfg(feature = "starttls")]
+use xmpp_parsers::starttls::{self, StartTls};
+
+#[cfg(all(
+    feature = "starttls",
+    feature = "tls-rust",
+    not(feature = "tls-native")
+))]
+use tokio_rustls::TlsAcceptor;
+
+#[cfg(all(feature = "starttls", feature = "tls-native"))]
+use tokio_native_tls::TlsAcceptor;
+
+#[cfg(feature = "starttls")]
+use crate::connect::starttls::StartTlsError;
+
+use xso::{AsXml, FromXml};
+
+use crate::{
+    connect::AsyncReadAndWrite,
+    error::{AuthError, Error},
+    stanzastream::XmppStream as BoxedXmppStream,
+    xmlstream::{
+        accept_stream, AcceptedStream, ReadError, StreamHeader, Timeouts, XmlStream, XmppStream,
+        XmppStreamElement,
+    },
+    Stanza,
+};
+
+#[cfg(feature = "component")]
+mod component;
+
+#[cfg(feature = "component")]
+pub use component::{AcceptedComponent, ComponentAcceptor, ComponentSecrets};
+
+mod s2s;
+
+pub use s2s::{S2sNegotiator, S2sResolver, ServerStream};
+
+type BoxedIo = Box<dyn AsyncReadAndWrite + Send + 'static>;
+
+/// How many times a client may fail to authenticate before the stream is
+/// closed.
+const MAX_AUTH_ATTEMPTS: usize = 3;
+
+/// Creates a server-side SASL mechanism for the channel binding of the
+/// connection, or returns `None` if it can't be offered on it.
+pub type ServerMechanismFactory =
+    Arc<dyn Fn(&ChannelBinding) -> Option<Box<dyn Mechanism + Send>> + Send + Sync>;
+
+/// A client stream which has been authenticated and bound by a
+/// [`ServerNegotiator`].
+pub struct BoundStream {
+    /// The full JID bound to the client.
+    pub jid: FullJid,
+
+    /// Whether the client negotiated TLS.
+    pub encrypted: bool,
+
+    /// The stream, ready to exchange stanzas with the client.
+    pub stream: BoxedXmppStream,
+}
+
+impl fmt::Debug for BoundStream {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BoundStream")
+            .field("jid", &self.jid)
+            .field("encrypted", &self.encrypted)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Drives incoming client-to-server streams through STARTTLS, SASL and
+/// resource binding.
+///
+/// If a TLS acceptor is configured, clients have to use STARTTLS before
+/// authenticating. The SASL mechanisms are offered in the order they have
+/// been added.
+#[derive(Clone)]
+pub struct ServerNegotiator {
+    domain: DomainPart,
+    timeouts: Timeouts,
+    #[cfg(feature = "starttls")]
+    tls_acceptor: Option<TlsAcceptor>,
+    mechanisms: Vec<ServerMechanismFactory>,
+}
+
+impl fmt::Debug for ServerNegotiator {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = fmt.debug_struct("ServerNegotiator");
+        debug
+            .field("domain", &self.domain)
+            .field("timeouts", &self.timeouts);
+        #[cfg(feature = "starttls")]
+        debug.field("tls", &self.tls_acceptor.is_some());
+        debug.field("mechanisms", &self.mechanisms.len()).finish()
+    }
+}
+
+impl ServerNegotiator {
+    /// Create a negotiator for streams addressed to `domain`, without TLS
+    /// nor any SASL mechanism.
+    pub fn new(domain: DomainPart) -> Self {
+        Self {
+            domain,
+            timeouts: Timeouts::default(),
+            #[cfg(feature = "starttls")]
+            tls_acceptor: None,
+            mechanisms: Vec::new(),
+        }
+    }
+
+    /// Use the given timeouts on the accepted streams.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Offer STARTTLS using `acceptor`, and require it before
+    /// authentication.
+    #[cfg(feature = "starttls")]
+    pub fn with_tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.tls_acceptor = Some(acceptor);
+        self
+    }
+
+    /// Offer the mechanism created by `factory`.
+    pub fn with_mechanism<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&ChannelBinding) -> Option<Box<dyn Mechanism + Send>> + Send + Sync + 'static,
+    {
+        self.mechanisms.push(Arc::new(factory));
+        self
+    }
+
+    /// Offer PLAIN, checking passwords with `validator`.
+    pub fn with_plain<V>(self, validator: V) -> Self
+    where
+        V: Validator<secret::Plain> + Clone + Send + Sync + 'static,
+    {
+        self.with_mechanism(move |_: &ChannelBinding| {
+            Some(Box::new(Plain::new(validator.clone())) as Box<dyn Mechanism + Send>)
+        })
+    }
+
+    /// Offer SCRAM using the hash function `S`, with the secrets returned by
+    /// `provider`.
+    ///
+    /// The -PLUS variant is offered too on TLS sessions which support
+    /// channel binding.
+    pub fn with_scram<S, P>(self, provider: P) -> Self
+    where
+        S: ScramProvider + Send + 'static,
+        S::Secret: secret::Pbkdf2Secret,
+        P: Provider<S::Secret> + Clone + Send + Sync + 'static,
+    {
+        let plus_provider = provider.clone();
+        self.with_mechanism(move |channel_binding: &ChannelBinding| {
+            channel_binding.name()?;
+            let mechanism = Scram::<S, P>::new(plus_provider.clone(), channel_binding.clone());
+            Some(Box::new(mechanism) as Box<dyn Mechanism + Send>)
+        })
+        .with_mechanism(move |channel_binding: &ChannelBinding| {
+            let mechanism = Scram::<S, P>::new(provider.clone(), ChannelBinding::None)
+                .with_plus_offered(channel_binding.name().is_some());
+            Some(Box::new(mechanism) as Box<dyn Mechanism + Send>)
+        })
+    }
+
+    /// Offer ANONYMOUS, binding clients to a random JID.
+    pub fn with_anonymous(self) -> Self {
+        self.with_mechanism(|_: &ChannelBinding| {
+            Some(Box::new(Anonymous::new()) as Box<dyn Mechanism + Send>)
+        })
+    }
+
+    /// Negotiate a client-to-server stream on `io`, usually a freshly
+    /// accepted TCP connection.
+    ///
+    /// The resource requested by the client is used if valid, a random one
+    /// is generated otherwise. Checking for conflicts with existing sessions
+    /// is left to the caller.
+    pub async fn accept<Io>(&self, io: Io) -> Result<BoundStream, Error>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let stream = accept_stream(BufStream::new(io), ns::JABBER_CLIENT, self.timeouts).await?;
+        #[cfg(feature = "starttls")]
+        if let Some(acceptor) = &self.tls_acceptor {
+            let (tls_stream, channel_binding) = self.starttls(stream, acceptor).await?;
+            let stream =
+                accept_stream(BufStream::new(tls_stream), ns::JABBER_CLIENT, self.timeouts).await?;
+            return self.authenticate(stream, channel_binding, true).await;
+        }
+        self.authenticate(stream, ChannelBinding::None, false).await
+    }
+
+    /// Send our stream header in response to the client's, followed by
+    /// `features`.
+    async fn open<Io: AsyncBufRead + AsyncWrite + Unpin>(
+        &self,
+        mut stream: AcceptedStream<Io>,
+        features: &StreamFeatures,
+    ) -> Result<XmppStream<Io>, Error> {
+        let header = stream.take_header();
+        let known_host = header.to.as_deref().is_some_and(|to| {
+            DomainPart::new(to).is_ok_and(|to| to.as_str() == self.domain.as_str())
+        });
+        let stream = stream
+            .send_header(StreamHeader {
+                from: Some(Cow::Borrowed(self.domain.as_str())),
+                to: header.from,
+                id: Some(Cow::Owned(uuid::Uuid::new_v4().to_string())),
+            })
+            .await?;
+        if !known_host {
+            let mut stream = stream.send_features(&StreamFeatures::default()).await?;
+            return Err(stream_error(&mut stream, DefinedCondition::HostUnknown).await);
+        }
+        Ok(stream.send_features(features).await?)
+    }
+
+    #[cfg(feature = "starttls")]
+    async fn starttls<Io>(
+        &self,
+        stream: AcceptedStream<BufStream<Io>>,
+        acceptor: &TlsAcceptor,
+    ) -> Result<
+        (
+            impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+            ChannelBinding,
+        ),
+        Error,
+    >
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let features = StreamFeatures {
+            starttls: Some(StartTls { required: true }),
+            ..Default::default()
+        };
+        let mut stream = self.open(stream, &features).await?;
+        match recv(&mut stream).await? {
+            XmppStreamElement::Starttls(starttls::Nonza::Request(_)) => (),
+            _ => return Err(stream_error(&mut stream, DefinedCondition::PolicyViolation).await),
+        }
+        stream
+            .send(&XmppStreamElement::Starttls(starttls::Nonza::Proceed(
+                starttls::Proceed,
+            )))
+            .await?;
+        let io = stream.into_inner().into_inner();
+        #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+        let tls_stream = acceptor.accept(io).await?;
+        #[cfg(feature = "tls-native")]
+        let tls_stream = acceptor.accept(io).await.map_err(StartTlsError::Tls)?;
+        let channel_binding = channel_binding(&tls_stream)?;
+        Ok((tls_stream, channel_binding))
+    }
+
+    /// Authenticate the client, then bind a resource.
+    async fn authenticate<Io>(
+        &self,
+        stream: AcceptedStream<BufStream<Io>>,
+        channel_binding: ChannelBinding,
+        encrypted: bool,
+    ) -> Result<BoundStream, Error>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let sasl_mechanisms = self
+            .mechanisms
+            .iter()
+            .filter_map(|factory| factory(&channel_binding))
+            .map(|mechanism| mechanism.name().to_owned())
+            .collect();
+        let features = StreamFeatures {
+            sasl_mechanisms: SaslMechanisms {
+                mechanisms: sasl_mechanisms,
+            },
+            sasl_cb: channel_binding_type(&channel_binding)
+                .map(|type_| SaslChannelBinding { types: vec![type_] }),
+            ..Default::default()
+        };
+        let mut stream = self.open(stream, &features).await?;
+
+        let mut attempts = 0;
+        let (identity, data) = loop {
+            let auth = match recv(&mut stream).await? {
+                XmppStreamElement::Sasl(Nonza::Auth(auth)) => auth,
+                _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+            };
+            let name = auth.mechanism.to_string();
+            let mechanism = self
+                .mechanisms
+                .iter()
+                .filter_map(|factory| factory(&channel_binding))
+                .find(|mechanism| mechanism.name() == name);
+            let result = match mechanism {
+                Some(mechanism) => sasl_exchange(&mut stream, mechanism, auth.data).await?,
+                None => Err(SaslDefinedCondition::InvalidMechanism),
+            };
+            match result {
+                Ok((identity, data)) => match self.bare_jid(identity) {
+                    Some(jid) => break (jid, data),
+                    None => send_failure(&mut stream, SaslDefinedCondition::InvalidAuthzid).await?,
+                },
+                Err(condition) => send_failure(&mut stream, condition).await?,
+            }
+            attempts += 1;
+            if attempts >= MAX_AUTH_ATTEMPTS {
+                let _ = stream.shutdown().await;
+                return Err(AuthError::Fail(SaslDefinedCondition::NotAuthorized).into());
+            }
+        };
+
+        let success = XmppStreamElement::Sasl(Nonza::Success(Success { data }));
+        let stream = stream.accept_reset(&success).await?;
+        let features = StreamFeatures {
+            bind: Some(BindFeature::new(true)),
+            ..Default::default()
+        };
+        let mut stream = self.open(stream, &features).await?;
+        let jid = bind(&mut stream, identity).await?;
+        Ok(BoundStream {
+            jid,
+            encrypted,
+            stream: stream.box_stream(),
+        })
+    }
+
+    fn bare_jid(&self, identity: Identity) -> Option<BareJid> {
+        let Identity::Username(username) = identity else {
+            return None;
+        };
+        let node = NodePart::new(&username).ok()?;
+        Some(BareJid::from_parts(Some(&node), &self.domain))
+    }
+}
+
+/// Run a SASL exchange with the client, starting from the initial response
+/// `data`.
+///
+/// Returns the identity of the client and the additional data to send
+/// along the success, or the condition to send in the failure.
+async fn sasl_exchange<Io: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<Io>,
+    mut mechanism: Box<dyn Mechanism + Send>,
+    mut data: Vec<u8>,
+) -> Result<Result<(Identity, Vec<u8>), SaslDefinedCondition>, Error> {
+    loop {
+        match mechanism.respond(&data) {
+            Ok(Response::Success(identity, data)) => return Ok(Ok((identity, data))),
+            Ok(Response::Proceed(challenge)) => {
+                stream
+                    .send(&XmppStreamElement::Sasl(Nonza::Challenge(Challenge {
+                        data: challenge,
+                    })))
+                    .await?;
+            }
+            Err(e) => {
+                log::debug!("SASL {} authentication failed: {}", mechanism.name(), e);
+                return Ok(Err(failure_condition(e)));
+            }
+        }
+        match recv(stream).await? {
+            XmppStreamElement::Sasl(Nonza::Response(response)) => data = response.data,
+            XmppStreamElement::Sasl(Nonza::Abort(_)) => {
+                return Ok(Err(SaslDefinedCondition::Aborted))
+            }
+            _ => return Err(stream_error(stream, DefinedCondition::NotAuthorized).await),
+        }
+    }
+}
+
+fn failure_condition(error: MechanismError) -> SaslDefinedCondition {
+    match error {
+        MechanismError::FailedToDecodeMessage
+        | MechanismError::CannotDecodeInitialMessage
+        | MechanismError::CannotDecodeResponse
+        | MechanismError::CannotDecodeProof
+        | MechanismError::NoUsernameSpecified
+        | MechanismError::NoPasswordSpecified
+        | MechanismError::NoUsername
+        | MechanismError::NoNonce
+        | MechanismError::NoProof => SaslDefinedCondition::MalformedRequest,
+        MechanismError::ErrorDecodingUsername | MechanismError::ErrorDecodingPassword => {
+            SaslDefinedCondition::IncorrectEncoding
+        }
+        _ => SaslDefinedCondition::NotAuthorized,
+    }
+}
+
+async fn send_failure<Io: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<Io>,
+    defined_condition: SaslDefinedCondition,
+) -> Result<(), Error> {
+    let failure = Failure {
+        defined_condition,
+        texts: Default::default(),
+    };
+    stream
+        .send(&XmppStreamElement::Sasl(Nonza::Failure(failure)))
+        .await?;
+    Ok(())
+}
+
+/// Wait for the client to request a resource, and bind it.
+async fn bind<Io: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<Io>,
+    jid: BareJid,
+) -> Result<FullJid, Error> {
+    let (id, payload) = match recv(stream).await? {
+        XmppStreamElement::Stanza(Stanza::Iq(Iq {
+            id,
+            payload: IqType::Set(payload),
+            ..
+        })) => (id, payload),
+        _ => return Err(stream_error(stream, DefinedCondition::NotAuthorized).await),
+    };
+    let Ok(query) = BindQuery::try_from(payload) else {
+        return Err(stream_error(stream, DefinedCondition::NotAuthorized).await);
+    };
+    let jid = query
+        .resource()
+        .and_then(|resource| jid.with_resource_str(resource).ok())
+        .unwrap_or_else(|| {
+            jid.with_resource_str(&uuid::Uuid::new_v4().to_string())
+                .unwrap()
+        });
+    let result = Iq::from_result(id, Some(BindResponse::from(jid.clone())));
+    stream
+        .send(&XmppStreamElement::Stanza(Stanza::Iq(result)))
+        .await?;
+    Ok(jid)
+}
+
+/// Receive the next element from the client, ignoring soft timeouts.
+async fn recv<Io: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<Io>,
+) -> Result<XmppStreamElement, Error> {
+    loop {
+        match stream.next().await {
+            Some(Ok(element)) => return Ok(element),
+            Some(Err(ReadError::SoftTimeout)) => (),
+            Some(Err(ReadError::HardError(e))) => return Err(e.into()),
+            Some(Err(ReadError::ParseError(e))) => {
+                log::debug!("Invalid element during stream negotiation: {}", e);
+                return Err(stream_error(stream, DefinedCondition::BadFormat).await);
+            }
+            Some(Err(ReadError::StreamFooterReceived)) | None => return Err(Error::Disconnected),
+        }
+    }
+}
+
+/// Send a stream error to the client and close the stream, returning the
+/// error to report to the caller.
+async fn stream_error<Io: AsyncBufRead + AsyncWrite + Unpin>(
+    stream: &mut XmppStream<Io>,
+    condition: DefinedCondition,
+) -> Error {
+    let error = StreamError {
+        condition,
+        text: None,
+        application_specific: vec![],
+    };
+    if let Err(e) = stream.send(&error).await {
+        return e.into();
+    }
+    let _ = stream.shutdown().await;
+    io::Error::new(io::ErrorKind::InvalidData, SentStreamError(error)).into()
+}
+
+/// The sender and recipient of `stanza`.
+fn addresses(stanza: &Stanza) -> (Option<&Jid>, Option<&Jid>) {
+    match stanza {
+        Stanza::Iq(iq) => (iq.from.as_ref(), iq.to.as_ref()),
+        Stanza::Message(message) => (message.from.as_ref(), message.to.as_ref()),
+        Stanza::Presence(presence) => (presence.from.as_ref(), presence.to.as_ref()),
+    }
+}
+
+/// How far we got in closing a stream whose peer sent an invalid stanza.
+enum Closing {
+    SendError(StreamError),
+    Close,
+    Closed,
+}
+
+/// Send the pending stream error on `stream`, then close it.
+fn poll_closing<T: FromXml + AsXml>(
+    closing: &mut Option<Closing>,
+    stream: &mut XmlStream<BoxedIo, T>,
+    cx: &mut Context,
+) -> Poll<Option<Stanza>> {
+    loop {
+        match closing.take() {
+            Some(Closing::SendError(error)) => {
+                match <XmlStream<BoxedIo, T> as Sink<&StreamError>>::poll_ready(
+                    Pin::new(&mut *stream),
+                    cx,
+                ) {
+                    Poll::Pending => {
+                        *closing = Some(Closing::SendError(error));
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(())) => {
+                        *closing = Some(Closing::Close);
+                        if Pin::new(&mut *stream).start_send(&error).is_err() {
+                            *closing = Some(Closing::Closed);
+                        }
+                    }
+                    Poll::Ready(Err(_)) => *closing = Some(Closing::Closed),
+                }
+            }
+            Some(Closing::Close) => {
+                match <XmlStream<BoxedIo, T> as Sink<&StreamError>>::poll_close(
+                    Pin::new(&mut *stream),
+                    cx,
+                ) {
+                    Poll::Pending => {
+                        *closing = Some(Closing::Close);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(_) => *closing = Some(Closing::Closed),
+                }
+            }
+            Some(Closing::Closed) | None => {
+                *closing = Some(Closing::Closed);
+                return Poll::Ready(None);
+            }
+        }
+    }
+}
+
+fn channel_binding_type(channel_binding: &ChannelBinding) -> Option<sasl_cb::Type> {
+    match channel_binding {
+        ChannelBinding::TlsUnique(_) => Some(sasl_cb::Type::TlsUnique),
+        ChannelBinding::TlsExporter(_) => Some(sasl_cb::Type::TlsExporter),
+        ChannelBinding::TlsServerEndPoint(_) => Some(sasl_cb::Type::TlsServerEndPoint),
+        ChannelBinding::None | ChannelBinding::Unsupported => None,
+    }
+}
+
+/// Compute the channel binding of the TLS session, using tls-exporter as
+/// rustls doesn't give access to our own certificate.
+#[cfg(all(
+    feature = "starttls",
+    feature = "tls-rust",
+    not(feature = "tls-native")
+))]
+fn channel_binding<Io>(
+    tls_stream: &tokio_rustls::server::TlsStream<Io>,
+) -> Result<ChannelBinding, Error> {
+    let (_, connection) = tls_stream.get_ref();
+    // tls-exporter is only secure with TLS 1.3.
+    if connection.protocol_version() != Some(tokio_rustls::rustls::ProtocolVersion::TLSv1_3) {
+        return Ok(ChannelBinding::None);
+    }
+    let data = connection
+        .export_keying_material(vec![0u8; 32], b"EXPORTER-Channel-Binding", None)
+        .map_err(StartTlsError::Tls)?;
+    Ok(ChannelBinding::TlsExporter(data))
+}
+
+/// Compute the tls-server-end-point channel binding of the TLS session.
+#[cfg(all(feature = "starttls", feature = "tls-native"))]
+fn channel_binding<Io: AsyncRead + AsyncWrite + Unpin>(
+    tls_stream: &tokio_native_tls::TlsStream<Io>,
+) -> Result<ChannelBinding, Error> {
+    match tls_stream.get_ref().tls_server_end_point() {
+        Ok(Some(data)) => Ok(ChannelBinding::TlsServerEndPoint(data)),
+        Ok(None) => Ok(ChannelBinding::None),
+        Err(e) => Err(StartTlsError::Tls(e).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sasl::common::scram::Sha256;
+    use sasl::common::Credentials;
+    use sasl::impl_validator_using_provider;
+    use sasl::server::{ProviderError, ValidatorError};
+
+    use crate::client::login::{auth, Transport};
+    use crate::error::AuthError;
+    use crate::xmlstream::initiate_stream;
+    use crate::SaslPolicy;
+
+    const SALT: &[u8] = b"QSXCR+Q6sek8bf92";
+
+    #[derive(Clone)]
+    struct Accounts;
+
+    impl Validator<secret::Plain> for Accounts {
+        fn validate(
+            &self,
+            identity: &Identity,
+            value: &secret::Plain,
+        ) -> Result<(), ValidatorError> {
+            match identity {
+                Identity::Username(username) if username == "juliet" && value.0 == "romeo" => {
+                    Ok(())
+                }
+                _ => Err(ValidatorError::AuthenticationFailed),
+            }
+        }
+    }
+
+    impl Provider<secret::Pbkdf2Sha256> for Accounts {
+        fn provide(&self, identity: &Identity) -> Result<secret::Pbkdf2Sha256, ProviderError> {
+            match identity {
+                Identity::Username(username) if username == "juliet" => {
+                    Ok(secret::Pbkdf2Sha256::derive("romeo", SALT, 4096)?)
+                }
+                _ => Err(ProviderError::AuthenticationFailed),
+            }
+        }
+    }
+
+    impl_validator_using_provider!(Accounts, secret::Pbkdf2Sha256);
+
+    async fn login(password: &str) -> (Result<FullJid, Error>, Result<BoundStream, Error>) {
+        let (client, server) = tokio::io::duplex(65536);
+        let negotiator = ServerNegotiator::new("capulet.lit".parse().unwrap())
+            .with_timeouts(Timeouts::tight())
+            .with_scram::<Sha256, _>(Accounts)
+            .with_plain(Accounts);
+        let server = tokio::spawn(async move { negotiator.accept(server).await });
+
+        let creds = Credentials::default()
+            .with_username("juliet")
+            .with_password(password);
+        let client = async move {
+            let header = || StreamHeader {
+                to: Some(Cow::Borrowed("capulet.lit")),
+                from: None,
+                id: None,
+            };
+            let stream = initiate_stream(
+                BufStream::new(client),
+                ns::JABBER_CLIENT,
+                header(),
+                Timeouts::tight(),
+            )
+            .await?;
+            let (features, stream) = stream.recv_features::<XmppStreamElement>().await?;
+            assert_eq!(
+                features.sasl_mechanisms.mechanisms,
+                ["SCRAM-SHA-256", "PLAIN"]
+            );
+            let transport = Transport {
+                client_certificate: false,
+                encrypted: false,
+            };
+            let stream = auth(
+                stream,
+                &features.sasl_mechanisms,
+                creds,
+                transport,
+                &SaslPolicy::new(),
+            )
+            .await?;
+            let (features, mut stream) = stream
+                .send_header(header())
+                .await?
+                .recv_features::<XmppStreamElement>()
+                .await?;
+            assert!(features.can_bind());
+            let request = Iq::from_set("bind", BindQuery::new(Some(String::from("balcony"))));
+            stream
+                .send(&XmppStreamElement::Stanza(Stanza::Iq(request)))
+                .await?;
+            match stream.next().await {
+                Some(Ok(XmppStreamElement::Stanza(Stanza::Iq(Iq {
+                    payload: IqType::Result(Some(payload)),
+                    ..
+                })))) => Ok(FullJid::from(BindResponse::try_from(payload).unwrap())),
+                other => panic!("unexpected bind response: {:?}", other),
+            }
+        };
+        let client = client.await;
+        (client, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn negotiate_c2s() {
+        let (client, server) = login("romeo").await;
+        let jid = FullJid::new("juliet@capulet.lit/balcony").unwrap();
+        assert_eq!(client.unwrap(), jid);
+        let server = server.unwrap();
+        assert_eq!(server.jid, jid);
+        assert!(!server.encrypted);
+    }
+
+    #[tokio::test]
+    async fn wrong_password() {
+        let (client, _server) = login("tybalt").await;
+        match client {
+            Err(Error::Auth(AuthError::Fail(SaslDefinedCondition::NotAuthorized))) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,955 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Server-to-server streams, authenticated using either SASL EXTERNAL with
+//! the TLS certificate of the peer, or
+//! [XEP-0220](https://xmpp.org/extensions/xep-0220.html) (Server Dialback).
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures::{Sink, SinkExt, Stream};
+use sasl::common::scram::{ScramProvider, Sha256};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, BufStream};
+use xmpp_parsers::{
+    dialback::{self, DialbackFeature, DialbackResult, DialbackVerify},
+    jid::{DomainPart, DomainRef, Jid},
+    minidom::{Element, Node},
+    ns,
+    sasl::{DefinedCondition as SaslDefinedCondition, Mechanism, Nonza, Success},
+    stream_error::{DefinedCondition, StreamError},
+    stream_features::{SaslMechanisms, StreamFeatures},
+};
+
+#[cfg(feature = "starttls")]
+use xmpp_parsers::starttls::{self, StartTls};
+
+#[cfg(all(
+    feature = "starttls",
+    feature = "tls-rust",
+    not(feature = "tls-native")
+))]
+use tokio_rustls::{
+    rustls::{
+        client::verify_server_name,
+        pki_types::{CertificateDer, ServerName},
+        server::ParsedCertificate,
+    },
+    TlsAcceptor,
+};
+
+#[cfg(all(feature = "starttls", feature = "tls-native"))]
+use tokio_native_tls::TlsAcceptor;
+
+#[cfg(all(feature = "starttls", feature = "tls-native"))]
+use crate::connect::starttls::StartTlsError;
+#[cfg(feature = "starttls")]
+use crate::connect::starttls::{starttls, ClientCertificate};
+
+use super::{addresses, poll_closing, recv, send_failure, stream_error, BoxedIo, Closing};
+use crate::{
+    connect::DnsConfig,
+    error::{AuthError, Error},
+    xmlstream::{
+        accept_stream, initiate_stream, AcceptedStream, PendingFeaturesSend, ReadError,
+        StreamHeader, Timeouts, XmlStream, XmppStream, XmppStreamElement,
+    },
+    Stanza,
+};
+
+/// Returns how to reach the server of a remote domain.
+pub type S2sResolver = Arc<dyn Fn(&DomainRef) -> DnsConfig + Send + Sync>;
+
+/// Opens and accepts server-to-server streams on behalf of local domains.
+///
+/// Outgoing streams use STARTTLS when the remote server offers it, then
+/// authenticate with SASL EXTERNAL if a client certificate is configured
+/// and the remote server accepts it, or with dialback otherwise.
+///
+/// Incoming streams are authenticated with SASL EXTERNAL if the peer
+/// presented a valid certificate for its domain, or with dialback. Keys
+/// received through dialback are checked by connecting back to the
+/// authoritative server of the originating domain.
+#[derive(Clone)]
+pub struct S2sNegotiator {
+    domains: Vec<DomainPart>,
+    secret: Arc<str>,
+    timeouts: Timeouts,
+    resolver: S2sResolver,
+    #[cfg(feature = "starttls")]
+    tls_acceptor: Option<TlsAcceptor>,
+    #[cfg(feature = "starttls")]
+    client_certificate: Option<ClientCertificate>,
+}
+
+impl fmt::Debug for S2sNegotiator {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        // Never print the secret.
+        let mut debug = fmt.debug_struct("S2sNegotiator");
+        debug
+            .field("domains", &self.domains)
+            .field("timeouts", &self.timeouts);
+        #[cfg(feature = "starttls")]
+        debug
+            .field("tls", &self.tls_acceptor.is_some())
+            .field("client_certificate", &self.client_certificate.is_some());
+        debug.finish_non_exhaustive()
+    }
+}
+
+impl S2sNegotiator {
+    /// Create a negotiator without any local domain, generating dialback
+    /// keys from `secret`.
+    ///
+    /// Every server answering for the same domains must share the secret.
+    pub fn new<S: Into<String>>(secret: S) -> Self {
+        Self {
+            domains: Vec::new(),
+            secret: Arc::from(secret.into()),
+            timeouts: Timeouts::default(),
+            resolver: Arc::new(default_resolver),
+            #[cfg(feature = "starttls")]
+            tls_acceptor: None,
+            #[cfg(feature = "starttls")]
+            client_certificate: None,
+        }
+    }
+
+    /// Accept incoming streams addressed to `domain`, and answer the
+    /// dialback verifications for it.
+    pub fn with_domain(mut self, domain: DomainPart) -> Self {
+        self.domains.push(domain);
+        self
+    }
+
+    /// Use the given timeouts on the streams.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Find remote servers using `resolver` instead of the
+    /// `_xmpp-server._tcp` SRV records of their domain.
+    pub fn with_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&DomainRef) -> DnsConfig + Send + Sync + 'static,
+    {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Offer STARTTLS on incoming streams using `acceptor`, and require it.
+    ///
+    /// SASL EXTERNAL is only offered if the acceptor asks for, and
+    /// verifies, client certificates. This is only supported with
+    /// `tls-rust`.
+    #[cfg(feature = "starttls")]
+    pub fn with_tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.tls_acceptor = Some(acceptor);
+        self
+    }
+
+    /// Present `client_certificate` to remote servers, and authenticate
+    /// using SASL EXTERNAL when they offer it.
+    #[cfg(feature = "starttls")]
+    pub fn with_client_certificate(mut self, client_certificate: ClientCertificate) -> Self {
+        self.client_certificate = Some(client_certificate);
+        self
+    }
+
+    /// Open an authenticated stream to the server of `remote`, to send
+    /// stanzas from `local`.
+    pub async fn connect(
+        &self,
+        local: DomainPart,
+        remote: DomainPart,
+    ) -> Result<ServerStream, Error> {
+        let (features, mut stream, id, encrypted) = self.initiate(&local, &remote).await?;
+
+        #[cfg(feature = "starttls")]
+        if self.client_certificate.is_some()
+            && features
+                .sasl_mechanisms
+                .mechanisms
+                .iter()
+                .any(|mechanism| mechanism == "EXTERNAL")
+        {
+            let auth = XmppStreamElement::Sasl(Nonza::Auth(xmpp_parsers::sasl::Auth {
+                mechanism: Mechanism::External,
+                data: local.as_str().as_bytes().to_vec(),
+            }));
+            stream.send(&auth).await?;
+            match recv(&mut stream).await? {
+                XmppStreamElement::Sasl(Nonza::Success(_)) => {
+                    let (_, stream) = stream
+                        .initiate_reset()
+                        .send_header(header(&local, &remote))
+                        .await?
+                        .recv_features::<XmppStreamElement>()
+                        .await?;
+                    return Ok(ServerStream::new(local, remote, encrypted, stream));
+                }
+                XmppStreamElement::Sasl(Nonza::Failure(failure)) => {
+                    log::debug!(
+                        "SASL EXTERNAL rejected by {}: {:?}, trying dialback",
+                        remote,
+                        failure.defined_condition
+                    );
+                }
+                _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+            }
+        }
+        #[cfg(not(feature = "starttls"))]
+        let _ = features;
+
+        let key = dialback_key(&self.secret, &remote, &local, &id);
+        let request = DialbackResult::new(local.clone(), remote.clone(), key);
+        stream
+            .send(&XmppStreamElement::Dialback(dialback::Nonza::Result(
+                request,
+            )))
+            .await?;
+        loop {
+            match recv(&mut stream).await? {
+                XmppStreamElement::Dialback(dialback::Nonza::Result(result))
+                    if result.from == remote && result.to == local =>
+                {
+                    if result.type_ == Some(dialback::Type::Valid) {
+                        return Ok(ServerStream::new(local, remote, encrypted, stream));
+                    }
+                    let _ = stream.shutdown().await;
+                    return Err(AuthError::DialbackFail.into());
+                }
+                // Answers to other requests, which we never send.
+                XmppStreamElement::Dialback(_) => (),
+                _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+            }
+        }
+    }
+
+    /// Accept a server-to-server stream on `io`, usually a freshly
+    /// accepted TCP connection, and wait for the peer to authenticate.
+    ///
+    /// The peer may also only use the stream to verify dialback keys we
+    /// generated, in which case `None` is returned once it closes it.
+    pub async fn accept<Io>(&self, io: Io) -> Result<Option<ServerStream>, Error>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let stream = accept_stream(BufStream::new(io), ns::JABBER_SERVER, self.timeouts).await?;
+        #[cfg(feature = "starttls")]
+        if let Some(acceptor) = &self.tls_acceptor {
+            let (tls_stream, certificate) = self.starttls(stream, acceptor).await?;
+            let stream =
+                accept_stream(BufStream::new(tls_stream), ns::JABBER_SERVER, self.timeouts).await?;
+            return self.authenticate(stream, certificate, true).await;
+        }
+        self.authenticate(stream, None, false).await
+    }
+
+    /// Send our stream header in response to the peer's, checking that it
+    /// is addressed to one of our domains.
+    ///
+    /// Returns the local and remote domains of the stream, along with its
+    /// id.
+    async fn open<Io: AsyncBufRead + AsyncWrite + Unpin>(
+        &self,
+        mut stream: AcceptedStream<Io>,
+    ) -> Result<
+        (
+            PendingFeaturesSend<Io>,
+            DomainPart,
+            Option<DomainPart>,
+            String,
+        ),
+        Error,
+    > {
+        let header = stream.take_header();
+        let local = header
+            .to
+            .as_deref()
+            .and_then(|to| to.parse::<DomainPart>().ok())
+            .filter(|to| self.domains.contains(to));
+        let remote = header
+            .from
+            .as_deref()
+            .and_then(|from| from.parse::<DomainPart>().ok());
+        let id = uuid::Uuid::new_v4().to_string();
+        let stream = stream
+            .send_header(StreamHeader {
+                from: header.to,
+                to: header.from,
+                id: Some(Cow::Borrowed(id.as_str())),
+            })
+            .await?;
+        let Some(local) = local else {
+            let mut stream = stream.send_features(&StreamFeatures::default()).await?;
+            return Err(stream_error(&mut stream, DefinedCondition::HostUnknown).await);
+        };
+        Ok((stream, local, remote, id))
+    }
+
+    #[cfg(feature = "starttls")]
+    async fn starttls<Io>(
+        &self,
+        stream: AcceptedStream<BufStream<Io>>,
+        acceptor: &TlsAcceptor,
+    ) -> Result<
+        (
+            impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+            Option<Vec<u8>>,
+        ),
+        Error,
+    >
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let features = StreamFeatures {
+            starttls: Some(StartTls { required: true }),
+            ..Default::default()
+        };
+        let (stream, _, _, _) = self.open(stream).await?;
+        let mut stream = stream.send_features(&features).await?;
+        match recv(&mut stream).await? {
+            XmppStreamElement::Starttls(starttls::Nonza::Request(_)) => (),
+            _ => return Err(stream_error(&mut stream, DefinedCondition::PolicyViolation).await),
+        }
+        stream
+            .send(&XmppStreamElement::Starttls(starttls::Nonza::Proceed(
+                starttls::Proceed,
+            )))
+            .await?;
+        let io = stream.into_inner().into_inner();
+        #[cfg(all(feature = "tls-rust", not(feature = "tls-native")))]
+        {
+            let tls_stream = acceptor.accept(io).await?;
+            let (_, connection) = tls_stream.get_ref();
+            let certificate = connection
+                .peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .map(|certificate| certificate.to_vec());
+            Ok((tls_stream, certificate))
+        }
+        #[cfg(feature = "tls-native")]
+        {
+            let tls_stream = acceptor.accept(io).await.map_err(StartTlsError::Tls)?;
+            Ok((tls_stream, None))
+        }
+    }
+
+    /// Wait for the peer to authenticate, answering its dialback
+    /// verifications in the meantime.
+    async fn authenticate<Io>(
+        &self,
+        stream: AcceptedStream<BufStream<Io>>,
+        certificate: Option<Vec<u8>>,
+        encrypted: bool,
+    ) -> Result<Option<ServerStream>, Error>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (stream, local, remote, id) = self.open(stream).await?;
+        let external = match (&remote, &certificate) {
+            (Some(remote), Some(certificate)) => certificate_matches(certificate, remote),
+            _ => false,
+        };
+        let mut mechanisms = Vec::new();
+        if external {
+            mechanisms.push(String::from("EXTERNAL"));
+        }
+        let features = StreamFeatures {
+            sasl_mechanisms: SaslMechanisms { mechanisms },
+            others: vec![DialbackFeature::default().into()],
+            ..Default::default()
+        };
+        let mut stream = stream.send_features(&features).await?;
+
+        loop {
+            let element = match recv(&mut stream).await {
+                Ok(element) => element,
+                Err(Error::Disconnected) => {
+                    let _ = stream.shutdown().await;
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            };
+            match element {
+                XmppStreamElement::Sasl(Nonza::Auth(auth))
+                    if external && auth.mechanism == Mechanism::External =>
+                {
+                    // Checked above when offering EXTERNAL.
+                    let remote = remote.clone().unwrap();
+                    if !auth.data.is_empty() && auth.data != remote.as_str().as_bytes() {
+                        send_failure(&mut stream, SaslDefinedCondition::InvalidAuthzid).await?;
+                        continue;
+                    }
+                    let success =
+                        XmppStreamElement::Sasl(Nonza::Success(Success { data: Vec::new() }));
+                    let stream = stream.accept_reset(&success).await?;
+                    let (stream, _, _, _) = self.open(stream).await?;
+                    let stream = stream
+                        .send_features(&StreamFeatures::default())
+                        .await?
+                        .box_stream();
+                    return Ok(Some(ServerStream::new(local, remote, encrypted, stream)));
+                }
+                XmppStreamElement::Sasl(Nonza::Auth(_)) => {
+                    send_failure(&mut stream, SaslDefinedCondition::InvalidMechanism).await?;
+                }
+                XmppStreamElement::Dialback(dialback::Nonza::Verify(verify)) => {
+                    let type_ = if !self.domains.contains(&verify.to) {
+                        dialback::Type::Error
+                    } else if verify.key
+                        == dialback_key(&self.secret, &verify.from, &verify.to, &verify.id)
+                    {
+                        dialback::Type::Valid
+                    } else {
+                        dialback::Type::Invalid
+                    };
+                    let reply = dialback::Nonza::Verify(verify.reply(type_));
+                    stream.send(&XmppStreamElement::Dialback(reply)).await?;
+                }
+                XmppStreamElement::Dialback(dialback::Nonza::Result(request))
+                    if request.type_.is_none() =>
+                {
+                    if request.to != local || remote.as_ref().is_some_and(|r| *r != request.from) {
+                        let reply = dialback::Nonza::Result(request.reply(dialback::Type::Error));
+                        stream.send(&XmppStreamElement::Dialback(reply)).await?;
+                        continue;
+                    }
+                    let valid = match self.verify(&local, &request.from, &id, &request.key).await {
+                        Ok(valid) => valid,
+                        Err(e) => {
+                            log::debug!("Dialback verification of {} failed: {}", request.from, e);
+                            false
+                        }
+                    };
+                    let type_ = if valid {
+                        dialback::Type::Valid
+                    } else {
+                        dialback::Type::Invalid
+                    };
+                    let reply = dialback::Nonza::Result(request.reply(type_));
+                    stream.send(&XmppStreamElement::Dialback(reply)).await?;
+                    if !valid {
+                        let _ = stream.shutdown().await;
+                        return Err(AuthError::DialbackFail.into());
+                    }
+                    return Ok(Some(ServerStream::new(
+                        local,
+                        request.from,
+                        encrypted,
+                        stream.box_stream(),
+                    )));
+                }
+                _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+            }
+        }
+    }
+
+    /// Ask the authoritative server of `remote` whether `key`, received on
+    /// our stream `id`, is valid.
+    async fn verify(
+        &self,
+        local: &DomainPart,
+        remote: &DomainPart,
+        id: &str,
+        key: &str,
+    ) -> Result<bool, Error> {
+        let (_, mut stream, _, _) = self.initiate(local, remote).await?;
+        let request = DialbackVerify::new(
+            local.clone(),
+            remote.clone(),
+            String::from(id),
+            String::from(key),
+        );
+        stream
+            .send(&XmppStreamElement::Dialback(dialback::Nonza::Verify(
+                request,
+            )))
+            .await?;
+        let valid = loop {
+            match recv(&mut stream).await? {
+                XmppStreamElement::Dialback(dialback::Nonza::Verify(verify)) if verify.id == id => {
+                    break verify.type_ == Some(dialback::Type::Valid)
+                }
+                XmppStreamElement::Dialback(_) => (),
+                _ => return Err(stream_error(&mut stream, DefinedCondition::NotAuthorized).await),
+            }
+        };
+        let _ = stream.shutdown().await;
+        Ok(valid)
+    }
+
+    /// Connect to the server of `remote`, using STARTTLS if it offers it.
+    ///
+    /// Returns the features and id of the stream, and whether it is
+    /// encrypted.
+    async fn initiate(
+        &self,
+        local: &DomainPart,
+        remote: &DomainPart,
+    ) -> Result<(StreamFeatures, XmppStream<BoxedIo>, String, bool), Error> {
+        let tcp_stream = (self.resolver)(remote).resolve().await?;
+        let mut stream = initiate_stream(
+            BufStream::new(tcp_stream),
+            ns::JABBER_SERVER,
+            header(local, remote),
+            self.timeouts,
+        )
+        .await?;
+        let id = stream.take_header().id.unwrap_or_default().into_owned();
+        let (features, stream) = stream.recv_features::<XmppStreamElement>().await?;
+
+        #[cfg(feature = "starttls")]
+        if features.can_starttls() {
+            let (tls_stream, _) =
+                starttls(stream, remote.as_str(), self.client_certificate.as_ref()).await?;
+            let mut stream = initiate_stream(
+                BufStream::new(tls_stream),
+                ns::JABBER_SERVER,
+                header(local, remote),
+                self.timeouts,
+            )
+            .await?;
+            let id = stream.take_header().id.unwrap_or_default().into_owned();
+            let (features, stream) = stream.recv_features::<XmppStreamElement>().await?;
+            return Ok((features, stream.box_stream(), id, true));
+        }
+
+        Ok((features, stream.box_stream(), id, false))
+    }
+}
+
+#[cfg(feature = "dns")]
+fn default_resolver(domain: &DomainRef) -> DnsConfig {
+    DnsConfig::srv(domain.as_str(), "_xmpp-server._tcp", 5269)
+}
+
+#[cfg(not(feature = "dns"))]
+fn default_resolver(domain: &DomainRef) -> DnsConfig {
+    DnsConfig::addr(&format!("{}:5269", domain))
+}
+
+fn header<'a>(local: &'a DomainPart, remote: &'a DomainPart) -> StreamHeader<'a> {
+    StreamHeader {
+        from: Some(Cow::Borrowed(local.as_str())),
+        to: Some(Cow::Borrowed(remote.as_str())),
+        id: None,
+    }
+}
+
+/// Generate the dialback key for a stream, as recommended in XEP-0185
+/// (Dialback Key Generation and Validation).
+fn dialback_key(secret: &str, receiving: &DomainRef, originating: &DomainRef, id: &str) -> String {
+    let key = hex(&Sha256::hash(secret.as_bytes()));
+    let data = format!("{} {} {}", receiving, originating, id);
+    let hmac =
+        Sha256::hmac(data.as_bytes(), key.as_bytes()).expect("HMAC accepts keys of any length");
+    hex(&hmac)
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether the DER encoded `certificate` is valid for `domain`.
+///
+/// The chain of the certificate has been verified by the TLS acceptor.
+#[cfg(all(
+    feature = "starttls",
+    feature = "tls-rust",
+    not(feature = "tls-native")
+))]
+fn certificate_matches(certificate: &[u8], domain: &DomainRef) -> bool {
+    let Ok(name) = ServerName::try_from(domain.as_str()) else {
+        return false;
+    };
+    let certificate = CertificateDer::from(certificate);
+    ParsedCertificate::try_from(&certificate)
+        .is_ok_and(|certificate| verify_server_name(&certificate, &name).is_ok())
+}
+
+#[cfg(not(all(
+    feature = "starttls",
+    feature = "tls-rust",
+    not(feature = "tls-native")
+)))]
+fn certificate_matches(_certificate: &[u8], _domain: &DomainRef) -> bool {
+    false
+}
+
+/// Replace the namespace `from` with `to` on `element` and its descendants,
+/// stopping at the first ones in another namespace.
+fn convert_ns(mut element: Element, from: &str, to: &str) -> Element {
+    if element.ns() != from {
+        return element;
+    }
+    let nodes = element.take_nodes().into_iter().map(|node| match node {
+        Node::Element(child) => Node::Element(convert_ns(child, from, to)),
+        node => node,
+    });
+    let mut builder = Element::builder(element.name(), to);
+    for (name, value) in element.attrs() {
+        builder = builder.attr(name, value);
+    }
+    builder.append_all(nodes).build()
+}
+
+/// The stream error to send if `stanza` wasn't sent from `remote` to
+/// `local`, as RFC 6120 requires on a server-to-server stream.
+fn check_addresses(
+    stanza: &Stanza,
+    local: &DomainPart,
+    remote: &DomainPart,
+) -> Option<DefinedCondition> {
+    let (from, to) = addresses(stanza);
+    let matches = |jid: Option<&Jid>, domain: &DomainPart| {
+        jid.is_some_and(|jid| jid.domain().as_str() == domain.as_str())
+    };
+    if !matches(from, remote) {
+        Some(DefinedCondition::InvalidFrom)
+    } else if !matches(to, local) {
+        Some(DefinedCondition::HostUnknown)
+    } else {
+        None
+    }
+}
+
+/// Authenticated server-to-server stream, bound to a pair of local and
+/// remote domains.
+///
+/// Like [`Component`][`crate::Component`], this is a `Stream`/`Sink` of
+/// stanzas. Stanzas are converted from and to the `jabber:server`
+/// namespace.
+///
+/// Received stanzas must be addressed from the remote domain to the local
+/// one; otherwise, the stream is closed with an `invalid-from` or
+/// `host-unknown` stream error, and ends.
+pub struct ServerStream {
+    /// Our domain on this stream.
+    pub local: DomainPart,
+
+    /// The domain of the peer.
+    pub remote: DomainPart,
+
+    /// Whether the stream is encrypted using TLS.
+    pub encrypted: bool,
+
+    stream: XmlStream<BoxedIo, Element>,
+
+    closing: Option<Closing>,
+}
+
+impl fmt::Debug for ServerStream {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ServerStream")
+            .field("local", &self.local)
+            .field("remote", &self.remote)
+            .field("encrypted", &self.encrypted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerStream {
+    fn new(
+        local: DomainPart,
+        remote: DomainPart,
+        encrypted: bool,
+        stream: XmppStream<BoxedIo>,
+    ) -> Self {
+        Self {
+            local,
+            remote,
+            encrypted,
+            stream: stream.retype(),
+            closing: None,
+        }
+    }
+
+    /// Send stanza
+    pub async fn send_stanza(&mut self, mut stanza: Stanza) -> Result<(), Error> {
+        stanza.ensure_id();
+        self.send(stanza).await
+    }
+
+    /// End connection
+    pub async fn send_end(&mut self) -> Result<(), Error> {
+        self.close().await
+    }
+}
+
+impl Stream for ServerStream {
+    type Item = Stanza;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.closing.is_some() {
+                let this = &mut *self;
+                return poll_closing(&mut this.closing, &mut this.stream, cx);
+            }
+            let element = match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(element))) => element,
+                Poll::Ready(Some(Err(ReadError::SoftTimeout))) => continue,
+                Poll::Ready(Some(Err(ReadError::ParseError(e)))) => {
+                    log::debug!("Ignoring invalid element: {}", e);
+                    continue;
+                }
+                // Anything else is an error, or the end of the stream.
+                Poll::Ready(_) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let element = convert_ns(element, ns::JABBER_SERVER, ns::DEFAULT_NS);
+            let stanza = match xso::try_from_element::<Stanza>(element) {
+                Ok(stanza) => stanza,
+                Err(e) => {
+                    log::debug!("Ignoring non-stanza element: {}", e);
+                    continue;
+                }
+            };
+            match check_addresses(&stanza, &self.local, &self.remote) {
+                None => return Poll::Ready(Some(stanza)),
+                Some(condition) => {
+                    log::warn!(
+                        "Closing the stream from {}, which sent a stanza with wrong addresses: {:?}",
+                        self.remote,
+                        stanza
+                    );
+                    self.closing = Some(Closing::SendError(StreamError {
+                        condition,
+                        text: None,
+                        application_specific: vec![],
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl Sink<Stanza> for ServerStream {
+    type Error = Error;
+
+    fn start_send(mut self: Pin<&mut Self>, item: Stanza) -> Result<(), Self::Error> {
+        let element = xso::transform::<Element, Stanza>(&item)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let element = convert_ns(element, ns::DEFAULT_NS, ns::JABBER_SERVER);
+        Pin::new(&mut self.stream)
+            .start_send(&element)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <XmlStream<BoxedIo, Element> as Sink<&Element>>::poll_ready(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <XmlStream<BoxedIo, Element> as Sink<&Element>>::poll_flush(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        <XmlStream<BoxedIo, Element> as Sink<&Element>>::poll_close(Pin::new(&mut self.stream), cx)
+            .map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+    use xmpp_parsers::jid::Jid;
+    use xmpp_parsers::message::Message;
+
+    #[test]
+    fn key_generation() {
+        // Using the inputs of the XEP-0185 example.
+        let key = dialback_key(
+            "s3cr3tf0rd14lb4ck",
+            &DomainPart::new("example.net").unwrap(),
+            &DomainPart::new("example.com").unwrap(),
+            "D60000229F",
+        );
+        assert_eq!(
+            key,
+            "008c689ff366b50c63d69a3e2d2c0e0e1f8404b0118eb688a0102c87cb691bdc"
+        );
+    }
+
+    /// Serve `negotiator` on a local port, sending the authenticated
+    /// streams to the returned receiver.
+    async fn serve(
+        negotiator: S2sNegotiator,
+    ) -> (
+        String,
+        tokio::sync::mpsc::UnboundedReceiver<Result<ServerStream, Error>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (io, _) = listener.accept().await.unwrap();
+                let negotiator = negotiator.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match negotiator.accept(io).await {
+                        Ok(None) => (),
+                        Ok(Some(stream)) => {
+                            let _ = tx.send(Ok(stream));
+                        }
+                        Err(e) => {
+                            let _ = tx.send(Err(e));
+                        }
+                    }
+                });
+            }
+        });
+        (addr, rx)
+    }
+
+    /// Connect from example.com to example.net, the authoritative server of
+    /// example.com using `authoritative_secret`.
+    async fn federate(
+        authoritative_secret: &str,
+    ) -> (Result<ServerStream, Error>, Result<ServerStream, Error>) {
+        let (com_addr, _) = serve(
+            S2sNegotiator::new(authoritative_secret)
+                .with_domain("example.com".parse().unwrap())
+                .with_timeouts(Timeouts::tight()),
+        )
+        .await;
+        let (net_addr, mut net_streams) = serve(
+            S2sNegotiator::new("tybalt")
+                .with_domain("example.net".parse().unwrap())
+                .with_timeouts(Timeouts::tight())
+                .with_resolver(move |_: &DomainRef| DnsConfig::addr(&com_addr)),
+        )
+        .await;
+        let com = S2sNegotiator::new("mercutio")
+            .with_domain("example.com".parse().unwrap())
+            .with_timeouts(Timeouts::tight())
+            .with_resolver(move |_: &DomainRef| DnsConfig::addr(&net_addr));
+
+        let outgoing = com
+            .connect(
+                "example.com".parse().unwrap(),
+                "example.net".parse().unwrap(),
+            )
+            .await;
+        let incoming = net_streams.recv().await.unwrap();
+        (outgoing, incoming)
+    }
+
+    #[tokio::test]
+    async fn dialback() {
+        let (outgoing, incoming) = federate("mercutio").await;
+        let mut outgoing = outgoing.unwrap();
+        let mut incoming = incoming.unwrap();
+        assert_eq!(outgoing.local.as_str(), "example.com");
+        assert_eq!(outgoing.remote.as_str(), "example.net");
+        assert_eq!(incoming.local.as_str(), "example.net");
+        assert_eq!(incoming.remote.as_str(), "example.com");
+        assert!(!outgoing.encrypted);
+
+        let mut message = Message::new(Jid::new("juliet@example.net").ok());
+        message.from = Jid::new("romeo@example.com").ok();
+        outgoing
+            .send_stanza(Stanza::Message(message.clone()))
+            .await
+            .unwrap();
+        match incoming.next().await {
+            Some(Stanza::Message(received)) => {
+                assert_eq!(received.to, message.to);
+                assert_eq!(received.from, message.from);
+            }
+            other => panic!("unexpected stanza: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn spoofed_from() {
+        let (outgoing, incoming) = federate("mercutio").await;
+        let mut outgoing = outgoing.unwrap();
+        let mut incoming = incoming.unwrap();
+
+        // Only example.com may be claimed over this stream.
+        let mut message = Message::new(Jid::new("juliet@example.net").ok());
+        message.from = Jid::new("tybalt@example.org").ok();
+        outgoing
+            .send_stanza(Stanza::Message(message))
+            .await
+            .unwrap();
+        assert!(incoming.next().await.is_none());
+        assert!(incoming.next().await.is_none());
+        // The stream error closes the stream on the other side too.
+        assert!(outgoing.next().await.is_none());
+    }
+
+    #[test]
+    fn addresses() {
+        let local: DomainPart = "example.net".parse().unwrap();
+        let remote: DomainPart = "example.com".parse().unwrap();
+        let mut message = Message::new(Jid::new("juliet@example.net").ok());
+        message.from = Jid::new("romeo@example.com").ok();
+        assert_eq!(
+            check_addresses(&Stanza::Message(message.clone()), &local, &remote),
+            None
+        );
+
+        let mut spoofed = message.clone();
+        spoofed.from = Jid::new("romeo@example.org").ok();
+        assert_eq!(
+            check_addresses(&Stanza::Message(spoofed), &local, &remote),
+            Some(DefinedCondition::InvalidFrom)
+        );
+
+        let mut elsewhere = message.clone();
+        elsewhere.to = Jid::new("juliet@example.org").ok();
+        assert_eq!(
+            check_addresses(&Stanza::Message(elsewhere), &local, &remote),
+            Some(DefinedCondition::HostUnknown)
+        );
+
+        let mut anonymous = message;
+        anonymous.from = None;
+        assert_eq!(
+            check_addresses(&Stanza::Message(anonymous), &local, &remote),
+            Some(DefinedCondition::InvalidFrom)
+        );
+    }
+
+    #[tokio::test]
+    async fn dialback_invalid_key() {
+        let (outgoing, incoming) = federate("benvolio").await;
+        match outgoing {
+            Err(Error::Auth(AuthError::DialbackFail)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match incoming {
+            Err(Error::Auth(AuthError::DialbackFail)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn namespace_conversion() {
+        let elem: Element = "<message xmlns='jabber:server' to='juliet@example.net'><body>Hi</body><x xmlns='urn:example'><y/></x></message>"
+            .parse()
+            .unwrap();
+        let converted = convert_ns(elem, ns::JABBER_SERVER, ns::JABBER_CLIENT);
+        assert!(converted.is("message", ns::JABBER_CLIENT));
+        assert_eq!(converted.attr("to"), Some("juliet@example.net"));
+        assert!(converted.has_child("body", ns::JABBER_CLIENT));
+        let x = converted.get_child("x", "urn:example").unwrap();
+        assert!(x.has_child("y", "urn:example"));
+    }
+}
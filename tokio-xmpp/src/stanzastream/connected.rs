@@ -410,10 +410,14 @@ impl ConnectedState {
                         })))
                     }
                     Break(NegotiationResult::StreamResumed { sm_state }) => {
+                        let bound_jid = sm_state.bound_jid().clone();
                         *self = Self::Ready {
                             sm_state: Some(sm_state),
                         };
-                        Poll::Ready(Some(ConnectedEvent::Worker(WorkerEvent::Resumed)))
+                        Poll::Ready(Some(ConnectedEvent::Worker(WorkerEvent::Resumed {
+                            bound_jid,
+                            features: features.clone(),
+                        })))
                     }
                     Break(NegotiationResult::StreamError { error }) => {
                         self.to_stream_error_state(error);
@@ -546,6 +550,9 @@ impl ConnectedState {
                 match item {
                     // Easy case, we got some data.
                     Ok(XmppStreamElement::Stanza(data)) => {
+                        if let Some(sm_state) = sm_state {
+                            sm_state.received();
+                        }
                         Poll::Ready(Some(ConnectedEvent::Worker(WorkerEvent::Stanza(data))))
                     }
 
@@ -834,4 +841,15 @@ impl ConnectedState {
             _ => false,
         }
     }
+
+    /// The stream management state, if the stream is ready.
+    ///
+    /// Returns `Some(None)` if the stream is ready without stream
+    /// management.
+    pub fn sm_state(&self) -> Option<Option<&SmState>> {
+        match self {
+            Self::Ready { sm_state } => Some(sm_state.as_ref()),
+            _ => None,
+        }
+    }
 }
@@ -35,6 +35,7 @@ use tokio::sync::{mpsc, oneshot};
 
 use xmpp_parsers::{jid::Jid, stream_features::StreamFeatures};
 
+use crate::client::LoginSettings;
 use crate::connect::ServerConnector;
 use crate::xmlstream::Timeouts;
 use crate::Stanza;
@@ -43,13 +44,16 @@ mod connected;
 mod error;
 mod negotiation;
 mod queue;
+mod resumption;
 mod stream_management;
 mod worker;
 
 use self::queue::QueueEntry;
 pub use self::queue::{StanzaStage, StanzaState, StanzaToken};
-pub use self::worker::{Connection, XmppStream};
-use self::worker::{StanzaStreamWorker, LOCAL_SHUTDOWN_TIMEOUT};
+use self::resumption::ResumptionSaver;
+pub use self::resumption::{ResumptionStore, SmResumption};
+pub use self::worker::{Connection, InlineBinding, XmppStream};
+use self::worker::{Reconnector, StanzaStreamWorker, LOCAL_SHUTDOWN_TIMEOUT};
 
 /// Event informing about the change of the [`StanzaStream`]'s status.
 #[derive(Debug)]
@@ -73,7 +77,15 @@ pub enum StreamEvent {
     /// The stream was reestablished **without** loss of state.
     ///
     /// This is merely informative. Potentially useful to prolong timeouts.
-    Resumed,
+    /// When resuming a stream left by a previous run of the application,
+    /// this is the first event.
+    Resumed {
+        /// The JID to which the stream is bound.
+        bound_jid: Jid,
+
+        /// The features reported by the stream.
+        features: StreamFeatures,
+    },
 }
 
 /// Event emitted by the [`StanzaStream`].
@@ -121,11 +133,40 @@ impl StanzaStream {
         timeouts: Timeouts,
         queue_depth: usize,
     ) -> Self {
+        Self::new_c2s_with_settings(
+            server,
+            jid,
+            password,
+            LoginSettings::default(),
+            timeouts,
+            queue_depth,
+        )
+    }
+
+    /// Establish a new client-to-server stream using the given
+    /// [`ServerConnector`] and [`LoginSettings`].
+    ///
+    /// See [`Self::new_c2s`] for the meaning of the other arguments.
+    pub fn new_c2s_with_settings<C: ServerConnector>(
+        server: C,
+        jid: Jid,
+        password: String,
+        settings: LoginSettings,
+        timeouts: Timeouts,
+        queue_depth: usize,
+    ) -> Self {
+        let resumption = settings
+            .resumption_store
+            .clone()
+            .map(|store| ResumptionSaver::new(store, jid.to_bare()));
         let reconnector = Box::new(
-            move |_preferred_location: Option<String>, slot: oneshot::Sender<Connection>| {
+            move |_preferred_location: Option<String>,
+                  resumable: bool,
+                  slot: oneshot::Sender<Connection>| {
                 let jid = jid.clone();
                 let server = server.clone();
                 let password = password.clone();
+                let settings = settings.clone();
                 tokio::spawn(async move {
                     const MAX_DELAY: Duration = Duration::new(30, 0);
                     let mut delay = Duration::new(1, 0);
@@ -136,16 +177,19 @@ impl StanzaStream {
                             jid.clone(),
                             password.clone(),
                             timeouts,
+                            &settings,
+                            !resumable,
                         )
                         .await
                         {
-                            Ok((features, stream)) => {
+                            Ok((features, stream, bound)) => {
                                 log::debug!("Connection as {} established", jid);
                                 let stream = stream.box_stream();
                                 let Err(mut conn) = slot.send(Connection {
                                     stream,
                                     features,
                                     identity: jid,
+                                    bound,
                                 }) else {
                                     // Send succeeded, we're done here.
                                     return;
@@ -178,7 +222,7 @@ impl StanzaStream {
                 });
             },
         );
-        Self::new(reconnector, queue_depth)
+        Self::new_with_reconnector(reconnector, queue_depth, resumption)
     }
 
     /// Create a new stanza stream.
@@ -200,6 +244,8 @@ impl StanzaStream {
     ///
     /// Most notably, the `connector` is **not** responsible for performing
     /// resource binding: Resource binding is handled by the `StanzaStream`.
+    /// Connectors may still bind as part of authentication, but must then
+    /// report it via [`Connection::bound`].
     ///
     /// `connector` will be called soon after `new()` was called to establish
     /// the first underlying stream for the `StanzaStream`.
@@ -213,9 +259,24 @@ impl StanzaStream {
     pub fn new(
         connector: Box<dyn FnMut(Option<String>, oneshot::Sender<Connection>) + Send + 'static>,
         queue_depth: usize,
+    ) -> Self {
+        let mut connector = connector;
+        Self::new_with_reconnector(
+            Box::new(move |preferred_location, _resumable, slot| {
+                connector(preferred_location, slot)
+            }),
+            queue_depth,
+            None,
+        )
+    }
+
+    fn new_with_reconnector(
+        reconnector: Reconnector,
+        queue_depth: usize,
+        resumption: Option<ResumptionSaver>,
     ) -> Self {
         // c2f = core to frontend, f2c = frontend to core
-        let (f2c_tx, c2f_rx) = StanzaStreamWorker::spawn(connector, queue_depth);
+        let (f2c_tx, c2f_rx) = StanzaStreamWorker::spawn(reconnector, queue_depth, resumption);
         Self {
             tx: f2c_tx,
             rx: c2f_rx,
@@ -25,7 +25,7 @@ use crate::Stanza;
 
 use super::queue::{QueueEntry, TransmitQueue};
 use super::stream_management::*;
-use super::worker::{parse_error_to_stream_error, XmppStream};
+use super::worker::{parse_error_to_stream_error, InlineBinding, XmppStream};
 
 static BIND_REQ_ID: &str = "resource-binding";
 
@@ -56,6 +56,15 @@ pub(super) enum NegotiationState {
 
     /// Receive the bind response.
     ReceiveBindResponse { sm_supported: bool },
+
+    /// The connector already bound the stream during authentication.
+    Bound {
+        /// Stream management state enabled alongside the binding.
+        sm_state: Option<SmState>,
+
+        /// The JID to which the stream is bound.
+        bound_jid: FullJid,
+    },
 }
 
 /// The ultimate result of a stream negotiation.
@@ -100,7 +109,23 @@ pub(super) enum NegotiationResult {
 }
 
 impl NegotiationState {
-    pub fn new(features: &StreamFeatures, sm_state: Option<SmState>) -> io::Result<Self> {
+    pub fn new(
+        features: &StreamFeatures,
+        sm_state: Option<SmState>,
+        bound: Option<InlineBinding>,
+    ) -> io::Result<Self> {
+        if let Some(InlineBinding { jid, sm_enabled }) = bound {
+            if sm_state.is_some() {
+                log::debug!(
+                    "Stream was bound by the connector. Dropping previous stream management state."
+                );
+            }
+            return Ok(Self::Bound {
+                sm_state: sm_enabled.map(|enabled| SmState::new(enabled, jid.clone().into())),
+                bound_jid: jid,
+            });
+        }
+
         match sm_state {
             Some(sm_state) => {
                 if features.stream_management.is_some() {
@@ -152,6 +177,14 @@ impl NegotiationState {
         // that we can proceed.
         // TODO: define a deadline for negotiation.
         match self {
+            Self::Bound {
+                sm_state,
+                bound_jid,
+            } => Poll::Ready(Break(NegotiationResult::StreamReset {
+                sm_state: sm_state.take(),
+                bound_jid: Jid::from(bound_jid.clone()),
+            })),
+
             Self::SendBindRequest { sm_supported } => {
                 match ready!(<XmppStream as Sink<&Stanza>>::poll_ready(
                     stream.as_mut(),
@@ -405,9 +438,15 @@ impl NegotiationState {
                         }
                         // We must emit Reset here because this is a
                         // fresh stream and we did not resume.
+                        let bound_jid: Jid = bound_jid
+                            .take()
+                            .expect(
+                                "State machine error: no bound_jid available in SM negotiation.",
+                            )
+                            .into();
                         Poll::Ready(Break(NegotiationResult::StreamReset {
-                            sm_state: Some(enabled.into()),
-                            bound_jid: bound_jid.take().expect("State machine error: no bound_jid available in SM negotiation.").into(),
+                            sm_state: Some(SmState::new(enabled, bound_jid.clone())),
+                            bound_jid,
                         }))
                     }
 
@@ -0,0 +1,226 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Storage of the XEP-0198 (Stream Management) state needed to resume a
+//! stream after a restart of the application.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::time::Duration;
+use std::io;
+use std::time::SystemTime;
+
+use xmpp_parsers::jid::{BareJid, Jid};
+
+use super::stream_management::SmState;
+
+/// How long a stream is assumed to stay resumable when the server didn't
+/// tell.
+const DEFAULT_MAX: Duration = Duration::from_secs(300);
+
+/// What is needed to resume a stream from another process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmResumption {
+    /// The id given by the server to the stream.
+    pub id: String,
+
+    /// The JID to which the stream is bound.
+    pub bound_jid: Jid,
+
+    /// How many stanzas were received on the stream.
+    pub inbound: u32,
+
+    /// How many stanzas were sent on the stream.
+    pub outbound: u32,
+
+    /// How long the server keeps the stream resumable once disconnected,
+    /// in seconds, if it told.
+    pub max: Option<u32>,
+
+    /// When this state was saved.
+    pub timestamp: SystemTime,
+}
+
+impl SmResumption {
+    /// Whether the server has most likely forgotten the stream by now.
+    pub fn is_expired(&self) -> bool {
+        let max = self
+            .max
+            .map(|max| Duration::from_secs(max.into()))
+            .unwrap_or(DEFAULT_MAX);
+        match self.timestamp.elapsed() {
+            Ok(elapsed) => elapsed >= max,
+            // Saved in the future, the clock must have changed.
+            Err(_) => true,
+        }
+    }
+}
+
+/// Persistent storage of [`SmResumption`]s, keyed by account.
+///
+/// The state is saved whenever stanzas are received, so implementations
+/// must be cheap to call from async code.
+pub trait ResumptionStore: fmt::Debug + Send + Sync + 'static {
+    /// Retrieve the state of the stream of the given account, if any.
+    fn load(&self, jid: &BareJid) -> io::Result<Option<SmResumption>>;
+
+    /// Store the state of the stream of the given account, replacing any
+    /// previous one.
+    fn store(&self, jid: &BareJid, resumption: &SmResumption) -> io::Result<()>;
+
+    /// Forget the state of the stream of the given account.
+    fn remove(&self, jid: &BareJid) -> io::Result<()>;
+}
+
+/// Keeps the [`ResumptionStore`] of an account up to date with its stream.
+pub(super) struct ResumptionSaver {
+    store: Arc<dyn ResumptionStore>,
+    jid: BareJid,
+    // Id and counters last saved, to only save changes.
+    saved: Option<(String, u32, u32)>,
+    // Whether nothing is stored anymore.
+    removed: bool,
+}
+
+impl ResumptionSaver {
+    pub fn new(store: Arc<dyn ResumptionStore>, jid: BareJid) -> Self {
+        Self {
+            store,
+            jid,
+            saved: None,
+            removed: false,
+        }
+    }
+
+    /// Load the state of the stream left by a previous run, if it can
+    /// still be resumed.
+    pub fn load(&mut self) -> Option<SmState> {
+        let resumption = match self.store.load(&self.jid) {
+            Ok(resumption) => resumption?,
+            Err(e) => {
+                log::warn!("Failed to load stream management state: {e}");
+                return None;
+            }
+        };
+        if resumption.is_expired() {
+            log::debug!("Stream management state of the previous run expired.");
+            self.remove();
+            return None;
+        }
+        Some(SmState::restore(resumption))
+    }
+
+    /// Save the state of the stream, or forget it if it isn't resumable.
+    pub fn save(&mut self, sm_state: Option<&SmState>) {
+        let Some(resumption) = sm_state.and_then(SmState::resumption) else {
+            if !self.removed {
+                self.remove();
+            }
+            return;
+        };
+        let saved = (
+            resumption.id.clone(),
+            resumption.inbound,
+            resumption.outbound,
+        );
+        if self.saved.as_ref() == Some(&saved) {
+            return;
+        }
+        match self.store.store(&self.jid, &resumption) {
+            Ok(()) => {
+                self.saved = Some(saved);
+                self.removed = false;
+            }
+            Err(e) => log::warn!("Failed to save stream management state: {e}"),
+        }
+    }
+
+    /// Forget the state of the stream, once it can't be resumed anymore.
+    pub fn remove(&mut self) {
+        self.saved = None;
+        match self.store.remove(&self.jid) {
+            Ok(()) => self.removed = true,
+            Err(e) => log::warn!("Failed to remove stream management state: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<HashMap<BareJid, SmResumption>>);
+
+    impl ResumptionStore for Arc<MemoryStore> {
+        fn load(&self, jid: &BareJid) -> io::Result<Option<SmResumption>> {
+            Ok(self.0.lock().unwrap().get(jid).cloned())
+        }
+
+        fn store(&self, jid: &BareJid, resumption: &SmResumption) -> io::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(jid.clone(), resumption.clone());
+            Ok(())
+        }
+
+        fn remove(&self, jid: &BareJid) -> io::Result<()> {
+            self.0.lock().unwrap().remove(jid);
+            Ok(())
+        }
+    }
+
+    fn resumption(timestamp: SystemTime) -> SmResumption {
+        SmResumption {
+            id: String::from("some-long-sm-id"),
+            bound_jid: Jid::new("juliet@capulet.lit/balcony").unwrap(),
+            inbound: 5,
+            outbound: 3,
+            max: Some(60),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn saver_roundtrip() {
+        let store = Arc::new(MemoryStore::default());
+        let jid = BareJid::new("juliet@capulet.lit").unwrap();
+        let mut saver = ResumptionSaver::new(Arc::new(store.clone()), jid.clone());
+        assert!(saver.load().is_none());
+
+        store.store(&jid, &resumption(SystemTime::now())).unwrap();
+        let mut sm_state = saver.load().unwrap();
+        assert_eq!(sm_state.resume_info(), Some(("some-long-sm-id", 5)));
+        sm_state.received();
+        saver.save(Some(&sm_state));
+        assert_eq!(store.load(&jid).unwrap().unwrap().inbound, 6);
+
+        saver.save(None);
+        assert_eq!(store.load(&jid).unwrap(), None);
+    }
+
+    #[test]
+    fn saver_forgets_expired() {
+        let store = Arc::new(MemoryStore::default());
+        let jid = BareJid::new("juliet@capulet.lit").unwrap();
+        let mut saver = ResumptionSaver::new(Arc::new(store.clone()), jid.clone());
+        let timestamp = SystemTime::now() - Duration::from_secs(61);
+        store.store(&jid, &resumption(timestamp)).unwrap();
+        assert!(saver.load().is_none());
+        assert_eq!(store.load(&jid).unwrap(), None);
+    }
+
+    #[test]
+    fn restored_accepts_lagging_counter() {
+        let mut sm_state = SmState::restore(resumption(SystemTime::now()));
+        assert_eq!(sm_state.resume(7).unwrap().count(), 0);
+        assert_eq!(sm_state.resumption().unwrap().outbound, 7);
+    }
+}
@@ -6,10 +6,12 @@
 
 use core::fmt;
 use std::collections::{vec_deque, VecDeque};
+use std::time::SystemTime;
 
-use xmpp_parsers::sm;
+use xmpp_parsers::{jid::Jid, sm};
 
 use super::queue::{QueueEntry, StanzaState};
+use super::resumption::SmResumption;
 
 #[derive(Debug)]
 pub(super) enum SmResumeInfo {
@@ -47,6 +49,16 @@ pub(super) struct SmState {
     /// Information about resumability of the stream
     resumption: SmResumeInfo,
 
+    /// The JID to which the stream is bound.
+    bound_jid: Jid,
+
+    /// How long the peer keeps the stream resumable, in seconds.
+    max: Option<u32>,
+
+    /// Whether this state was restored from a [`SmResumption`], in which
+    /// case the stanzas which were still unacked are gone.
+    restored: bool,
+
     /// Unacked stanzas in the order they were sent
     // We use a VecDeque here because that has better performance
     // characteristics with the ringbuffer-type usage we're seeing here:
@@ -62,6 +74,9 @@ impl fmt::Debug for SmState {
             .field("outbound_base", &self.outbound_base)
             .field("inbound_ctr", &self.inbound_ctr)
             .field("resumption", &self.resumption)
+            .field("bound_jid", &self.bound_jid)
+            .field("max", &self.max)
+            .field("restored", &self.restored)
             .field("len(unacked_stanzas)", &self.unacked_stanzas.len())
             .finish()
     }
@@ -162,6 +177,19 @@ impl SmState {
     /// Returns an iterator which yields the queue entries which need to be
     /// retransmitted.
     pub fn resume(&mut self, h: u32) -> Result<vec_deque::Drain<'_, QueueEntry>, SmError> {
+        if self.restored {
+            // Our counter may lag behind, as it was saved before the last
+            // stanzas got sent, and those which weren't acked can't be
+            // retransmitted anymore.
+            if h.wrapping_sub(self.outbound_base) > u32::MAX / 2 {
+                log::warn!(
+                    "The peer didn't receive {} stanzas sent before the restart, they are lost.",
+                    self.outbound_base.wrapping_sub(h)
+                );
+            }
+            self.outbound_base = h;
+            self.restored = false;
+        }
         self.remote_acked(h)?;
         // Return the entire leftover queue. We cannot receive acks for them,
         // unless they are retransmitted, because the peer has not seen them
@@ -214,6 +242,32 @@ impl SmState {
         self.inbound_ctr
     }
 
+    /// Count a stanza received from the peer.
+    pub fn received(&mut self) {
+        self.inbound_ctr = self.inbound_ctr.wrapping_add(1);
+    }
+
+    /// Get the JID to which the stream is bound.
+    pub fn bound_jid(&self) -> &Jid {
+        &self.bound_jid
+    }
+
+    /// Get what is needed to resume the stream after a restart, if it is
+    /// resumable.
+    pub fn resumption(&self) -> Option<SmResumption> {
+        let (id, inbound) = self.resume_info()?;
+        Some(SmResumption {
+            id: id.to_owned(),
+            bound_jid: self.bound_jid.clone(),
+            inbound,
+            outbound: self
+                .outbound_base
+                .wrapping_add(self.unacked_stanzas.len() as u32),
+            max: self.max,
+            timestamp: SystemTime::now(),
+        })
+    }
+
     /// Get the info necessary for resumption.
     ///
     /// Returns the stream ID and the current inbound counter if resumption is
@@ -226,9 +280,10 @@ impl SmState {
     }
 }
 
-/// Initialize stream management state
-impl From<sm::Enabled> for SmState {
-    fn from(other: sm::Enabled) -> Self {
+impl SmState {
+    /// Initialize stream management state, for a stream bound to
+    /// `bound_jid`.
+    pub fn new(other: sm::Enabled, bound_jid: Jid) -> Self {
         let resumption = if other.resume {
             match other.id {
                 Some(id) => SmResumeInfo::Resumable {
@@ -250,6 +305,27 @@ impl From<sm::Enabled> for SmState {
             pending_acks: 0,
             pending_req: false,
             resumption,
+            bound_jid,
+            max: other.max,
+            restored: false,
+            unacked_stanzas: VecDeque::new(),
+        }
+    }
+
+    /// Restore the state of a stream saved before a restart.
+    pub fn restore(resumption: SmResumption) -> Self {
+        Self {
+            outbound_base: resumption.outbound,
+            inbound_ctr: resumption.inbound,
+            pending_acks: 0,
+            pending_req: false,
+            resumption: SmResumeInfo::Resumable {
+                id: resumption.id,
+                location: None,
+            },
+            bound_jid: resumption.bound_jid,
+            max: resumption.max,
+            restored: true,
             unacked_stanzas: VecDeque::new(),
         }
     }
@@ -21,8 +21,8 @@ use tokio::{
 
 use xmpp_parsers::{
     iq,
-    jid::Jid,
-    ping,
+    jid::{FullJid, Jid},
+    ping, sm,
     stream_error::{DefinedCondition, StreamError},
     stream_features::StreamFeatures,
 };
@@ -34,6 +34,7 @@ use crate::Stanza;
 use super::connected::{ConnectedEvent, ConnectedState};
 use super::negotiation::NegotiationState;
 use super::queue::{QueueEntry, TransmitQueue};
+use super::resumption::ResumptionSaver;
 use super::stream_management::SmState;
 use super::{Event, StreamEvent};
 
@@ -52,13 +53,41 @@ pub struct Connection {
 
     /// The identity to which this stream belongs.
     ///
-    /// Note that connectors must not return bound streams. However, the Jid
-    /// may still be a full jid in order to request a specific resource at
-    /// bind time. If `identity` is a bare JID, the peer will assign the
-    /// resource.
+    /// Note that connectors must not return bound streams, unless the
+    /// binding is reported via [`Self::bound`]. However, the Jid may still
+    /// be a full jid in order to request a specific resource at bind time.
+    /// If `identity` is a bare JID, the peer will assign the resource.
     pub identity: Jid,
+
+    /// The binding performed by the connector during authentication, if
+    /// any.
+    ///
+    /// If set, the stream is not bound again and any previous stream
+    /// management state is discarded.
+    pub bound: Option<InlineBinding>,
+}
+
+/// Resource binding performed by a connector as part of authentication,
+/// e.g. using XEP-0386 (Bind 2).
+#[derive(Debug)]
+pub struct InlineBinding {
+    /// The full JID to which the stream has been bound.
+    pub jid: FullJid,
+
+    /// Stream management state enabled alongside the binding, if any.
+    pub sm_enabled: Option<sm::Enabled>,
 }
 
+/// Callback used by a [`StanzaStream`][`super::StanzaStream`] to request a
+/// new [`Connection`].
+///
+/// The arguments are the preferred location to connect to, whether the
+/// stream holds stream management state it will attempt to resume (in
+/// which case the connector should not bind inline), and the slot to send
+/// the connection to.
+pub(super) type Reconnector =
+    Box<dyn FnMut(Option<String>, bool, oneshot::Sender<Connection>) + Send + 'static>;
+
 // Allow for up to 10s for local shutdown.
 // TODO: make this configurable maybe?
 pub(super) static LOCAL_SHUTDOWN_TIMEOUT: Duration = Duration::new(10, 0);
@@ -75,7 +104,10 @@ pub(super) enum WorkerEvent {
     },
 
     /// The stream has been resumed successfully.
-    Resumed,
+    Resumed {
+        bound_jid: Jid,
+        features: StreamFeatures,
+    },
 
     /// Data received successfully.
     Stanza(Stanza),
@@ -135,6 +167,18 @@ impl WorkerStream {
         WorkerEvent::Disconnected { slot: tx, error }
     }
 
+    /// Whether the stream holds stream management state to resume once the
+    /// next connection is established.
+    fn is_resumable(&self) -> bool {
+        match self {
+            Self::Connecting {
+                sm_state: Some(sm_state),
+                ..
+            } => sm_state.resume_info().is_some(),
+            _ => false,
+        }
+    }
+
     fn poll_duplex(
         self: Pin<&mut Self>,
         transmit_queue: &mut TransmitQueue<QueueEntry>,
@@ -163,13 +207,14 @@ impl WorkerStream {
                             stream,
                             features,
                             identity,
+                            bound,
                         }) => {
                             let substate = ConnectedState::Negotiating {
                                 // We panic here, but that is ok-ish, because
                                 // that will "only" crash the worker and thus
                                 // the stream, and that is kind of exactly
                                 // what we want.
-                                substate: NegotiationState::new(&features, sm_state.take())
+                                substate: NegotiationState::new(&features, sm_state.take(), bound)
                                     .expect("Non-negotiable stream"),
                             };
                             *this = Self::Connected {
@@ -357,6 +402,14 @@ impl WorkerStream {
             Self::Connected { substate, .. } => substate.queue_sm_request(),
         }
     }
+
+    /// The stream management state, if the stream is ready.
+    fn sm_state(&self) -> Option<Option<&SmState>> {
+        match self {
+            Self::Terminated | Self::Connecting { .. } => None,
+            Self::Connected { substate, .. } => substate.sm_state(),
+        }
+    }
 }
 
 struct DriveDuplex<'x> {
@@ -416,10 +469,11 @@ pub(super) fn parse_error_to_stream_error(e: xso::error::Error) -> StreamError {
 
 /// Worker system for a [`StanzaStream`].
 pub(super) struct StanzaStreamWorker {
-    reconnector: Box<dyn FnMut(Option<String>, oneshot::Sender<Connection>) + Send + 'static>,
+    reconnector: Reconnector,
     frontend_tx: mpsc::Sender<Event>,
     stream: WorkerStream,
     transmit_queue: TransmitQueue<QueueEntry>,
+    resumption: Option<ResumptionSaver>,
 }
 
 macro_rules! send_or_break {
@@ -445,13 +499,14 @@ macro_rules! send_or_break {
 
 impl StanzaStreamWorker {
     pub fn spawn(
-        mut reconnector: Box<
-            dyn FnMut(Option<String>, oneshot::Sender<Connection>) + Send + 'static,
-        >,
+        mut reconnector: Reconnector,
         queue_depth: usize,
+        mut resumption: Option<ResumptionSaver>,
     ) -> (mpsc::Sender<QueueEntry>, mpsc::Receiver<Event>) {
+        // Resume the stream left by a previous run, if any.
+        let sm_state = resumption.as_mut().and_then(ResumptionSaver::load);
         let (conn_tx, conn_rx) = oneshot::channel();
-        reconnector(None, conn_tx);
+        reconnector(None, sm_state.is_some(), conn_tx);
         // c2f = core to frontend
         let (c2f_tx, c2f_rx) = mpsc::channel(queue_depth);
         // f2c = frontend to core
@@ -461,10 +516,11 @@ impl StanzaStreamWorker {
             frontend_tx: c2f_tx,
             stream: WorkerStream::Connecting {
                 slot: conn_rx,
-                sm_state: None,
+                sm_state,
                 notify: None,
             },
             transmit_queue,
+            resumption,
         };
         tokio::spawn(async move { worker.run().await });
         (f2c_tx, c2f_rx)
@@ -503,6 +559,9 @@ impl StanzaStreamWorker {
                         // Stream terminated by local choice. Exit.
                         break;
                     };
+                    if let (Some(resumption), Some(sm_state)) = (&mut self.resumption, self.stream.sm_state()) {
+                        resumption.save(sm_state);
+                    }
                     match ev {
                         WorkerEvent::Reset { bound_jid, features } => send_or_break!(
                             Event::Stream(StreamEvent::Reset { bound_jid, features }) => permit in self.frontend_tx,
@@ -522,10 +581,10 @@ impl StanzaStreamWorker {
                                 log::debug!("Immediately aborting reconnect because the frontend is gone.");
                                 break;
                             }
-                            (self.reconnector)(None, slot);
+                            (self.reconnector)(None, self.stream.is_resumable(), slot);
                         }
-                        WorkerEvent::Resumed => send_or_break!(
-                            Event::Stream(StreamEvent::Resumed) => permit in self.frontend_tx,
+                        WorkerEvent::Resumed { bound_jid, features } => send_or_break!(
+                            Event::Stream(StreamEvent::Resumed { bound_jid, features }) => permit in self.frontend_tx,
                             self.transmit_queue => self.stream,
                         ),
                         WorkerEvent::Stanza(stanza) => send_or_break!(
@@ -566,6 +625,10 @@ impl StanzaStreamWorker {
             Ok(()) => log::debug!("Stream closed successfully"),
             Err(e) => log::debug!("Stream closure failed: {e}"),
         }
+        // A closed stream can't be resumed anymore.
+        if let Some(resumption) = &mut self.resumption {
+            resumption.remove();
+        }
     }
 }
 
@@ -31,7 +31,7 @@ use crate::connect::AsyncReadAndWrite;
 
 use super::capture::{log_enabled, log_recv, log_send, CaptureBufRead};
 
-use xmpp_parsers::ns::STREAM as XML_STREAM_NS;
+use xmpp_parsers::ns::{self, STREAM as XML_STREAM_NS};
 
 /// Configuration for timeouts on an XML stream.
 ///
@@ -242,6 +242,12 @@ impl<Io: AsyncBufRead + AsyncWrite> RawXmlStream<Io> {
         writer
             .ns_tracker_mut()
             .declare_fixed(None, stream_ns.into());
+        if stream_ns == ns::JABBER_SERVER {
+            // Some servers only look for dialback elements under this prefix.
+            writer
+                .ns_tracker_mut()
+                .declare_fixed(Some(xml_ncname!("db")), ns::DIALBACK.into());
+        }
         writer
     }
 
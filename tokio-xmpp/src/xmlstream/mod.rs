@@ -339,6 +339,22 @@ impl<Io: AsyncBufRead + AsyncWrite + Unpin, T: FromXml + AsXml + fmt::Debug> Xml
         Ok(AcceptedStream { stream, header })
     }
 
+    /// Change the type of the XSOs exchanged on the stream, without
+    /// resetting it.
+    ///
+    /// This is needed when the stream switches to a payload which `T`
+    /// cannot represent, such as the `jabber:server` stanzas once a
+    /// server-to-server stream has been authenticated using dialback.
+    ///
+    /// # Panics
+    ///
+    /// The same conditions as for [`initiate_reset`][`Self::initiate_reset`]
+    /// apply.
+    pub fn retype<U: FromXml + AsXml>(self) -> XmlStream<Io, U> {
+        self.assert_retypable();
+        XmlStream::wrap(self.inner)
+    }
+
     /// Discard all XML state and return the inner I/O object.
     pub fn into_inner(self) -> Io {
         self.assert_retypable();
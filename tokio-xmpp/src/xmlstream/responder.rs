@@ -88,4 +88,14 @@ impl<Io: AsyncBufRead + AsyncWrite + Unpin> PendingFeaturesSend<Io> {
 
         Ok(XmlStream::wrap(stream))
     }
+
+    /// Skip sending stream features.
+    ///
+    /// This is the responder-side counterpart to
+    /// [`PendingFeaturesRecv::skip_features`][`super::PendingFeaturesRecv::skip_features`],
+    /// and is only useful in
+    /// [XEP-0114](https://xmpp.org/extensions/xep-0114.html) connections.
+    pub fn skip_features<T: FromXml + AsXml>(self) -> XmlStream<Io, T> {
+        XmlStream::wrap(self.stream)
+    }
 }
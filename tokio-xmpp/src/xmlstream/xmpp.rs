@@ -6,7 +6,9 @@
 
 use xso::{AsXml, FromXml};
 
-use xmpp_parsers::{component, sasl, sm, starttls, stream_error::ReceivedStreamError};
+use xmpp_parsers::{
+    component, dialback, sasl, sasl2, sm, starttls, stream_error::ReceivedStreamError,
+};
 
 use crate::Stanza;
 
@@ -22,6 +24,10 @@ pub enum XmppStreamElement {
     #[xml(transparent)]
     Sasl(sasl::Nonza),
 
+    /// XEP-0388 SASL2-related nonza
+    #[xml(transparent)]
+    Sasl2(sasl2::Nonza),
+
     /// STARTTLS-related nonza
     #[xml(transparent)]
     Starttls(starttls::Nonza),
@@ -30,6 +36,10 @@ pub enum XmppStreamElement {
     #[xml(transparent)]
     ComponentHandshake(component::Handshake),
 
+    /// XEP-0220 dialback nonzas
+    #[xml(transparent)]
+    Dialback(dialback::Nonza),
+
     /// Stream error received
     #[xml(transparent)]
     StreamError(ReceivedStreamError),
@@ -5,18 +5,34 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use alloc::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+#[cfg(feature = "avatars")]
+use crate::parsers::vcard_update::VCardUpdate;
 use crate::{
-    event_loop,
+    bookmarks, commands, event_loop, handle, iq,
     jid::{BareJid, Jid},
-    message, muc,
-    parsers::disco::DiscoInfoResult,
-    upload, Error, Event, RoomNick,
+    mam, message, muc,
+    parsers::{
+        bookmarks2::Conference,
+        chatstates::ChatState,
+        data_forms::DataForm,
+        disco::DiscoInfoResult,
+        muc::user::{Affiliation, Role},
+        presence::Show,
+        pubsub::{owner, ItemId, PubSubPayload, Subscription},
+        stanza_error::StanzaError,
+    },
+    presence, pubsub, roster,
+    store::StateStore,
+    upload, AgentHandle, Error, Event, MessageId, RoomNick,
 };
 use tokio_xmpp::Client as TokioXmppClient;
+use xso::{AsXml, FromXml};
 
 pub struct Agent {
     pub(crate) client: TokioXmppClient,
@@ -26,10 +42,57 @@ pub struct Agent {
     pub(crate) node: String,
     pub(crate) uploads: Vec<(String, Jid, PathBuf)>,
     pub(crate) awaiting_disco_bookmarks_type: bool,
+    pub(crate) bookmarks: bookmarks::Bookmarks,
+    // Whether to receive the messages of our other clients
+    pub(crate) carbons: bool,
+    // Mapping of contact->last message received, until displayed
+    pub(crate) displayable: HashMap<BareJid, message::displayed::Displayable>,
+    // Whether to synchronise the displayed messages with our other clients
+    pub(crate) displayed_sync: bool,
+    // Mapping of contact->stanza-id of the last message displayed
+    pub(crate) displayed: HashMap<BareJid, String>,
     // Mapping of room->nick
     pub(crate) rooms_joined: HashMap<BareJid, RoomNick>,
     pub(crate) rooms_joining: HashMap<BareJid, RoomNick>,
     pub(crate) rooms_leaving: HashMap<BareJid, RoomNick>,
+    pub(crate) mam_queries: HashMap<String, mam::PendingQuery>,
+    pub(crate) mam_query_counter: u64,
+    // Mapping of archive->last seen stanza-id
+    pub(crate) archive_positions: HashMap<BareJid, String>,
+    pub(crate) roster: roster::Roster,
+    pub(crate) state: Arc<dyn StateStore>,
+    // Whether the session was set up by this run, rather than resumed from
+    // the stream of a previous one
+    pub(crate) session_started: bool,
+    pub(crate) roster_set_counter: u64,
+    pub(crate) presences: presence::Presences,
+    // Mapping of room->nick->occupant
+    pub(crate) room_occupants: HashMap<BareJid, HashMap<RoomNick, muc::Occupant>>,
+    // Mapping of iq id->room and affiliation whose list was requested
+    pub(crate) muc_admin_queries: HashMap<String, (BareJid, Affiliation)>,
+    pub(crate) muc_admin_counter: u64,
+    // Rooms we are creating, whose configuration form is expected
+    pub(crate) rooms_configuring: HashSet<BareJid>,
+    // Mapping of iq id->room whose configuration was requested or submitted
+    pub(crate) muc_config_queries: HashMap<String, BareJid>,
+    pub(crate) command_registry: commands::CommandRegistry,
+    // Mapping of iq id->entity and node of the command executed
+    pub(crate) command_queries: HashMap<String, (Jid, String)>,
+    pub(crate) command_counter: u64,
+    pub(crate) iq_handlers: iq::handlers::IqHandlers,
+    pub(crate) pubsub_handlers: pubsub::handlers::PubSubHandlers,
+    // When the application last sent a message or changed its presence
+    pub(crate) last_activity: Arc<Mutex<Instant>>,
+    pub(crate) request_timeout: Duration,
+    // Events produced while waiting for the response to a request
+    pub(crate) pending_events: Vec<Event>,
+    pub(crate) own_presence: presence::send::OwnPresence,
+    // Where retrieved avatars are stored, if they are enabled
+    #[cfg(feature = "avatars")]
+    pub(crate) avatar_cache: Option<pubsub::avatar::AvatarCache>,
+    // The XEP-0153 hash advertised in our presence, once we set an avatar
+    #[cfg(feature = "avatars")]
+    pub(crate) own_avatar: Option<VCardUpdate>,
 }
 
 impl Agent {
@@ -50,14 +113,36 @@ impl Agent {
     }
 
     pub async fn send_raw_message<'a>(&mut self, settings: message::send::RawMessageSettings<'a>) {
+        self.mark_active();
         message::send::send_raw_message(self, settings).await
     }
 
-    pub async fn send_message<'a>(&mut self, settings: message::send::MessageSettings<'a>) {
+    /// Send a chat message to a contact, returning its id.
+    pub async fn send_message<'a>(
+        &mut self,
+        settings: message::send::MessageSettings<'a>,
+    ) -> MessageId {
+        self.mark_active();
         message::send::send_message(self, settings).await
     }
 
+    /// Notify a contact of our chat state, e.g. that we are composing a
+    /// message, without sending one.
+    pub async fn send_chat_state(&mut self, recipient: BareJid, state: ChatState) {
+        message::chat_states::send_chat_state(self, recipient, state).await
+    }
+
+    /// Mark the messages received from a contact as displayed.
+    ///
+    /// A XEP-0333 marker is sent for the last one if the contact asked for
+    /// it, and with [ClientFeature::DisplayedSync][crate::ClientFeature::DisplayedSync]
+    /// our other clients are told using XEP-0490, see [Event::ChatDisplayed].
+    pub async fn mark_displayed(&mut self, contact: BareJid) -> Result<(), StanzaError> {
+        message::displayed::mark_displayed(self, contact).await
+    }
+
     pub async fn send_room_message<'a>(&mut self, settings: muc::room::RoomMessageSettings<'a>) {
+        self.mark_active();
         muc::room::send_room_message(self, settings).await
     }
 
@@ -65,9 +150,406 @@ impl Agent {
         &mut self,
         settings: muc::private_message::RoomPrivateMessageSettings<'a>,
     ) {
+        self.mark_active();
         muc::private_message::send_room_private_message(self, settings).await
     }
 
+    /// Create a room, by joining it.
+    ///
+    /// Once created, an [Event::RoomCreated] and then an
+    /// [Event::RoomConfigForm] will be produced; the room stays locked until
+    /// the configuration is submitted with [Agent::submit_room_config], or
+    /// cancelled with [Agent::cancel_room_config].  If the room already
+    /// existed, it is only joined.
+    pub async fn create_room<'a>(&mut self, settings: muc::room::JoinRoomSettings<'a>) {
+        muc::config::create_room(self, settings).await
+    }
+
+    /// Request the configuration form of a room we own, which produces an
+    /// [Event::RoomConfigForm].
+    pub async fn request_room_config(&mut self, room: BareJid) {
+        muc::config::request_config(self, room).await
+    }
+
+    /// Submit the configuration of a room, once modified.  An
+    /// [Event::RoomConfigured] will be produced once accepted.
+    pub async fn submit_room_config(&mut self, room: BareJid, config: muc::RoomConfig) {
+        muc::config::submit_config(self, room, config).await
+    }
+
+    /// Cancel the configuration of a room, which destroys it if it was just
+    /// created.
+    pub async fn cancel_room_config(&mut self, room: BareJid) {
+        muc::config::cancel_config(self, room).await
+    }
+
+    /// Iterate over the occupants of a room we are in, including ourselves.
+    pub fn room_occupants(&self, room: &BareJid) -> impl Iterator<Item = &muc::Occupant> {
+        self.room_occupants
+            .get(room)
+            .into_iter()
+            .flat_map(|occupants| occupants.values())
+    }
+
+    /// Get an occupant of a room we are in, by nickname.
+    pub fn room_occupant(&self, room: &BareJid, nick: &RoomNick) -> Option<&muc::Occupant> {
+        self.room_occupants.get(room)?.get(nick)
+    }
+
+    /// Kick an occupant out of a room, which requires being a moderator.
+    pub async fn kick(&mut self, room: BareJid, nick: RoomNick, reason: Option<&str>) {
+        muc::admin::kick(self, room, nick, reason).await
+    }
+
+    /// Ban a user from a room, which requires being an admin.
+    pub async fn ban(&mut self, room: BareJid, jid: BareJid, reason: Option<&str>) {
+        muc::admin::ban(self, room, jid, reason).await
+    }
+
+    /// Change the role of an occupant.  Once done, an
+    /// [Event::OccupantRoleChanged] will be produced.
+    pub async fn set_role(
+        &mut self,
+        room: BareJid,
+        nick: RoomNick,
+        role: Role,
+        reason: Option<&str>,
+    ) {
+        muc::admin::set_role(self, room, nick, role, reason).await
+    }
+
+    /// Let a visitor speak in a moderated room.
+    pub async fn grant_voice(&mut self, room: BareJid, nick: RoomNick) {
+        muc::admin::set_role(self, room, nick, Role::Participant, None).await
+    }
+
+    /// Prevent an occupant from speaking in a moderated room.
+    pub async fn revoke_voice(&mut self, room: BareJid, nick: RoomNick) {
+        muc::admin::set_role(self, room, nick, Role::Visitor, None).await
+    }
+
+    /// Make an occupant a moderator.
+    pub async fn grant_moderator(&mut self, room: BareJid, nick: RoomNick) {
+        muc::admin::set_role(self, room, nick, Role::Moderator, None).await
+    }
+
+    /// Change the affiliation of a user with a room.
+    pub async fn set_affiliation(
+        &mut self,
+        room: BareJid,
+        jid: BareJid,
+        affiliation: Affiliation,
+        reason: Option<&str>,
+    ) {
+        muc::admin::set_affiliation(self, room, jid, affiliation, reason).await
+    }
+
+    /// Change the affiliations of several users at once, e.g. to edit the
+    /// member list of a room.
+    pub async fn set_affiliations(
+        &mut self,
+        room: BareJid,
+        affiliations: Vec<(BareJid, Affiliation)>,
+    ) {
+        muc::admin::set_affiliations(self, room, affiliations).await
+    }
+
+    /// Request the list of users having an affiliation with a room, e.g. its
+    /// members or the banned users.  An [Event::RoomAffiliations] will be
+    /// produced.
+    pub async fn request_affiliations(&mut self, room: BareJid, affiliation: Affiliation) {
+        muc::admin::request_affiliations(self, room, affiliation).await
+    }
+
+    /// Change the subject of a room.
+    pub async fn set_room_subject(&mut self, room: BareJid, subject: &str, lang: Option<&str>) {
+        muc::admin::set_subject(self, room, subject, lang).await
+    }
+
+    /// Change our nickname in a room.
+    pub async fn change_room_nick(&mut self, room: BareJid, nick: RoomNick) {
+        muc::admin::change_nick(self, room, nick).await
+    }
+
+    /// Destroy a room we own.  Its occupants, including us, will get an
+    /// [Event::RoomDestroyed].
+    pub async fn destroy_room(
+        &mut self,
+        room: BareJid,
+        reason: Option<&str>,
+        alternate: Option<BareJid>,
+    ) {
+        muc::admin::destroy_room(self, room, reason, alternate).await
+    }
+
+    /// Fetch history from the archive of the account or of a room.
+    ///
+    /// Every archived message produces an [Event::ArchivedChatMessage] or an
+    /// [Event::ArchivedRoomMessage], and [Event::HistoryFetched] is produced
+    /// once every page has been received.  After a reconnection, the
+    /// messages missed since the last stanza-id seen from each archive are
+    /// fetched automatically.
+    pub async fn fetch_history(&mut self, settings: mam::FetchHistorySettings) {
+        mam::send::fetch_history(self, settings).await
+    }
+
+    /// Get the conference bookmarks, as last received from the server.
+    pub fn bookmarks(&self) -> &bookmarks::Bookmarks {
+        &self.bookmarks
+    }
+
+    /// Bookmark a room, or replace its bookmark.
+    ///
+    /// The bookmarks are stored using XEP-0402 if our server supports it, in
+    /// private XML storage otherwise.  Once stored, an
+    /// [Event::BookmarkChanged] is produced, and the room is joined if it
+    /// is to be joined automatically, or left otherwise.
+    pub async fn set_bookmark(
+        &mut self,
+        room: BareJid,
+        conference: Conference,
+    ) -> Result<(), StanzaError> {
+        bookmarks::send::set_bookmark(self, room, conference).await
+    }
+
+    /// Remove the bookmark of a room, leaving it.  Once removed, an
+    /// [Event::BookmarkRemoved] is produced.
+    pub async fn remove_bookmark(&mut self, room: BareJid) -> Result<(), StanzaError> {
+        bookmarks::send::remove_bookmark(self, room).await
+    }
+
+    /// Get the stanza-id of the last message seen from an archive, which is
+    /// either the bare JID of the account or the JID of a room.
+    pub fn archive_position(&self, archive: &BareJid) -> Option<&str> {
+        self.archive_positions.get(archive).map(String::as_str)
+    }
+
+    /// Get the contact list, as last received from the server.
+    pub fn roster(&self) -> &roster::Roster {
+        &self.roster
+    }
+
+    /// Add a contact to the contact list, or replace its name and groups.
+    ///
+    /// Once the server has processed it, an [Event::ContactAdded] or
+    /// [Event::ContactChanged] will be produced.  This doesn't subscribe to
+    /// the contact's presence, see [Agent::subscribe].
+    pub async fn set_contact(&mut self, settings: roster::ContactSettings) {
+        roster::send::set_contact(self, settings).await
+    }
+
+    /// Remove a contact from the contact list, cancelling the subscriptions
+    /// in both directions.
+    pub async fn remove_contact(&mut self, jid: BareJid) {
+        roster::send::remove_contact(self, jid).await
+    }
+
+    /// Ask a contact for a subscription to its presence.
+    pub async fn subscribe(&mut self, jid: BareJid, status: Option<&str>) {
+        roster::send::subscribe(self, jid, status).await
+    }
+
+    /// Cancel our subscription to a contact's presence.
+    pub async fn unsubscribe(&mut self, jid: BareJid) {
+        roster::send::unsubscribe(self, jid).await
+    }
+
+    /// Accept a [Event::SubscriptionRequest], letting the contact see our
+    /// presence.
+    pub async fn approve_subscription(&mut self, jid: BareJid) {
+        roster::send::approve_subscription(self, jid).await
+    }
+
+    /// Refuse a [Event::SubscriptionRequest], or revoke a subscription
+    /// previously approved.
+    pub async fn deny_subscription(&mut self, jid: BareJid) {
+        roster::send::deny_subscription(self, jid).await
+    }
+
+    /// Get the presences of our contacts, as last received.
+    pub fn presences(&self) -> &presence::Presences {
+        &self.presences
+    }
+
+    /// Change our presence, sent to our contacts and to the rooms we are in.
+    ///
+    /// It is kept across reconnections.
+    pub async fn set_presence(&mut self, show: Option<Show>, status: Option<&str>, priority: i8) {
+        self.mark_active();
+        presence::send::set_presence(self, show, status, priority).await
+    }
+
+    /// Request the list of commands another entity provides, which produces
+    /// an [Event::CommandList].
+    pub async fn list_commands(&mut self, to: Jid) {
+        commands::send::list_commands(self, to).await
+    }
+
+    /// Execute a command of another entity, or proceed with it.  Each stage
+    /// produces an [Event::CommandResponse], or an [Event::CommandError].
+    pub async fn execute_command(&mut self, settings: commands::CommandSettings) {
+        commands::send::execute_command(self, settings).await
+    }
+
+    /// Send a request to another entity and wait for its result, parsed as
+    /// `Resp`.
+    ///
+    /// The stanzas received in the meantime are still handled, and their
+    /// events are produced by the next call to [Agent::wait_for_events].  If
+    /// no response arrives in time (see
+    /// [ClientBuilder::set_request_timeout][crate::ClientBuilder::set_request_timeout]),
+    /// a remote-server-timeout error is returned; an empty or invalid result
+    /// produces an undefined-condition error.
+    pub async fn request<Req: AsXml, Resp: FromXml>(
+        &mut self,
+        to: Jid,
+        kind: iq::IqKind,
+        req: Req,
+    ) -> Result<Resp, StanzaError> {
+        iq::request::request(self, to, kind, req).await
+    }
+
+    /// Send a request to another entity and wait for its result, which is
+    /// expected to be empty, see [Agent::request].
+    pub async fn request_empty<Req: AsXml>(
+        &mut self,
+        to: Jid,
+        kind: iq::IqKind,
+        req: Req,
+    ) -> Result<(), StanzaError> {
+        iq::request::request_empty(self, to, kind, req).await
+    }
+
+    /// Publish an item to a node, returning its id.
+    ///
+    /// Options set with [PublishSettings::with_option][pubsub::PublishSettings::with_option]
+    /// are preconditions on the configuration of the node.
+    pub async fn publish<P: PubSubPayload>(
+        &mut self,
+        settings: pubsub::PublishSettings<P>,
+    ) -> Result<ItemId, StanzaError> {
+        pubsub::send::publish(self, settings).await
+    }
+
+    /// Fetch the items of a node, parsing their payload as `P`.
+    ///
+    /// Items whose payload is absent or can't be parsed are skipped.
+    pub async fn fetch_items<P>(
+        &mut self,
+        settings: pubsub::FetchItemsSettings,
+    ) -> Result<pubsub::ItemsPage<P>, StanzaError>
+    where
+        P: PubSubPayload,
+        P::Error: core::fmt::Display,
+    {
+        pubsub::send::fetch_items(self, settings).await
+    }
+
+    /// Retract an item from a node of `service`, or of our own account if
+    /// None, notifying the subscribers if `notify` is true.
+    pub async fn retract_item(
+        &mut self,
+        service: Option<BareJid>,
+        node: &str,
+        id: &str,
+        notify: bool,
+    ) -> Result<(), StanzaError> {
+        pubsub::send::retract_item(self, service, node, id, notify).await
+    }
+
+    /// Subscribe our account to a node, returning the state of the
+    /// subscription.  Its events are passed to the handlers registered with
+    /// [ClientBuilder::register_pubsub_handler][crate::ClientBuilder::register_pubsub_handler].
+    pub async fn pubsub_subscribe(
+        &mut self,
+        service: BareJid,
+        node: &str,
+    ) -> Result<Subscription, StanzaError> {
+        pubsub::send::subscribe(self, service, node).await
+    }
+
+    /// Unsubscribe our account from a node.
+    pub async fn pubsub_unsubscribe(
+        &mut self,
+        service: BareJid,
+        node: &str,
+    ) -> Result<(), StanzaError> {
+        pubsub::send::unsubscribe(self, service, node).await
+    }
+
+    /// Fetch the configuration form of a node of `service`, or of our own
+    /// account if None.
+    pub async fn node_config(
+        &mut self,
+        service: Option<BareJid>,
+        node: &str,
+    ) -> Result<DataForm, StanzaError> {
+        pubsub::send::node_config(self, service, node).await
+    }
+
+    /// Change the configuration of a node, with the form returned by
+    /// [Agent::node_config] once filled.
+    pub async fn configure_node(
+        &mut self,
+        service: Option<BareJid>,
+        node: &str,
+        form: DataForm,
+    ) -> Result<(), StanzaError> {
+        pubsub::send::configure_node(self, service, node, form).await
+    }
+
+    /// Fetch the affiliation of every entity to a node.
+    pub async fn node_affiliations(
+        &mut self,
+        service: Option<BareJid>,
+        node: &str,
+    ) -> Result<Vec<owner::Affiliation>, StanzaError> {
+        pubsub::send::node_affiliations(self, service, node).await
+    }
+
+    /// Change the affiliation of some entities to a node.
+    pub async fn set_node_affiliations(
+        &mut self,
+        service: Option<BareJid>,
+        node: &str,
+        affiliations: Vec<owner::Affiliation>,
+    ) -> Result<(), StanzaError> {
+        pubsub::send::set_node_affiliations(self, service, node, affiliations).await
+    }
+
+    /// Publish our avatar, `data` being an image of type `mime`.
+    ///
+    /// It is published using PEP for XEP-0084, and in our vCard for the
+    /// contacts which only support XEP-0153, its hash being advertised in
+    /// our presence; the call only fails if both can't be updated.
+    #[cfg(feature = "avatars")]
+    pub async fn set_avatar(&mut self, data: &[u8], mime: &str) -> Result<(), StanzaError> {
+        self.mark_active();
+        pubsub::avatar::set_avatar(self, data, mime).await
+    }
+
+    /// Stop publishing our avatar, see [Agent::set_avatar].
+    #[cfg(feature = "avatars")]
+    pub async fn remove_avatar(&mut self) -> Result<(), StanzaError> {
+        self.mark_active();
+        pubsub::avatar::remove_avatar(self).await
+    }
+
+    // Reset the idle time answered to last activity requests.
+    fn mark_active(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Move the agent into a background task, which handles the incoming
+    /// stanzas, and return a handle to it.
+    ///
+    /// The handle can be cloned and used from any number of tasks at once;
+    /// events are received through [AgentHandle::subscribe] instead of
+    /// [Agent::wait_for_events].  This must be called from a tokio runtime.
+    pub fn spawn(self) -> AgentHandle {
+        handle::spawn(self)
+    }
+
     /// Wait for new events, or Error::Disconnected when connection is closed and will not reconnect.
     pub async fn wait_for_events(&mut self) -> Vec<Event> {
         event_loop::wait_for_events(self).await
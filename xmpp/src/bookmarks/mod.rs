@@ -0,0 +1,83 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+
+use crate::{jid::BareJid, parsers::bookmarks2::Conference, Event};
+
+pub mod receive;
+pub mod send;
+
+/// Where the bookmarks of the account are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Storage {
+    /// XEP-0402 bookmarks, one item per room in a PEP node.
+    Pep,
+    /// XEP-0048 bookmarks, in XEP-0049 private XML storage.
+    Private,
+}
+
+/// The conference bookmarks of the account, as last received from the server.
+#[derive(Clone, Debug, Default)]
+pub struct Bookmarks {
+    pub(crate) storage: Option<Storage>,
+    conferences: HashMap<BareJid, Conference>,
+}
+
+impl Bookmarks {
+    pub fn get(&self, room: &BareJid) -> Option<&Conference> {
+        self.conferences.get(room)
+    }
+
+    pub fn contains(&self, room: &BareJid) -> bool {
+        self.conferences.contains_key(room)
+    }
+
+    pub fn len(&self) -> usize {
+        self.conferences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conferences.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BareJid, &Conference)> {
+        self.conferences.iter()
+    }
+
+    /// Replace all the bookmarks, returning the events describing the
+    /// differences with the previous ones.
+    pub(crate) fn replace(&mut self, conferences: Vec<(BareJid, Conference)>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut old = core::mem::take(&mut self.conferences);
+        for (room, conference) in conferences {
+            if old.remove(&room).as_ref() != Some(&conference) {
+                events.push(Event::BookmarkChanged(room.clone(), conference.clone()));
+            }
+            self.conferences.insert(room, conference);
+        }
+        events.extend(old.into_keys().map(Event::BookmarkRemoved));
+        events
+    }
+
+    /// Add, replace or remove the bookmark of a room, returning the event it
+    /// produces.
+    pub(crate) fn set(&mut self, room: BareJid, conference: Option<Conference>) -> Option<Event> {
+        match conference {
+            Some(conference) => {
+                if self.conferences.get(&room) == Some(&conference) {
+                    return None;
+                }
+                self.conferences.insert(room.clone(), conference.clone());
+                Some(Event::BookmarkChanged(room, conference))
+            }
+            None => self
+                .conferences
+                .remove(&room)
+                .map(|_| Event::BookmarkRemoved(room)),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::str::FromStr;
+
+use crate::{
+    jid::{BareJid, Jid},
+    minidom::Element,
+    muc::room::{JoinRoomSettings, LeaveRoomSettings},
+    parsers::{
+        bookmarks::Storage,
+        bookmarks2::Conference,
+        pubsub::{event::Payload, pubsub::Items, ItemId},
+    },
+    Agent, Event, RoomNick,
+};
+
+fn parse_item(id: Option<&ItemId>, payload: Option<Element>) -> Option<(BareJid, Conference)> {
+    let Some(id) = id else {
+        warn!("Ignoring bookmark without an id");
+        return None;
+    };
+    let room = match BareJid::from_str(&id.0) {
+        Ok(room) => room,
+        Err(e) => {
+            warn!("Ignoring bookmark of invalid room {}: {e}", id.0);
+            return None;
+        }
+    };
+    match payload.map(Conference::try_from) {
+        Some(Ok(conference)) => Some((room, conference)),
+        Some(Err(e)) => {
+            warn!("Wrong payload type in bookmarks2 item: {e}");
+            None
+        }
+        None => {
+            warn!("Ignoring bookmark of {room} without a payload");
+            None
+        }
+    }
+}
+
+/// Produce the events of these changes to the bookmarks, joining the rooms
+/// which are now to be joined automatically, and leaving the ones which
+/// aren't any more.
+pub(crate) async fn apply(agent: &mut Agent, events: &mut Vec<Event>, changes: Vec<Event>) {
+    for event in &changes {
+        let (room, conference) = match event {
+            Event::BookmarkChanged(room, conference) => (room, Some(conference)),
+            Event::BookmarkRemoved(room) => (room, None),
+            _ => continue,
+        };
+        let autojoin = conference.is_some_and(|conference| conference.autojoin);
+        let joined = agent.rooms_joined.contains_key(room);
+        if autojoin && !joined && !agent.rooms_joining.contains_key(room) {
+            let conference = conference.unwrap();
+            agent
+                .join_room(JoinRoomSettings {
+                    room: room.clone(),
+                    nick: conference.nick.clone().map(RoomNick::new),
+                    password: conference.password.clone(),
+                    status: None,
+                })
+                .await;
+        } else if !autojoin && joined {
+            // So maybe another client of ours left the room... let's leave it too
+            agent.leave_room(LeaveRoomSettings::new(room.clone())).await;
+        }
+    }
+    events.extend(changes);
+}
+
+/// Handle the answer to our request of the XEP-0402 bookmarks.
+pub async fn handle_bookmarks2_result(agent: &mut Agent, events: &mut Vec<Event>, items: Items) {
+    let conferences = items
+        .items
+        .into_iter()
+        .filter_map(|item| parse_item(item.id.as_ref(), item.payload))
+        .collect();
+    let changes = agent.bookmarks.replace(conferences);
+    apply(agent, events, changes).await;
+}
+
+/// Handle the answer to our request of the XEP-0048 bookmarks.
+pub async fn handle_private_result(agent: &mut Agent, events: &mut Vec<Event>, storage: Storage) {
+    let conferences = storage
+        .conferences
+        .into_iter()
+        .map(|conference| conference.into_bookmarks2())
+        .collect();
+    let changes = agent.bookmarks.replace(conferences);
+    apply(agent, events, changes).await;
+}
+
+/// Handle a change to our XEP-0402 bookmarks, notified by our server.
+pub async fn handle_event(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &Jid,
+    payload: Payload,
+) {
+    // Only our own account may modify our bookmarks.
+    if *from != agent.client.bound_jid().unwrap().to_bare() {
+        warn!("Received a bookmarks2 event from {from}, ignoring");
+        return;
+    }
+
+    let changes = match payload {
+        Payload::Items {
+            published,
+            retracted,
+            ..
+        } => {
+            let mut changes = Vec::new();
+            for item in published {
+                if let Some((room, conference)) = parse_item(item.id.as_ref(), item.payload) {
+                    changes.extend(agent.bookmarks.set(room, Some(conference)));
+                }
+            }
+            for id in retracted {
+                match BareJid::from_str(&id.0) {
+                    Ok(room) => changes.extend(agent.bookmarks.set(room, None)),
+                    Err(e) => warn!("Ignoring retracted bookmark of invalid room {}: {e}", id.0),
+                }
+            }
+            changes
+        }
+        Payload::Purge { .. } | Payload::Delete { .. } => {
+            warn!("The bookmarks2 PEP node was emptied!");
+            agent.bookmarks.replace(Vec::new())
+        }
+        _ => Vec::new(),
+    };
+    apply(agent, events, changes).await;
+}
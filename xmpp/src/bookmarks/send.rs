@@ -0,0 +1,144 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    bookmarks::{receive, Storage},
+    iq::{request, IqKind},
+    jid::{BareJid, Jid},
+    parsers::{
+        bookmarks::{self, Storage as PrivateStorage},
+        bookmarks2::Conference,
+        disco::{DiscoInfoQuery, DiscoInfoResult},
+        iq::Iq,
+        ns,
+        private::Query as PrivateXMLQuery,
+        pubsub::pubsub::{Items, PubSub},
+        stanza_error::StanzaError,
+    },
+    pubsub::{
+        self,
+        send::{own_jid, PublishSettings},
+    },
+    Agent,
+};
+
+/// Where the bookmarks are stored, according to the features of our
+/// account.
+pub(crate) fn storage_from_disco(disco: &DiscoInfoResult) -> Storage {
+    if disco
+        .features
+        .iter()
+        .any(|feature| feature.var == ns::BOOKMARKS2_COMPAT)
+    {
+        Storage::Pep
+    } else {
+        Storage::Private
+    }
+}
+
+/// Request all the bookmarks, from where they are stored.
+pub(crate) async fn request_bookmarks(agent: &mut Agent, storage: Storage) {
+    let iq = match storage {
+        // XEP-0402 bookmarks (modern)
+        Storage::Pep => {
+            let pubsub = PubSub::Items {
+                items: Items::new(ns::BOOKMARKS2),
+                set_query: None,
+                set_result: None,
+            };
+            Iq::from_get("bookmarks", pubsub)
+        }
+        // XEP-0048 v1.0 bookmarks (legacy)
+        Storage::Private => Iq::from_get(
+            "bookmarks-legacy",
+            PrivateXMLQuery {
+                storage: PrivateStorage::new(),
+            },
+        ),
+    };
+    let _ = agent.client.send_stanza(iq.into()).await;
+}
+
+/// Where the bookmarks are stored, asking our account if it isn't known yet.
+async fn storage(agent: &mut Agent) -> Result<Storage, StanzaError> {
+    if let Some(storage) = agent.bookmarks.storage {
+        return Ok(storage);
+    }
+    let own = Jid::from(own_jid(agent)?);
+    let disco: DiscoInfoResult =
+        request::request(agent, own, IqKind::Get, DiscoInfoQuery { node: None }).await?;
+    let storage = storage_from_disco(&disco);
+    agent.bookmarks.storage = Some(storage);
+    Ok(storage)
+}
+
+/// Modify the XEP-0048 bookmarks, keeping the ones we don't know about.
+async fn update_private<F>(agent: &mut Agent, update: F) -> Result<(), StanzaError>
+where
+    F: FnOnce(&mut Vec<bookmarks::Conference>),
+{
+    let own = Jid::from(own_jid(agent)?);
+    let query = PrivateXMLQuery {
+        storage: PrivateStorage::new(),
+    };
+    let mut query: PrivateXMLQuery =
+        request::request(agent, own.clone(), IqKind::Get, query).await?;
+    update(&mut query.storage.conferences);
+    request::request_empty(agent, own, IqKind::Set, query).await
+}
+
+/// Produce the event of a change we made, once the server accepted it.
+async fn apply(agent: &mut Agent, room: BareJid, conference: Option<Conference>) {
+    let changes = agent.bookmarks.set(room, conference).into_iter().collect();
+    let mut events = Vec::new();
+    receive::apply(agent, &mut events, changes).await;
+    agent.pending_events.extend(events);
+}
+
+/// Add the bookmark of a room, or replace it.
+pub async fn set_bookmark(
+    agent: &mut Agent,
+    room: BareJid,
+    conference: Conference,
+) -> Result<(), StanzaError> {
+    match storage(agent).await? {
+        Storage::Pep => {
+            let settings = PublishSettings::new(ns::BOOKMARKS2, conference.clone())
+                .with_id(room.as_str())
+                .private();
+            pubsub::send::publish(agent, settings).await?;
+        }
+        Storage::Private => {
+            let legacy = bookmarks::Conference::from_bookmarks2(room.clone(), conference.clone());
+            update_private(agent, |conferences| {
+                match conferences.iter_mut().find(|c| c.jid == legacy.jid) {
+                    Some(previous) => *previous = legacy,
+                    None => conferences.push(legacy),
+                }
+            })
+            .await?;
+        }
+    }
+    apply(agent, room, Some(conference)).await;
+    Ok(())
+}
+
+/// Remove the bookmark of a room.
+pub async fn remove_bookmark(agent: &mut Agent, room: BareJid) -> Result<(), StanzaError> {
+    match storage(agent).await? {
+        Storage::Pep => {
+            pubsub::send::retract_item(agent, None, ns::BOOKMARKS2, room.as_str(), true).await?;
+        }
+        Storage::Private => {
+            update_private(agent, |conferences| {
+                conferences.retain(|c| c.jid != room);
+            })
+            .await?;
+        }
+    }
+    apply(agent, room, None).await;
+    Ok(())
+}
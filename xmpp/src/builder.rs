@@ -8,16 +8,33 @@
 use crate::tokio_xmpp::connect::{DnsConfig, StartTlsServerConnector};
 use alloc::sync::Arc;
 use core::str::FromStr;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "avatars")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use xso::{AsXml, FromXml};
 
+#[cfg(feature = "avatars")]
+use crate::pubsub::avatar::AvatarCache;
 use crate::{
+    commands::{CommandHandler, CommandRegistry},
+    iq::handlers::{IqHandlers, IqKind},
     jid::{BareJid, Jid, ResourceRef},
+    mam,
     parsers::{
         disco::{DiscoInfoResult, Feature, Identity},
         ns,
+        pubsub::PubSubPayload,
+        stanza_error::StanzaError,
+    },
+    pubsub::{handlers::PubSubHandlers, PubSubNotification},
+    store::{MemoryStateStore, StateResumptionStore, StateStore, StateTokenStore},
+    tokio_xmpp::{
+        connect::ServerConnector, xmlstream::Timeouts, Client as TokioXmppClient, LoginSettings,
+        SaslPolicy,
     },
-    tokio_xmpp::{connect::ServerConnector, xmlstream::Timeouts, Client as TokioXmppClient},
     Agent, ClientFeature, RoomNick,
 };
 
@@ -53,6 +70,15 @@ pub struct ClientBuilder<'a, C: ServerConnector> {
     features: Vec<ClientFeature>,
     resource: Option<String>,
     timeouts: Timeouts,
+    login_settings: LoginSettings,
+    state: Option<Arc<dyn StateStore>>,
+    commands: CommandRegistry,
+    iq_handlers: IqHandlers,
+    pubsub_handlers: PubSubHandlers,
+    software_version: Option<(String, String)>,
+    request_timeout: Duration,
+    #[cfg(feature = "avatars")]
+    avatar_cache: PathBuf,
 }
 
 #[cfg(any(feature = "starttls-rust", feature = "starttls-native"))]
@@ -61,7 +87,7 @@ impl ClientBuilder<'_, StartTlsServerConnector> {
         Self::new_with_connector(
             jid.clone(),
             password,
-            StartTlsServerConnector(DnsConfig::srv_default_client(jid.domain())),
+            StartTlsServerConnector::from(DnsConfig::srv_default_client(jid.domain())),
         )
     }
 }
@@ -83,6 +109,15 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
             features: vec![],
             resource: None,
             timeouts: Timeouts::default(),
+            login_settings: LoginSettings::default(),
+            state: None,
+            commands: CommandRegistry::default(),
+            iq_handlers: IqHandlers::default(),
+            pubsub_handlers: PubSubHandlers::default(),
+            software_version: None,
+            request_timeout: Duration::from_secs(30),
+            #[cfg(feature = "avatars")]
+            avatar_cache: PathBuf::from("data/avatars"),
         }
     }
 
@@ -121,6 +156,123 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
         self
     }
 
+    /// Configure how the client logs into its account.
+    ///
+    /// See [`LoginSettings`] for the available settings and their defaults.
+    pub fn set_login_settings(mut self, login_settings: LoginSettings) -> Self {
+        self.login_settings = login_settings;
+        self
+    }
+
+    /// Restrict which SASL mechanisms may be used to log in, e.g. to never
+    /// send the password in clear using PLAIN.
+    pub fn set_sasl_policy(mut self, sasl_policy: SaslPolicy) -> Self {
+        self.login_settings.sasl_policy = sasl_policy;
+        self
+    }
+
+    /// Keep the state of the agent in this store between runs: the contact
+    /// list, so that the server only sends what changed since if it supports
+    /// roster versioning, the position reached in each message archive, the
+    /// XEP-0484 (FAST) tokens, and the stream management (XEP-0198) state, so
+    /// that the stream can be resumed after a restart.  The tokens and the
+    /// stream management state are left to the token store and resumption
+    /// store of the login settings, if they have one.
+    ///
+    /// Avatars aren't stored, they have their own directory, see
+    /// `set_avatar_cache`.
+    ///
+    /// By default, the state is only kept in memory, across reconnections.
+    pub fn set_state_store<S: StateStore>(mut self, store: S) -> Self {
+        self.state = Some(Arc::new(store));
+        self
+    }
+
+    /// Provide an ad-hoc command to other entities, under this node.
+    ///
+    /// It is advertised to the entities allowed to execute it, see
+    /// [CommandHandler::is_allowed].
+    pub fn register_command(
+        mut self,
+        node: &str,
+        name: &str,
+        handler: impl CommandHandler + 'static,
+    ) -> Self {
+        self.commands
+            .register(String::from(node), String::from(name), Box::new(handler));
+        self
+    }
+
+    /// Answer the `<iq type='get'/>` requests whose payload has this name and
+    /// namespace, the namespace being advertised as a disco feature.
+    ///
+    /// The handler receives the sender and the parsed payload, and returns
+    /// either the payload of the result, which may be an [Option] to send an
+    /// empty result, or the error to send back.  Handlers registered for
+    /// ping, software version, entity time or last activity replace the
+    /// built-in ones.
+    pub fn register_iq_get<Req, Resp, F>(mut self, name: &str, ns: &str, handler: F) -> Self
+    where
+        Req: FromXml + 'static,
+        Resp: AsXml + 'static,
+        F: Fn(&Jid, Req) -> Result<Resp, StanzaError> + Send + Sync + 'static,
+    {
+        self.iq_handlers.register(IqKind::Get, name, ns, handler);
+        self
+    }
+
+    /// Answer the `<iq type='set'/>` requests whose payload has this name and
+    /// namespace, see [ClientBuilder::register_iq_get].
+    pub fn register_iq_set<Req, Resp, F>(mut self, name: &str, ns: &str, handler: F) -> Self
+    where
+        Req: FromXml + 'static,
+        Resp: AsXml + 'static,
+        F: Fn(&Jid, Req) -> Result<Resp, StanzaError> + Send + Sync + 'static,
+    {
+        self.iq_handlers.register(IqKind::Set, name, ns, handler);
+        self
+    }
+
+    /// Pass the events of this PubSub node to `handler`, with the payload of
+    /// its items parsed as `P`.
+    ///
+    /// Our interest in the node is advertised through entity capabilities,
+    /// so that the PEP nodes of our contacts are notified without having to
+    /// subscribe to them.  The handler runs while events are being handled,
+    /// and shouldn't block.
+    pub fn register_pubsub_handler<P, F>(mut self, node: &str, handler: F) -> Self
+    where
+        P: PubSubPayload + 'static,
+        P::Error: core::fmt::Display,
+        F: Fn(&Jid, PubSubNotification<P>) + Send + Sync + 'static,
+    {
+        self.pubsub_handlers.register(node, handler);
+        self
+    }
+
+    /// The name and version of the software, as answered to software version
+    /// requests.  By default, the client name is used along with the version
+    /// of this library.
+    pub fn set_software_version(mut self, name: &str, version: &str) -> Self {
+        self.software_version = Some((String::from(name), String::from(version)));
+        self
+    }
+
+    /// How long [Agent::request] waits for a response, 30 seconds by
+    /// default.
+    pub fn set_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// The directory where the avatars of other entities are stored, named
+    /// after their hash, `data/avatars` by default.
+    #[cfg(feature = "avatars")]
+    pub fn set_avatar_cache<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.avatar_cache = directory.into();
+        self
+    }
+
     pub fn enable_feature(mut self, feature: ClientFeature) -> Self {
         self.features.push(feature);
         self
@@ -133,7 +285,12 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
             "en",
             self.disco.1.to_string(),
         )];
-        let mut features = vec![Feature::new(ns::DISCO_INFO)];
+        let mut features = vec![
+            Feature::new(ns::DISCO_INFO),
+            Feature::new(ns::CHATSTATES),
+            Feature::new(ns::RECEIPTS),
+            Feature::new(ns::CHAT_MARKERS),
+        ];
         #[cfg(feature = "avatars")]
         {
             if self.features.contains(&ClientFeature::Avatars) {
@@ -143,6 +300,21 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
         if self.features.contains(&ClientFeature::JoinRooms) {
             features.push(Feature::new(format!("{}+notify", ns::BOOKMARKS2)));
         }
+        if self.features.contains(&ClientFeature::DisplayedSync) {
+            features.push(Feature::new(format!("{}+notify", ns::MDS)));
+        }
+        if !self.commands.is_empty() {
+            features.push(Feature::new(ns::COMMANDS));
+        }
+        for feature in self.iq_handlers.features() {
+            features.push(Feature::new(feature));
+        }
+        for node in self.pubsub_handlers.nodes() {
+            let feature = Feature::new(format!("{}+notify", node));
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
         DiscoInfoResult {
             node: None,
             identities,
@@ -151,26 +323,62 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
         }
     }
 
-    pub fn build(self) -> Agent {
+    pub fn build(mut self) -> Agent {
         let jid: Jid = if let Some(resource) = &self.resource {
             self.jid.with_resource_str(resource).unwrap().into()
         } else {
             self.jid.clone().into()
         };
 
-        let client = TokioXmppClient::new_with_connector(
+        // Enable carbons while binding when the server supports it, saving a
+        // round trip.
+        if self.features.contains(&ClientFeature::Carbons) {
+            self.login_settings.enable_carbons = true;
+        }
+
+        if let Some(state) = &self.state {
+            if self.login_settings.token_store.is_none() {
+                let tokens = StateTokenStore::new(state.clone());
+                self.login_settings.token_store = Some(Arc::new(tokens));
+            }
+            if self.login_settings.resumption_store.is_none() {
+                let resumption = StateResumptionStore::new(state.clone());
+                self.login_settings.resumption_store = Some(Arc::new(resumption));
+            }
+        }
+
+        let client = TokioXmppClient::new_with_settings(
             jid,
             self.password,
             self.server_connector.clone(),
             self.timeouts,
+            self.login_settings.clone(),
         );
         self.build_impl(client)
     }
 
     // This function is meant to be used for testing build
-    pub(crate) fn build_impl(self, client: TokioXmppClient) -> Agent {
+    pub(crate) fn build_impl(mut self, client: TokioXmppClient) -> Agent {
+        let software_version = self.software_version.take().unwrap_or_else(|| {
+            (
+                self.disco.1.clone(),
+                String::from(env!("CARGO_PKG_VERSION")),
+            )
+        });
+        let last_activity = Arc::new(Mutex::new(Instant::now()));
+        self.iq_handlers
+            .register_builtins(software_version, last_activity.clone());
         let disco = self.make_disco();
         let node = self.website;
+        let state = self
+            .state
+            .unwrap_or_else(|| Arc::new(MemoryStateStore::new()));
+        let archive_positions = mam::load_positions(&*state);
+        #[cfg(feature = "avatars")]
+        let avatar_cache = self
+            .features
+            .contains(&ClientFeature::Avatars)
+            .then(|| AvatarCache::new(self.avatar_cache));
 
         Agent {
             client,
@@ -180,9 +388,40 @@ impl<C: ServerConnector> ClientBuilder<'_, C> {
             node,
             uploads: Vec::new(),
             awaiting_disco_bookmarks_type: false,
+            bookmarks: Default::default(),
+            carbons: self.features.contains(&ClientFeature::Carbons),
+            displayable: HashMap::new(),
+            displayed_sync: self.features.contains(&ClientFeature::DisplayedSync),
+            displayed: HashMap::new(),
             rooms_joined: HashMap::new(),
             rooms_joining: HashMap::new(),
             rooms_leaving: HashMap::new(),
+            mam_queries: HashMap::new(),
+            mam_query_counter: 0,
+            archive_positions,
+            roster: Default::default(),
+            state,
+            session_started: false,
+            roster_set_counter: 0,
+            presences: Default::default(),
+            own_presence: Default::default(),
+            room_occupants: HashMap::new(),
+            muc_admin_queries: HashMap::new(),
+            muc_admin_counter: 0,
+            rooms_configuring: HashSet::new(),
+            muc_config_queries: HashMap::new(),
+            command_registry: self.commands,
+            command_queries: HashMap::new(),
+            command_counter: 0,
+            iq_handlers: self.iq_handlers,
+            pubsub_handlers: self.pubsub_handlers,
+            last_activity,
+            request_timeout: self.request_timeout,
+            pending_events: Vec::new(),
+            #[cfg(feature = "avatars")]
+            avatar_cache,
+            #[cfg(feature = "avatars")]
+            own_avatar: None,
         }
     }
 }
@@ -0,0 +1,156 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Ad-Hoc Commands (XEP-0050), both to execute the commands of other
+//! entities and to provide our own.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::{
+    jid::Jid,
+    parsers::{
+        commands::{Action, Actions, Note},
+        data_forms::DataForm,
+    },
+};
+
+pub mod receive;
+pub mod send;
+
+pub use send::CommandSettings;
+
+/// A command this client provides to other entities, registered with
+/// [ClientBuilder::register_command][crate::ClientBuilder::register_command].
+pub trait CommandHandler: Send + Sync {
+    /// Whether this entity may list and execute this command.  Our own
+    /// account always may, other entities only if this returns true.
+    fn is_allowed(&self, _from: &Jid) -> bool {
+        false
+    }
+
+    /// Start a new session of this command, for this entity.
+    fn start(&self, from: &Jid) -> Box<dyn CommandSession>;
+}
+
+/// A session of a command, from its first stage until it is completed or
+/// cancelled.
+pub trait CommandSession: Send {
+    /// Proceed with the command.
+    ///
+    /// The first stage is executed with [Action::Execute] and no form; the
+    /// next ones get the action chosen by the requester, amongst the ones
+    /// allowed by the previous stage, and the form they submitted.  Executing
+    /// at a later stage is replaced by the default action of the previous
+    /// one, so it is never passed here again.
+    /// Cancellation is handled before reaching the session, which is then
+    /// dropped.
+    fn step(&mut self, action: Action, form: Option<DataForm>) -> CommandStep;
+}
+
+/// What to answer to the requester after a stage.
+#[derive(Debug, Clone)]
+pub enum CommandStep {
+    /// Ask for more input, the session stays open.
+    Form {
+        /// The form to fill.
+        form: DataForm,
+        /// The actions the requester can take besides cancelling.
+        actions: Actions,
+        /// Messages to show along with the form.
+        notes: Vec<Note>,
+    },
+    /// The command is finished, the session gets closed.
+    Completed {
+        /// A form containing the result, if any.
+        form: Option<DataForm>,
+        /// Messages to show about the result.
+        notes: Vec<Note>,
+    },
+}
+
+pub(crate) struct RegisteredCommand {
+    pub name: String,
+    pub handler: Box<dyn CommandHandler>,
+}
+
+/// How long a session may stay idle before it gets dropped.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How many sessions an entity may keep open at once.
+pub(crate) const MAX_SESSIONS: usize = 8;
+
+pub(crate) struct OpenSession {
+    pub from: Jid,
+    pub node: String,
+    pub session: Box<dyn CommandSession>,
+    // The actions offered by the last stage
+    pub actions: Actions,
+    pub last_used: Instant,
+}
+
+impl OpenSession {
+    /// The action to take at the current stage when the requester asks for
+    /// this one, or `None` if it isn't offered.
+    ///
+    /// Executing means taking the default action of the stage, or its only
+    /// way forward when there is no default.
+    pub fn resolve(&self, action: Action) -> Option<Action> {
+        let allowed = match action {
+            Action::Cancel => true,
+            Action::Prev => self.actions.prev,
+            Action::Next => self.actions.next,
+            Action::Complete => self.actions.complete,
+            Action::Execute => {
+                return match (
+                    &self.actions.execute,
+                    self.actions.next,
+                    self.actions.complete,
+                ) {
+                    (Some(Action::Execute), _, _) => None,
+                    (Some(default), _, _) => self.resolve(default.clone()),
+                    (None, true, false) => Some(Action::Next),
+                    (None, false, true) => Some(Action::Complete),
+                    (None, _, _) => None,
+                }
+            }
+        };
+        allowed.then_some(action)
+    }
+}
+
+/// The commands we provide, and their open sessions.
+#[derive(Default)]
+pub(crate) struct CommandRegistry {
+    pub commands: BTreeMap<String, RegisteredCommand>,
+    pub sessions: HashMap<String, OpenSession>,
+    pub session_counter: u64,
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, node: String, name: String, handler: Box<dyn CommandHandler>) {
+        self.commands
+            .insert(node, RegisteredCommand { name, handler });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Drop the sessions which stayed idle for too long.
+    pub fn expire_sessions(&mut self, now: Instant) {
+        self.sessions
+            .retain(|_, open| now.duration_since(open.last_used) < SESSION_TIMEOUT);
+    }
+
+    /// How many sessions this entity has open.
+    pub fn session_count(&self, from: &Jid) -> usize {
+        self.sessions
+            .values()
+            .filter(|open| open.from == *from)
+            .count()
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Instant;
+
+use crate::{
+    commands::{CommandStep, OpenSession, MAX_SESSIONS},
+    jid::Jid,
+    minidom::Element,
+    parsers::{
+        commands::{Action, Command, Status},
+        disco::{DiscoItemsQuery, DiscoItemsResult, Item as DiscoItem},
+        iq::Iq,
+        ns,
+        stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    },
+    Agent, Event,
+};
+
+async fn send_error(
+    agent: &mut Agent,
+    to: Jid,
+    id: String,
+    condition: DefinedCondition,
+    text: &str,
+) {
+    let type_ = match condition {
+        DefinedCondition::BadRequest => ErrorType::Modify,
+        DefinedCondition::ResourceConstraint => ErrorType::Wait,
+        _ => ErrorType::Cancel,
+    };
+    let error = StanzaError::new(type_, condition, "en", text);
+    let iq = Iq::from_error(id, error).with_to(to).into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+/// Refuse an action the current stage doesn't offer.
+async fn send_bad_action(agent: &mut Agent, to: Jid, id: String) {
+    let mut error = StanzaError::new(
+        ErrorType::Modify,
+        DefinedCondition::BadRequest,
+        "en",
+        "Action not allowed at this stage.",
+    );
+    error.other = Some(Element::builder("bad-action", ns::COMMANDS).build());
+    let iq = Iq::from_error(id, error).with_to(to).into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+fn is_allowed(agent: &Agent, from: &Jid, node: &str) -> bool {
+    let Some(command) = agent.command_registry.commands.get(node) else {
+        return false;
+    };
+    let own = agent
+        .client
+        .bound_jid()
+        .is_some_and(|own| own.to_bare() == from.to_bare());
+    own || command.handler.is_allowed(from)
+}
+
+/// Answer a disco#items query on the commands node with the commands this
+/// entity may execute.
+pub async fn handle_disco_items(agent: &mut Agent, from: Jid, id: String, query: DiscoItemsQuery) {
+    let Some(own_jid) = agent.client.bound_jid().cloned() else {
+        return;
+    };
+    let items = agent
+        .command_registry
+        .commands
+        .iter()
+        .filter(|(node, _)| is_allowed(agent, &from, node))
+        .map(|(node, command)| DiscoItem {
+            jid: own_jid.clone(),
+            node: Some(node.clone()),
+            name: Some(command.name.clone()),
+        })
+        .collect();
+    let result = DiscoItemsResult {
+        node: query.node,
+        items,
+        rsm: None,
+    };
+    let iq = Iq::from_result(id, Some(result)).with_to(from).into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+/// Execute a stage of one of our commands for another entity.
+pub async fn handle_command_request(agent: &mut Agent, from: Jid, id: String, payload: Element) {
+    let command = match Command::try_from(payload) {
+        Ok(command) => command,
+        Err(e) => {
+            let text = format!("{}", e);
+            send_error(agent, from, id, DefinedCondition::BadRequest, &text).await;
+            return;
+        }
+    };
+    let node = command.node;
+
+    if !agent.command_registry.commands.contains_key(&node) {
+        send_error(
+            agent,
+            from,
+            id,
+            DefinedCondition::ItemNotFound,
+            "Unknown command.",
+        )
+        .await;
+        return;
+    }
+    if !is_allowed(agent, &from, &node) {
+        send_error(agent, from, id, DefinedCondition::Forbidden, "Not allowed.").await;
+        return;
+    }
+
+    let now = Instant::now();
+    let registry = &mut agent.command_registry;
+    registry.expire_sessions(now);
+    let mut action = command.action.unwrap_or(Action::Execute);
+    let (sessionid, mut open) = match command.sessionid {
+        None => {
+            if registry.session_count(&from) >= MAX_SESSIONS {
+                send_error(
+                    agent,
+                    from,
+                    id,
+                    DefinedCondition::ResourceConstraint,
+                    "Too many sessions.",
+                )
+                .await;
+                return;
+            }
+            let handler = &registry.commands[&node].handler;
+            let session = handler.start(&from);
+            registry.session_counter += 1;
+            let sessionid = format!("{}-{}", node, registry.session_counter);
+            let open = OpenSession {
+                from: from.clone(),
+                node: node.clone(),
+                session,
+                actions: Default::default(),
+                last_used: now,
+            };
+            (sessionid, open)
+        }
+        Some(sessionid) => match registry.sessions.remove(&sessionid) {
+            Some(open) if open.from == from && open.node == node => match open.resolve(action) {
+                Some(resolved) => {
+                    action = resolved;
+                    (sessionid, open)
+                }
+                None => {
+                    registry.sessions.insert(sessionid, open);
+                    send_bad_action(agent, from, id).await;
+                    return;
+                }
+            },
+            Some(open) => {
+                // Someone else's session, keep it open.
+                registry.sessions.insert(sessionid, open);
+                send_error(
+                    agent,
+                    from,
+                    id,
+                    DefinedCondition::BadRequest,
+                    "Bad session.",
+                )
+                .await;
+                return;
+            }
+            None => {
+                send_error(
+                    agent,
+                    from,
+                    id,
+                    DefinedCondition::BadRequest,
+                    "Bad session.",
+                )
+                .await;
+                return;
+            }
+        },
+    };
+
+    let response = if action == Action::Cancel {
+        Command::response(node, sessionid, Status::Canceled)
+    } else {
+        match open.session.step(action, command.form) {
+            CommandStep::Form {
+                form,
+                actions,
+                notes,
+            } => {
+                let mut response = Command::response(node, sessionid.clone(), Status::Executing)
+                    .with_actions(actions.clone())
+                    .with_form(form);
+                response.notes = notes;
+                open.actions = actions;
+                open.last_used = now;
+                agent.command_registry.sessions.insert(sessionid, open);
+                response
+            }
+            CommandStep::Completed { form, notes } => {
+                let mut response = Command::response(node, sessionid, Status::Completed);
+                response.form = form;
+                response.notes = notes;
+                response
+            }
+        }
+    };
+
+    let iq = Iq::from_result(id, Some(response)).with_to(from).into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+/// Handle the list of commands of another entity.
+pub fn handle_commands_list(events: &mut Vec<Event>, from: Jid, result: DiscoItemsResult) {
+    events.push(Event::CommandList(from, result.items));
+}
+
+/// Handle the response of another entity to one of our requests.
+pub fn handle_command_response(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: Jid,
+    id: &str,
+    command: Command,
+) {
+    if agent.command_queries.remove(id).is_none() {
+        warn!("Received unexpected command response {id} from {from}");
+        return;
+    }
+    events.push(Event::CommandResponse(from, command));
+}
+
+/// Report a command request which failed.
+pub fn handle_error(agent: &mut Agent, events: &mut Vec<Event>, id: &str, error: &StanzaError) {
+    if let Some((to, node)) = agent.command_queries.remove(id) {
+        events.push(Event::CommandError(to, node, error.clone()));
+    }
+}
+
+/// Whether this disco#items result lists commands.
+pub(crate) fn is_commands_list(result: &DiscoItemsResult) -> bool {
+    result.node.as_deref() == Some(ns::COMMANDS)
+}
@@ -0,0 +1,97 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::Jid,
+    parsers::{
+        commands::{Action, Command},
+        data_forms::DataForm,
+        disco::DiscoItemsQuery,
+        iq::Iq,
+        ns,
+    },
+    Agent,
+};
+
+/// A request to execute a command of another entity, or to proceed with it.
+#[derive(Clone, Debug)]
+pub struct CommandSettings {
+    pub to: Jid,
+    pub node: String,
+    pub sessionid: Option<String>,
+    pub action: Option<Action>,
+    pub form: Option<DataForm>,
+}
+
+impl CommandSettings {
+    /// Start executing this command.
+    pub fn new(to: Jid, node: impl Into<String>) -> Self {
+        Self {
+            to,
+            node: node.into(),
+            sessionid: None,
+            action: None,
+            form: None,
+        }
+    }
+
+    /// Proceed with a session, as returned in [Event::CommandResponse][crate::Event::CommandResponse].
+    pub fn with_sessionid(mut self, sessionid: impl Into<String>) -> Self {
+        self.sessionid = Some(sessionid.into());
+        self
+    }
+
+    pub fn with_action(mut self, action: Action) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Submit this form, filled from the one of the previous stage.
+    pub fn with_form(mut self, form: DataForm) -> Self {
+        self.form = Some(form);
+        self
+    }
+}
+
+/// Request the list of commands an entity provides.
+pub async fn list_commands(agent: &mut Agent, to: Jid) {
+    agent.command_counter += 1;
+    let id = format!("commands-{}", agent.command_counter);
+    let query = DiscoItemsQuery {
+        node: Some(String::from(ns::COMMANDS)),
+        rsm: None,
+    };
+    let iq = Iq::from_get(id, query).with_to(to);
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to request the list of commands: {}", e);
+    }
+}
+
+pub async fn execute_command(agent: &mut Agent, settings: CommandSettings) {
+    let CommandSettings {
+        to,
+        node,
+        sessionid,
+        action,
+        form,
+    } = settings;
+
+    let command = Command {
+        sessionid,
+        action: Some(action.unwrap_or(Action::Execute)),
+        form,
+        ..Command::new(node.clone())
+    };
+
+    agent.command_counter += 1;
+    let id = format!("command-{}", agent.command_counter);
+    let iq = Iq::from_set(id.clone(), command).with_to(to.clone());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to send command {} to {}: {}", node, to, e);
+        return;
+    }
+    agent.command_queries.insert(id, (to, node));
+}
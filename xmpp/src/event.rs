@@ -4,12 +4,26 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use tokio_xmpp::jid::BareJid;
-#[cfg(feature = "avatars")]
-use tokio_xmpp::jid::Jid;
-use tokio_xmpp::parsers::{message::Body, roster::Item as RosterItem};
+use tokio_xmpp::jid::{BareJid, FullJid, Jid};
+use tokio_xmpp::parsers::{
+    bookmarks2::Conference,
+    chatstates::ChatState,
+    commands::Command,
+    disco::Item as DiscoItem,
+    message::Body,
+    muc::user::{Affiliation, Role},
+    roster::Item as RosterItem,
+    stanza_error::StanzaError,
+};
 
-use crate::{delay::StanzaTimeInfo, Error, MessageId, RoomNick};
+use crate::{
+    delay::StanzaTimeInfo,
+    mam::History,
+    message::carbons::Carbon,
+    muc::{Occupant, RoomConfig},
+    presence::ResourcePresence,
+    Error, MessageId, RoomNick,
+};
 
 #[derive(Debug)]
 pub enum Event {
@@ -18,8 +32,26 @@ pub enum Event {
     ContactAdded(RosterItem),
     ContactRemoved(RosterItem),
     ContactChanged(RosterItem),
+    /// A contact asked for a subscription to our presence, see
+    /// [`Agent::approve_subscription`][crate::Agent::approve_subscription].
+    /// - The [`BareJid`] is the contact's JID.
+    /// - The String is the status message sent along, if any.
+    SubscriptionRequest(BareJid, Option<String>),
+    /// A resource of a contact became available, or changed its presence.
+    /// - The [`FullJid`] is the resource's JID.
+    /// - The [`ResourcePresence`] is its new presence.
+    PresenceChanged(FullJid, ResourcePresence),
+    /// A resource of a contact became unavailable.
+    /// - The [`FullJid`] is the resource's JID.
+    PresenceUnavailable(FullJid),
+    /// The avatar of an entity was retrieved.
+    /// - The [`Jid`] is the entity's JID.
+    /// - The String is the path of the image, in the avatar cache.
     #[cfg(feature = "avatars")]
     AvatarRetrieved(Jid, String),
+    /// An entity stopped publishing its avatar.
+    #[cfg(feature = "avatars")]
+    AvatarRemoved(Jid),
     /// A chat message was received. It may have been delayed on the network.
     /// - The [`MessageId`] is a unique identifier for this message.
     /// - The [`BareJid`] is the sender's JID.
@@ -32,8 +64,107 @@ pub enum Event {
     /// - The [`Body`] is the new body of the message, to replace the old one.
     /// - The [`StanzaTimeInfo`] is the time the message correction was sent/received
     ChatMessageCorrection(MessageId, BareJid, Body, StanzaTimeInfo),
+    /// A copy of a chat message sent or received by another of our clients,
+    /// from XEP-0280 (Message Carbons).
+    /// - The [`Carbon`] tells whether it was sent or received.
+    /// - The [`MessageId`] is a unique identifier for this message.
+    /// - The [`BareJid`] is the JID of the contact we chat with.
+    /// - The [`Body`] is the message body.
+    /// - The [`StanzaTimeInfo`] about when message was received, and when the message was claimed sent.
+    ChatMessageCarbon(Carbon, Option<MessageId>, BareJid, Body, StanzaTimeInfo),
+    /// A copy of a message correction sent or received by another of our
+    /// clients.
+    /// - The [`Carbon`] tells whether it was sent or received.
+    /// - The [`MessageId`] is the ID of the message that was corrected.
+    /// - The [`BareJid`] is the JID of the contact we chat with.
+    /// - The [`Body`] is the new body of the message, to replace the old one.
+    /// - The [`StanzaTimeInfo`] is the time the message correction was sent/received
+    ChatMessageCorrectionCarbon(Carbon, MessageId, BareJid, Body, StanzaTimeInfo),
+    /// A message we sent was delivered, see
+    /// [`MessageSettings::with_receipt`][crate::message::send::MessageSettings::with_receipt].
+    /// - The [`BareJid`] is the contact's JID.
+    /// - The [`MessageId`] is the ID of the message delivered.
+    MessageDelivered(BareJid, MessageId),
+    /// A contact displayed the messages we sent, up to this one, see
+    /// [`MessageSettings::with_markable`][crate::message::send::MessageSettings::with_markable].
+    /// - The [`BareJid`] is the contact's JID.
+    /// - The [`MessageId`] is the ID of the last message displayed.
+    MessageDisplayed(BareJid, MessageId),
+    /// One of our clients displayed the messages of a chat, up to this one,
+    /// see [`Agent::mark_displayed`][crate::Agent::mark_displayed].
+    /// - The [`BareJid`] is the JID of the contact we chat with.
+    /// - The String is the stanza-id of the last message displayed, in our
+    ///   archive.
+    ChatDisplayed(BareJid, String),
+    /// A contact notified their chat state, e.g. that they are composing a
+    /// message.
+    /// - The [`BareJid`] is the contact's JID.
+    /// - The [`ChatState`] is their new state.
+    ChatStateChanged(BareJid, ChatState),
+    /// A room was bookmarked, or its bookmark changed, see
+    /// [`Agent::set_bookmark`][crate::Agent::set_bookmark].
+    /// - The [`BareJid`] is the room's JID.
+    /// - The [`Conference`] is its new bookmark.
+    BookmarkChanged(BareJid, Conference),
+    /// The bookmark of a room was removed.
+    BookmarkRemoved(BareJid),
     RoomJoined(BareJid),
     RoomLeft(BareJid),
+    /// We created a room by joining it, which stays locked until configured.
+    /// - The [`BareJid`] is the room's address.
+    RoomCreated(BareJid),
+    /// The configuration form of a room, see
+    /// [`Agent::create_room`][crate::Agent::create_room].
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomConfig`] is the form, to modify and submit.
+    RoomConfigForm(BareJid, RoomConfig),
+    /// The configuration of a room has been accepted.
+    /// - The [`BareJid`] is the room's address.
+    RoomConfigured(BareJid),
+    /// A room we were in has been destroyed, [Event::RoomLeft] follows.
+    /// - The first [`BareJid`] is the room's address.
+    /// - The second [`BareJid`] is an alternate room to join instead, if any.
+    /// - The String is the reason given, if any.
+    RoomDestroyed(BareJid, Option<BareJid>, Option<String>),
+    /// Someone entered a room we are in, or was already there when we joined.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`Occupant`] is the new occupant.
+    OccupantJoined(BareJid, Occupant),
+    /// An occupant left a room of their own accord.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the nickname they had.
+    OccupantLeft(BareJid, RoomNick),
+    /// An occupant changed their nickname.
+    /// - The [`BareJid`] is the room's address.
+    /// - The first [`RoomNick`] is the old nickname.
+    /// - The second [`RoomNick`] is the new nickname.
+    OccupantNickChanged(BareJid, RoomNick, RoomNick),
+    /// An occupant was kicked out of a room.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the nickname they had.
+    /// - The String is the reason given, if any.
+    OccupantKicked(BareJid, RoomNick, Option<String>),
+    /// An occupant was banned from a room.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the nickname they had.
+    /// - The String is the reason given, if any.
+    OccupantBanned(BareJid, RoomNick, Option<String>),
+    /// The role of an occupant changed, e.g. they were granted voice.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the occupant's nickname.
+    /// - The [`Role`] is their new role.
+    OccupantRoleChanged(BareJid, RoomNick, Role),
+    /// The affiliation of an occupant changed, e.g. they became a member.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the occupant's nickname.
+    /// - The [`Affiliation`] is their new affiliation.
+    OccupantAffiliationChanged(BareJid, RoomNick, Affiliation),
+    /// The list of users with an affiliation, see
+    /// [`Agent::request_affiliations`][crate::Agent::request_affiliations].
+    /// - The first [`BareJid`] is the room's address.
+    /// - The [`Affiliation`] is the one requested.
+    /// - The [`Vec`] contains the users having it.
+    RoomAffiliations(BareJid, Affiliation, Vec<BareJid>),
     RoomMessage(Option<MessageId>, BareJid, RoomNick, Body, StanzaTimeInfo),
     /// A message in a MUC was corrected/edited.
     /// - The [`MessageId`] is the ID of the message that was corrected.
@@ -59,4 +190,51 @@ pub enum Event {
     RoomPrivateMessageCorrection(MessageId, BareJid, RoomNick, Body, StanzaTimeInfo),
     ServiceMessage(Option<MessageId>, BareJid, Body, StanzaTimeInfo),
     HttpUploadedFile(String),
+    /// The commands another entity provides, see
+    /// [`Agent::list_commands`][crate::Agent::list_commands].
+    /// - The [`Jid`] is the entity.
+    /// - Each [`DiscoItem`] is a command, with its node and name.
+    CommandList(Jid, Vec<DiscoItem>),
+    /// The response to a stage of a command, see
+    /// [`Agent::execute_command`][crate::Agent::execute_command].
+    /// - The [`Jid`] is the entity executing it.
+    /// - The [`Command`] contains the session, status, form and notes.
+    CommandResponse(Jid, Command),
+    /// A command failed to execute.
+    /// - The [`Jid`] is the entity executing it.
+    /// - The String is the node of the command.
+    /// - The [`StanzaError`] is the error returned.
+    CommandError(Jid, String, StanzaError),
+    /// A one-to-one chat message was fetched from the account's archive.
+    /// - The String is its stanza-id in the archive.
+    /// - The [`MessageId`] is the identifier given by its sender.
+    /// - The first [`BareJid`] is the sender, which may be ourselves.
+    /// - The second [`BareJid`] is the recipient.
+    /// - The [`Body`] is the message body.
+    /// - The [`StanzaTimeInfo`] starts with the delay added by the archive.
+    ArchivedChatMessage(
+        String,
+        Option<MessageId>,
+        BareJid,
+        BareJid,
+        Body,
+        StanzaTimeInfo,
+    ),
+    /// A message was fetched from the archive of a room.
+    /// - The String is its stanza-id in the archive.
+    /// - The [`MessageId`] is the identifier given by its sender.
+    /// - The [`BareJid`] is the room's address.
+    /// - The [`RoomNick`] is the nickname of the sender.
+    /// - The [`Body`] is the message body.
+    /// - The [`StanzaTimeInfo`] starts with the delay added by the archive.
+    ArchivedRoomMessage(
+        String,
+        Option<MessageId>,
+        BareJid,
+        RoomNick,
+        Body,
+        StanzaTimeInfo,
+    ),
+    /// Every page of the requested history has been fetched.
+    HistoryFetched(History),
 }
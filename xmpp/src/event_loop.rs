@@ -6,58 +6,21 @@
 
 use futures::StreamExt;
 use tokio_xmpp::{
-    parsers::{disco::DiscoInfoQuery, iq::Iq, roster::Roster},
+    parsers::{disco::DiscoInfoQuery, iq::Iq},
     Event as TokioXmppEvent, Stanza,
 };
 
-use crate::{iq, message, presence, Agent, Event};
+use crate::{iq, mam, message, presence, roster, Agent, Event};
 
 /// Wait for new events, or Error::Disconnected when stream is closed and will not reconnect.
 pub async fn wait_for_events(agent: &mut Agent) -> Vec<Event> {
-    if let Some(event) = agent.client.next().await {
-        let mut events = Vec::new();
-
-        match event {
-            TokioXmppEvent::Online { resumed: false, .. } => {
-                let presence =
-                    presence::send::make_initial_presence(&agent.disco, &agent.node).into();
-                let _ = agent.client.send_stanza(presence).await;
-                events.push(Event::Online);
-                // TODO: only send this when the ContactList feature is enabled.
-                let iq = Iq::from_get(
-                    "roster",
-                    Roster {
-                        ver: None,
-                        items: vec![],
-                    },
-                )
-                .into();
-                let _ = agent.client.send_stanza(iq).await;
-
-                // Query account disco to know what bookmarks spec is used
-                let iq = Iq::from_get("disco-account", DiscoInfoQuery { node: None }).into();
-                let _ = agent.client.send_stanza(iq).await;
-                agent.awaiting_disco_bookmarks_type = true;
-            }
-            TokioXmppEvent::Online { resumed: true, .. } => {}
-            TokioXmppEvent::Disconnected(e) => {
-                events.push(Event::Disconnected(e));
-            }
-            TokioXmppEvent::Stanza(Stanza::Iq(iq)) => {
-                let new_events = iq::handle_iq(agent, iq).await;
-                events.extend(new_events);
-            }
-            TokioXmppEvent::Stanza(Stanza::Message(message)) => {
-                let new_events = message::receive::handle_message(agent, message).await;
-                events.extend(new_events);
-            }
-            TokioXmppEvent::Stanza(Stanza::Presence(presence)) => {
-                let new_events = presence::receive::handle_presence(agent, presence).await;
-                events.extend(new_events);
-            }
-        }
+    // Events produced while waiting for the response to a request.
+    if !agent.pending_events.is_empty() {
+        return core::mem::take(&mut agent.pending_events);
+    }
 
-        events
+    if let Some(event) = agent.client.next().await {
+        handle_event(agent, event).await
     } else {
         // Stream was closed and not opening again because TokioXmppClient reconnect is false
         // However we set reconnect true in agent builder so this should never happen and indicates
@@ -65,3 +28,52 @@ pub async fn wait_for_events(agent: &mut Agent) -> Vec<Event> {
         panic!("xmpp::Agent should never receive None event (stream closed, no reconnect)");
     }
 }
+
+pub(crate) async fn handle_event(agent: &mut Agent, event: TokioXmppEvent) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    match event {
+        // A stream resumed from a previous run still needs the session to be
+        // set up on our side.
+        TokioXmppEvent::Online { resumed, .. } if !resumed || !agent.session_started => {
+            agent.session_started = true;
+
+            // Whatever we knew of our contacts is stale now.
+            events.extend(agent.presences.clear());
+
+            let presence = presence::send::make_presence(agent).into();
+            let _ = agent.client.send_stanza(presence).await;
+            events.push(Event::Online);
+            // TODO: only send this when the ContactList feature is enabled.
+            roster::send::request_roster(agent, &mut events).await;
+            message::carbons::enable(agent).await;
+            message::displayed::request_displayed(agent, &mut events).await;
+
+            // Query account disco to know what bookmarks spec is used
+            let iq = Iq::from_get("disco-account", DiscoInfoQuery { node: None }).into();
+            let _ = agent.client.send_stanza(iq).await;
+            agent.awaiting_disco_bookmarks_type = true;
+
+            // Fetch what we missed while disconnected
+            mam::send::catch_up(agent).await;
+        }
+        TokioXmppEvent::Online { .. } => {}
+        TokioXmppEvent::Disconnected(e) => {
+            events.push(Event::Disconnected(e));
+        }
+        TokioXmppEvent::Stanza(Stanza::Iq(iq)) => {
+            let new_events = iq::handle_iq(agent, iq).await;
+            events.extend(new_events);
+        }
+        TokioXmppEvent::Stanza(Stanza::Message(message)) => {
+            let new_events = message::receive::handle_message(agent, message).await;
+            events.extend(new_events);
+        }
+        TokioXmppEvent::Stanza(Stanza::Presence(presence)) => {
+            let new_events = presence::receive::handle_presence(agent, presence).await;
+            events.extend(new_events);
+        }
+    }
+
+    events
+}
@@ -9,5 +9,11 @@ pub enum ClientFeature {
     #[cfg(feature = "avatars")]
     Avatars,
     ContactList,
+    /// Receive the messages sent and received by our other clients, see
+    /// [Event::ChatMessageCarbon][crate::Event::ChatMessageCarbon].
+    Carbons,
+    /// Synchronise up to where each chat was displayed with our other
+    /// clients, see [Event::ChatDisplayed][crate::Event::ChatDisplayed].
+    DisplayedSync,
     JoinRooms,
 }
@@ -0,0 +1,331 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A handle to an [Agent] driven by its own task, which can be cloned and
+//! used from many tasks at once.
+
+use alloc::sync::Arc;
+use futures::{future::BoxFuture, stream::BoxStream, StreamExt};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_xmpp::{
+    jid::{BareJid, Jid},
+    minidom::Element,
+    parsers::{
+        presence::Show,
+        stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    },
+};
+use xso::{AsXml, FromXml};
+
+use crate::{
+    event_loop,
+    iq::{request, IqKind},
+    message::send::MessageSettings,
+    muc::{
+        private_message::RoomPrivateMessageSettings,
+        room::{JoinRoomSettings, LeaveRoomSettings, RoomMessageSettings},
+    },
+    Agent, Error, Event, MessageId, RoomNick,
+};
+
+/// How many events are kept for subscribers which are lagging behind,
+/// before the oldest ones get dropped.
+const EVENT_CAPACITY: usize = 1024;
+
+type Job = Box<dyn for<'a> FnOnce(&'a mut Agent) -> BoxFuture<'a, ()> + Send>;
+
+enum Command {
+    Run(Job),
+    Disconnect(oneshot::Sender<Result<(), Error>>),
+}
+
+// Only lives until it is matched.
+#[allow(clippy::large_enum_variant)]
+enum Next {
+    Command(Option<Command>),
+    Event(Option<tokio_xmpp::Event>),
+}
+
+/// A cheap, cloneable handle to an [Agent] running in a background task,
+/// see [Agent::spawn].
+///
+/// Every method only waits for its own operation, so that many tasks can
+/// send messages, join rooms or make requests at the same time.  Events are
+/// received through [AgentHandle::subscribe].
+///
+/// The agent stops once every handle has been dropped, or on
+/// [AgentHandle::disconnect].  Afterwards, the methods of the remaining
+/// handles return [Error::Disconnected].
+#[derive(Clone)]
+pub struct AgentHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    events: broadcast::Sender<Arc<Event>>,
+    // Subscribed before the agent started, handed to the first subscriber.
+    first_events: Arc<Mutex<Option<broadcast::Receiver<Arc<Event>>>>>,
+    request_timeout: Duration,
+}
+
+impl AgentHandle {
+    /// Run a closure on the agent from its task, and return its result.
+    ///
+    /// This gives access to everything the [Agent] provides, for instance
+    /// `handle.run(|agent| Box::pin(async move { agent.roster().len() }))`.
+    /// The other operations wait for it to complete, so it shouldn't wait
+    /// for anything but sending stanzas.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: for<'a> FnOnce(&'a mut Agent) -> BoxFuture<'a, T> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move |agent| {
+            Box::pin(async move {
+                let _ = tx.send(f(agent).await);
+            })
+        });
+        self.commands
+            .send(Command::Run(job))
+            .map_err(|_| Error::Disconnected)?;
+        rx.await.map_err(|_| Error::Disconnected)
+    }
+
+    /// Receive the events produced from now on.
+    ///
+    /// The first subscriber also gets the events produced since the agent
+    /// was spawned, such as [Event::Online].  Each subscriber gets every
+    /// event; one falling too far behind misses the oldest ones.  The stream
+    /// ends once the agent has stopped.
+    pub fn subscribe(&self) -> BoxStream<'static, Arc<Event>> {
+        let events = self
+            .first_events
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| self.events.subscribe());
+        futures::stream::unfold(events, |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => return Some((event, events)),
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        warn!("Event subscriber lagging behind, {count} events were dropped");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// See [Agent::send_message].
+    pub async fn send_message(
+        &self,
+        recipient: BareJid,
+        message: &str,
+        lang: Option<&str>,
+    ) -> Result<MessageId, Error> {
+        let message = String::from(message);
+        let lang = lang.map(String::from);
+        self.run(move |agent| {
+            Box::pin(async move {
+                let mut settings = MessageSettings::new(recipient, &message);
+                settings.lang = lang.as_deref();
+                agent.send_message(settings).await
+            })
+        })
+        .await
+    }
+
+    /// See [Agent::send_room_message].
+    pub async fn send_room_message(
+        &self,
+        room: BareJid,
+        message: &str,
+        lang: Option<&str>,
+    ) -> Result<(), Error> {
+        let message = String::from(message);
+        let lang = lang.map(String::from);
+        self.run(move |agent| {
+            Box::pin(async move {
+                let mut settings = RoomMessageSettings::new(room, &message);
+                settings.lang = lang.as_deref();
+                agent.send_room_message(settings).await
+            })
+        })
+        .await
+    }
+
+    /// See [Agent::send_room_private_message].
+    pub async fn send_room_private_message(
+        &self,
+        room: BareJid,
+        recipient: RoomNick,
+        message: &str,
+        lang: Option<&str>,
+    ) -> Result<(), Error> {
+        let message = String::from(message);
+        let lang = lang.map(String::from);
+        self.run(move |agent| {
+            Box::pin(async move {
+                let mut settings = RoomPrivateMessageSettings::new(room, recipient, &message);
+                settings.lang = lang.as_deref();
+                agent.send_room_private_message(settings).await
+            })
+        })
+        .await
+    }
+
+    /// See [Agent::join_room].
+    pub async fn join_room(
+        &self,
+        room: BareJid,
+        nick: Option<RoomNick>,
+        password: Option<&str>,
+    ) -> Result<(), Error> {
+        let password = password.map(String::from);
+        self.run(move |agent| {
+            Box::pin(async move {
+                let mut settings = JoinRoomSettings::new(room);
+                settings.nick = nick;
+                settings.password = password;
+                agent.join_room(settings).await
+            })
+        })
+        .await
+    }
+
+    /// See [Agent::leave_room].
+    pub async fn leave_room(&self, room: BareJid) -> Result<(), Error> {
+        self.run(move |agent| Box::pin(agent.leave_room(LeaveRoomSettings::new(room))))
+            .await
+    }
+
+    /// See [Agent::set_presence].
+    pub async fn set_presence(
+        &self,
+        show: Option<Show>,
+        status: Option<&str>,
+        priority: i8,
+    ) -> Result<(), Error> {
+        let status = status.map(String::from);
+        self.run(move |agent| {
+            Box::pin(async move { agent.set_presence(show, status.as_deref(), priority).await })
+        })
+        .await
+    }
+
+    /// See [Agent::request].
+    ///
+    /// Only sending the request goes through the agent task, so any number
+    /// of requests can be waited for at the same time.
+    pub async fn request<Req: AsXml, Resp: FromXml>(
+        &self,
+        to: Jid,
+        kind: IqKind,
+        req: Req,
+    ) -> Result<Resp, StanzaError> {
+        let result = self.send_request(to.clone(), kind, req).await?;
+        request::parse_result(&to, result)
+    }
+
+    /// See [Agent::request_empty].
+    pub async fn request_empty<Req: AsXml>(
+        &self,
+        to: Jid,
+        kind: IqKind,
+        req: Req,
+    ) -> Result<(), StanzaError> {
+        self.send_request(to, kind, req).await.map(|_| ())
+    }
+
+    async fn send_request<Req: AsXml>(
+        &self,
+        to: Jid,
+        kind: IqKind,
+        req: Req,
+    ) -> Result<Option<Element>, StanzaError> {
+        let request = request::make_request(kind, &req)?;
+        let dest = to.clone();
+        let token = self
+            .run(move |agent| Box::pin(agent.client.send_iq(Some(dest), request)))
+            .await
+            .map_err(|err| {
+                StanzaError::new(
+                    ErrorType::Cancel,
+                    DefinedCondition::InternalServerError,
+                    "en",
+                    format!("{}", err),
+                )
+            })?;
+        match tokio::time::timeout(self.request_timeout, token).await {
+            Ok(response) => request::handle_response(&to, response),
+            Err(_) => Err(request::timeout_error(&to)),
+        }
+    }
+
+    /// Stop the agent, closing its stream cleanly.
+    pub async fn disconnect(&self) -> Result<(), Error> {
+        let (tx, rx) = oneshot::channel();
+        self.commands
+            .send(Command::Disconnect(tx))
+            .map_err(|_| Error::Disconnected)?;
+        rx.await.map_err(|_| Error::Disconnected)?
+    }
+}
+
+/// Move the agent into its own task, and return a handle to it.
+pub(crate) fn spawn(agent: Agent) -> AgentHandle {
+    let (commands, commands_rx) = mpsc::unbounded_channel();
+    let (events, first_events) = broadcast::channel(EVENT_CAPACITY);
+    let handle = AgentHandle {
+        commands,
+        events: events.clone(),
+        first_events: Arc::new(Mutex::new(Some(first_events))),
+        request_timeout: agent.request_timeout,
+    };
+    tokio::spawn(drive(agent, commands_rx, events));
+    handle
+}
+
+async fn drive(
+    mut agent: Agent,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    events: broadcast::Sender<Arc<Event>>,
+) {
+    loop {
+        // Events produced by a request made from a job.
+        for event in core::mem::take(&mut agent.pending_events) {
+            let _ = events.send(Arc::new(event));
+        }
+
+        // Both are cancel-safe, the chosen one is then handled to completion.
+        let next = tokio::select! {
+            command = commands.recv() => Next::Command(command),
+            event = agent.client.next() => Next::Event(event),
+        };
+        match next {
+            Next::Command(Some(Command::Run(job))) => job(&mut agent).await,
+            Next::Command(Some(Command::Disconnect(done))) => {
+                let _ = done.send(agent.disconnect().await);
+                return;
+            }
+            Next::Command(None) => {
+                // Every handle is gone, nobody can use this agent anymore.
+                let _ = agent.disconnect().await;
+                return;
+            }
+            Next::Event(Some(event)) => {
+                for event in event_loop::handle_event(&mut agent, event).await {
+                    let _ = events.send(Arc::new(event));
+                }
+            }
+            Next::Event(None) => {
+                panic!("xmpp::Agent should never receive None event (stream closed, no reconnect)")
+            }
+        }
+    }
+}
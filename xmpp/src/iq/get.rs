@@ -8,14 +8,15 @@ use tokio_xmpp::{
     jid::Jid,
     minidom::Element,
     parsers::{
-        disco::DiscoInfoQuery,
+        disco::{DiscoInfoQuery, DiscoItemsQuery},
         iq::Iq,
         ns,
         stanza_error::{DefinedCondition, ErrorType, StanzaError},
     },
 };
 
-use crate::{Agent, Event};
+use super::handlers::{self, IqKind};
+use crate::{commands, Agent, Event};
 
 pub async fn handle_iq_get(
     agent: &mut Agent,
@@ -45,7 +46,25 @@ pub async fn handle_iq_get(
                 let _ = agent.client.send_stanza(iq).await;
             }
         }
+    } else if payload.is("query", ns::DISCO_ITEMS) && payload.attr("node") == Some(ns::COMMANDS) {
+        match DiscoItemsQuery::try_from(payload) {
+            Ok(query) => commands::receive::handle_disco_items(agent, from, id, query).await,
+            Err(err) => {
+                let error = StanzaError::new(
+                    ErrorType::Modify,
+                    DefinedCondition::BadRequest,
+                    "en",
+                    &format!("{}", err),
+                );
+                let iq = Iq::from_error(id, error).with_to(from).into();
+                let _ = agent.client.send_stanza(iq).await;
+            }
+        }
     } else {
+        if handlers::handle_iq(agent, IqKind::Get, from.clone(), id.clone(), payload).await {
+            return;
+        }
+
         // We MUST answer unhandled get iqs with a service-unavailable error.
         let error = StanzaError::new(
             ErrorType::Cancel,
@@ -0,0 +1,174 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use alloc::collections::BTreeSet;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use alloc::sync::Arc;
+use tokio_xmpp::{
+    jid::Jid,
+    minidom::Element,
+    parsers::{
+        date::DateTime,
+        iq::{Iq, IqType},
+        last_activity::{LastActivityQuery, LastActivityResult},
+        ns,
+        ping::Ping,
+        stanza_error::{DefinedCondition, ErrorType, StanzaError},
+        time::{TimeQuery, TimeResult},
+        version::{VersionQuery, VersionResult},
+    },
+};
+use xso::{AsXml, FromXml};
+
+use crate::Agent;
+
+/// The type of an iq request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IqKind {
+    /// Request for some data.
+    Get,
+    /// Request to modify some data.
+    Set,
+}
+
+type Handler = Box<dyn Fn(&Jid, Element) -> Result<Option<Element>, StanzaError> + Send + Sync>;
+
+/// The handlers for the iqs other entities send us, keyed on the type of the
+/// iq and the name and namespace of its payload.
+#[derive(Default)]
+pub(crate) struct IqHandlers {
+    handlers: HashMap<(IqKind, String, String), Handler>,
+}
+
+impl IqHandlers {
+    // StanzaError is the payload we send back, boxing it would only make
+    // handlers more verbose.
+    #[allow(clippy::result_large_err)]
+    pub fn register<Req, Resp, F>(&mut self, kind: IqKind, name: &str, ns: &str, handler: F)
+    where
+        Req: FromXml + 'static,
+        Resp: AsXml + 'static,
+        F: Fn(&Jid, Req) -> Result<Resp, StanzaError> + Send + Sync + 'static,
+    {
+        let handler = move |from: &Jid, payload: Element| {
+            let request = xso::try_from_element::<Req>(payload).map_err(|err| {
+                StanzaError::new(
+                    ErrorType::Modify,
+                    DefinedCondition::BadRequest,
+                    "en",
+                    format!("{}", err),
+                )
+            })?;
+            let response = handler(from, request)?;
+            to_element(&response).map_err(|err| {
+                error!("Failed to serialise the response to an iq: {}", err);
+                StanzaError::new(
+                    ErrorType::Wait,
+                    DefinedCondition::InternalServerError,
+                    "en",
+                    "Failed to serialise the response.",
+                )
+            })
+        };
+        self.handlers.insert(
+            (kind, String::from(name), String::from(ns)),
+            Box::new(handler),
+        );
+    }
+
+    pub fn contains(&self, kind: IqKind, name: &str, ns: &str) -> bool {
+        self.handlers
+            .contains_key(&(kind, String::from(name), String::from(ns)))
+    }
+
+    /// The namespaces of every handler, to be advertised as disco features.
+    pub fn features(&self) -> BTreeSet<&str> {
+        self.handlers.keys().map(|(_, _, ns)| ns.as_str()).collect()
+    }
+
+    /// Register the handlers of XEP-0199, XEP-0092, XEP-0202 and XEP-0012,
+    /// unless the application provided its own.
+    #[allow(clippy::result_large_err)]
+    pub fn register_builtins(
+        &mut self,
+        software: (String, String),
+        last_activity: Arc<Mutex<Instant>>,
+    ) {
+        if !self.contains(IqKind::Get, "ping", ns::PING) {
+            self.register(IqKind::Get, "ping", ns::PING, |_, _: Ping| Ok(None::<Ping>));
+        }
+        if !self.contains(IqKind::Get, "query", ns::VERSION) {
+            let (name, version) = software;
+            self.register(
+                IqKind::Get,
+                "query",
+                ns::VERSION,
+                move |_, _: VersionQuery| {
+                    Ok(VersionResult {
+                        name: name.clone(),
+                        version: version.clone(),
+                        os: None,
+                    })
+                },
+            );
+        }
+        if !self.contains(IqKind::Get, "time", ns::TIME) {
+            self.register(IqKind::Get, "time", ns::TIME, |_, _: TimeQuery| {
+                let now = DateTime(chrono::Local::now().fixed_offset());
+                Ok(Element::from(TimeResult(now)))
+            });
+        }
+        if !self.contains(IqKind::Get, "query", ns::LAST_ACTIVITY) {
+            self.register(
+                IqKind::Get,
+                "query",
+                ns::LAST_ACTIVITY,
+                move |_, _: LastActivityQuery| {
+                    let idle = last_activity.lock().unwrap().elapsed();
+                    Ok(LastActivityResult::new(idle.as_secs()))
+                },
+            );
+        }
+    }
+}
+
+fn to_element<T: AsXml>(value: &T) -> Result<Option<Element>, xso::error::Error> {
+    // An absent payload, such as None, produces an empty result.
+    if value.as_xml_iter()?.next().is_none() {
+        return Ok(None);
+    }
+    xso::transform(value).map(Some)
+}
+
+/// Answer an iq using the registered handlers, returning false if none of
+/// them handles it.
+pub(crate) async fn handle_iq(
+    agent: &mut Agent,
+    kind: IqKind,
+    from: Jid,
+    id: String,
+    payload: Element,
+) -> bool {
+    let key = (kind, String::from(payload.name()), payload.ns());
+    let Some(handler) = agent.iq_handlers.handlers.get(&key) else {
+        return false;
+    };
+    let payload = match handler(&from, payload) {
+        Ok(response) => IqType::Result(response),
+        Err(error) => IqType::Error(error),
+    };
+    let iq = Iq {
+        from: None,
+        to: Some(from),
+        id,
+        payload,
+    };
+    let _ = agent.client.send_stanza(iq.into()).await;
+    true
+}
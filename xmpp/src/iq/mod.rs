@@ -6,12 +6,16 @@
 
 use tokio_xmpp::parsers::iq::{Iq, IqType};
 
-use crate::{Agent, Event};
+use crate::{commands, mam, muc, Agent, Event};
 
 pub mod get;
+pub mod handlers;
+pub mod request;
 pub mod result;
 pub mod set;
 
+pub use handlers::IqKind;
+
 pub async fn handle_iq(agent: &mut Agent, iq: Iq) -> Vec<Event> {
     let mut events = vec![];
     let from = iq
@@ -22,8 +26,14 @@ pub async fn handle_iq(agent: &mut Agent, iq: Iq) -> Vec<Event> {
         get::handle_iq_get(agent, &mut events, from, iq.to, iq.id, payload).await;
     } else if let IqType::Result(Some(payload)) = iq.payload {
         result::handle_iq_result(agent, &mut events, from, iq.to, iq.id, payload).await;
+    } else if let IqType::Result(None) = iq.payload {
+        muc::config::handle_empty_result(agent, &mut events, &iq.id);
     } else if let IqType::Set(payload) = iq.payload {
         set::handle_iq_set(agent, &mut events, from, iq.to, iq.id, payload).await;
+    } else if let IqType::Error(error) = iq.payload {
+        mam::receive::handle_error(agent, &from, &iq.id);
+        muc::admin::handle_error(agent, &from, &iq.id);
+        commands::receive::handle_error(agent, &mut events, &iq.id, &error);
     }
     events
 }
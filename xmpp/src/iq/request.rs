@@ -0,0 +1,153 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use futures::StreamExt;
+use tokio_xmpp::{
+    jid::Jid,
+    minidom::Element,
+    parsers::stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    IqFailure, IqRequest, IqResponse,
+};
+use xso::{AsXml, FromXml};
+
+use super::handlers::IqKind;
+use crate::{event_loop, Agent};
+
+enum Outcome {
+    Response(Result<IqResponse, IqFailure>),
+    Event(Option<tokio_xmpp::Event>),
+    Timeout,
+}
+
+fn local_error(type_: ErrorType, condition: DefinedCondition, text: &str) -> StanzaError {
+    StanzaError::new(type_, condition, "en", text)
+}
+
+// Local failures are reported the same way as the errors of the recipient.
+#[allow(clippy::result_large_err)]
+pub(crate) fn make_request<Req: AsXml>(kind: IqKind, req: &Req) -> Result<IqRequest, StanzaError> {
+    let payload = xso::transform::<Element, _>(req).map_err(|err| {
+        local_error(
+            ErrorType::Modify,
+            DefinedCondition::BadRequest,
+            &format!("{}", err),
+        )
+    })?;
+    Ok(match kind {
+        IqKind::Get => IqRequest::Get(payload),
+        IqKind::Set => IqRequest::Set(payload),
+    })
+}
+
+pub(crate) fn timeout_error(to: &Jid) -> StanzaError {
+    warn!("Request to {to} timed out");
+    local_error(
+        ErrorType::Wait,
+        DefinedCondition::RemoteServerTimeout,
+        "No response received in time.",
+    )
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn handle_response(
+    to: &Jid,
+    response: Result<IqResponse, IqFailure>,
+) -> Result<Option<Element>, StanzaError> {
+    match response {
+        Ok(IqResponse::Result(payload)) => Ok(payload),
+        Ok(IqResponse::Error(error)) => Err(error),
+        Err(failure) => {
+            warn!("Failed to send iq to {to}: {failure}");
+            Err(local_error(
+                ErrorType::Cancel,
+                DefinedCondition::InternalServerError,
+                &format!("{}", failure),
+            ))
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn parse_result<Resp: FromXml>(
+    to: &Jid,
+    result: Option<Element>,
+) -> Result<Resp, StanzaError> {
+    let Some(result) = result else {
+        warn!("Empty result from {to}, while a payload was expected");
+        return Err(local_error(
+            ErrorType::Cancel,
+            DefinedCondition::UndefinedCondition,
+            "Empty result.",
+        ));
+    };
+    xso::try_from_element::<Resp>(result).map_err(|err| {
+        warn!("Wrong result format from {to}: {err}");
+        local_error(
+            ErrorType::Cancel,
+            DefinedCondition::UndefinedCondition,
+            &format!("{}", err),
+        )
+    })
+}
+
+/// Send an iq to another entity and wait for its response, while still
+/// handling the other incoming stanzas.
+///
+/// The events these produce are kept for the next call to
+/// [Agent::wait_for_events].
+pub(crate) async fn send_request(
+    agent: &mut Agent,
+    to: Jid,
+    request: IqRequest,
+) -> Result<Option<Element>, StanzaError> {
+    let token = agent.client.send_iq(Some(to.clone()), request).await;
+    let timeout = tokio::time::sleep(agent.request_timeout);
+    tokio::pin!(token);
+    tokio::pin!(timeout);
+
+    loop {
+        let outcome = tokio::select! {
+            response = &mut token => Outcome::Response(response),
+            _ = &mut timeout => Outcome::Timeout,
+            event = agent.client.next() => Outcome::Event(event),
+        };
+        match outcome {
+            Outcome::Response(response) => return handle_response(&to, response),
+            Outcome::Timeout => return Err(timeout_error(&to)),
+            Outcome::Event(Some(event)) => {
+                // Boxed, as handling the event may make requests itself.
+                let events = Box::pin(event_loop::handle_event(agent, event)).await;
+                agent.pending_events.extend(events);
+            }
+            Outcome::Event(None) => {
+                panic!("xmpp::Agent should never receive None event (stream closed, no reconnect)")
+            }
+        }
+    }
+}
+
+/// Send a typed request, and parse its result.
+pub async fn request<Req: AsXml, Resp: FromXml>(
+    agent: &mut Agent,
+    to: Jid,
+    kind: IqKind,
+    req: Req,
+) -> Result<Resp, StanzaError> {
+    let request = make_request(kind, &req)?;
+    let result = send_request(agent, to.clone(), request).await?;
+    parse_result(&to, result)
+}
+
+/// Send a typed request, ignoring the payload of its result.
+pub async fn request_empty<Req: AsXml>(
+    agent: &mut Agent,
+    to: Jid,
+    kind: IqKind,
+    req: Req,
+) -> Result<(), StanzaError> {
+    let request = make_request(kind, &req)?;
+    send_request(agent, to, request).await.map(|_| ())
+}
@@ -5,12 +5,20 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::{
-    disco,
+    bookmarks, commands, disco,
     jid::Jid,
+    mam,
     minidom::Element,
-    muc::room::JoinRoomSettings,
-    parsers::{disco::DiscoInfoResult, ns, private::Query as PrivateXMLQuery, roster::Roster},
-    pubsub, upload, Agent, Event, RoomNick,
+    muc,
+    parsers::{
+        commands::Command,
+        disco::{DiscoInfoResult, DiscoItemsResult},
+        muc::{AdminQuery, OwnerQuery},
+        ns,
+        private::Query as PrivateXMLQuery,
+        roster::Roster,
+    },
+    pubsub, roster, upload, Agent, Event,
 };
 
 pub async fn handle_iq_result(
@@ -23,10 +31,11 @@ pub async fn handle_iq_result(
 ) {
     // TODO: move private iqs like this one somewhere else, for
     // security reasons.
-    if payload.is("query", ns::ROSTER) && from == agent.client.bound_jid().unwrap().to_bare() {
-        let roster = Roster::try_from(payload).unwrap();
-        for item in roster.items.into_iter() {
-            events.push(Event::ContactAdded(item));
+    let own = agent.client.bound_jid().map(|jid| Jid::from(jid.to_bare()));
+    if payload.is("query", ns::ROSTER) && Some(&from) == own.as_ref() {
+        match Roster::try_from(payload) {
+            Ok(roster) => roster::receive::handle_roster_result(agent, events, roster).await,
+            Err(e) => warn!("Wrong roster format from {from}: {e}"),
         }
     } else if payload.is("pubsub", ns::PUBSUB) {
         let new_events = pubsub::handle_iq_result(&from, payload, agent).await;
@@ -37,30 +46,41 @@ pub async fn handle_iq_result(
     } else if payload.is("query", ns::PRIVATE) {
         match PrivateXMLQuery::try_from(payload) {
             Ok(query) => {
-                for conf in query.storage.conferences {
-                    let (jid, room) = conf.into_bookmarks2();
-                    agent
-                        .join_room(JoinRoomSettings {
-                            room: jid,
-                            nick: room.nick.map(RoomNick::new),
-                            password: room.password,
-                            status: None,
-                        })
-                        .await;
-                }
+                bookmarks::receive::handle_private_result(agent, events, query.storage).await
             }
-            Err(e) => {
-                panic!("Wrong XEP-0048 v1.0 Bookmark format: {}", e);
+            Err(e) => warn!("Wrong XEP-0048 v1.0 Bookmark format from {from}: {e}"),
+        }
+    } else if payload.is("query", ns::MUC_ADMIN) {
+        match AdminQuery::try_from(payload) {
+            Ok(query) => muc::admin::handle_admin_result(agent, events, &id, query),
+            Err(e) => warn!("Wrong muc#admin format from {from}: {e}"),
+        }
+    } else if payload.is("query", ns::MUC_OWNER) {
+        match OwnerQuery::try_from(payload) {
+            Ok(query) => muc::config::handle_owner_result(agent, events, &id, query),
+            Err(e) => warn!("Wrong muc#owner format from {from}: {e}"),
+        }
+    } else if payload.is("command", ns::COMMANDS) {
+        match Command::try_from(payload) {
+            Ok(command) => {
+                commands::receive::handle_command_response(agent, events, from, &id, command)
             }
+            Err(e) => warn!("Wrong command format from {from}: {e}"),
         }
-    } else if payload.is("query", ns::DISCO_INFO) {
-        match DiscoInfoResult::try_from(payload.clone()) {
-            Ok(disco) => {
-                disco::handle_disco_info_result(agent, disco, from).await;
+    } else if payload.is("query", ns::DISCO_ITEMS) {
+        match DiscoItemsResult::try_from(payload) {
+            Ok(result) if commands::receive::is_commands_list(&result) => {
+                commands::receive::handle_commands_list(events, from, result)
             }
-            Err(e) => match e {
-                _ => panic!("Wrong disco#info format: {}", e),
-            },
+            Ok(_) => (),
+            Err(e) => warn!("Wrong disco#items format from {from}: {e}"),
+        }
+    } else if payload.is("fin", ns::MAM) {
+        mam::receive::handle_fin(agent, events, &from, id, payload).await;
+    } else if payload.is("query", ns::DISCO_INFO) {
+        match DiscoInfoResult::try_from(payload) {
+            Ok(disco) => disco::handle_disco_info_result(agent, disco, from).await,
+            Err(e) => warn!("Wrong disco#info format from {from}: {e}"),
         }
     }
 }
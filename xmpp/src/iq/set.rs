@@ -9,20 +9,35 @@ use tokio_xmpp::{
     minidom::Element,
     parsers::{
         iq::Iq,
+        ns,
         stanza_error::{DefinedCondition, ErrorType, StanzaError},
     },
 };
 
-use crate::{Agent, Event};
+use super::handlers::{self, IqKind};
+use crate::{commands, roster, Agent, Event};
 
 pub async fn handle_iq_set(
     agent: &mut Agent,
-    _events: &mut Vec<Event>,
+    events: &mut Vec<Event>,
     from: Jid,
     _to: Option<Jid>,
     id: String,
-    _payload: Element,
+    payload: Element,
 ) {
+    if payload.is("query", ns::ROSTER) {
+        roster::receive::handle_roster_push(agent, events, from, id, payload).await;
+        return;
+    }
+    if payload.is("command", ns::COMMANDS) {
+        commands::receive::handle_command_request(agent, from, id, payload).await;
+        return;
+    }
+
+    if handlers::handle_iq(agent, IqKind::Set, from.clone(), id.clone(), payload).await {
+        return;
+    }
+
     // We MUST answer unhandled set iqs with a service-unavailable error.
     let error = StanzaError::new(
         ErrorType::Cancel,
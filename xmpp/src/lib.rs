@@ -22,23 +22,31 @@ use jid::{ResourcePart, ResourceRef};
 use parsers::message::Id as MessageId;
 
 pub mod agent;
+pub mod bookmarks;
 pub mod builder;
+pub mod commands;
 pub mod delay;
 pub mod disco;
 pub mod event;
 pub mod event_loop;
 pub mod feature;
+pub mod handle;
 pub mod iq;
+pub mod mam;
 pub mod message;
 pub mod muc;
 pub mod presence;
 pub mod pubsub;
+pub mod roster;
+pub mod store;
 pub mod upload;
 
 pub use agent::Agent;
 pub use builder::{ClientBuilder, ClientType};
 pub use event::Event;
 pub use feature::ClientFeature;
+pub use handle::AgentHandle;
+pub use store::{JsonDirStateStore, MemoryStateStore, StateStore};
 
 pub type Error = tokio_xmpp::Error;
 
@@ -48,7 +56,7 @@ pub type Error = tokio_xmpp::Error;
 /// user account.
 ///
 // TODO: Introduce RoomMember and track by occupant-id
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct RoomNick(ResourcePart);
 
 impl RoomNick {
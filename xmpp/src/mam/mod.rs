@@ -0,0 +1,120 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Fetching history from XEP-0313 (Message Archive Management) archives.
+//!
+//! Queries are paged using XEP-0059 (Result Set Management).  The last
+//! stanza-id seen from each archive is remembered in the state store, so
+//! that the history missed while disconnected is fetched again once
+//! reconnected.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use core::str::FromStr;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{
+    jid::BareJid,
+    parsers::{
+        data_forms::{DataForm, DataFormType, Field},
+        mam::{Query, QueryId},
+        ns,
+        rsm::SetQuery,
+    },
+    store::{self, StateStore},
+    Agent,
+};
+
+pub mod receive;
+pub mod send;
+
+pub use send::FetchHistorySettings;
+
+/// The history to fetch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum History {
+    /// Every one-to-one conversation of the account.
+    Account,
+    /// The one-to-one conversation with a contact, from the account's
+    /// archive.
+    Contact(BareJid),
+    /// The archive of a room.
+    Room(BareJid),
+}
+
+/// A query whose pages are still being fetched.
+#[derive(Debug)]
+pub(crate) struct PendingQuery {
+    pub(crate) history: History,
+    /// The archive answering, either our account or a room.
+    pub(crate) archive: BareJid,
+    pub(crate) start: Option<DateTime<Utc>>,
+    pub(crate) page_size: usize,
+    /// Whether this query catches up with the messages missed while
+    /// disconnected, in which case it moves the archive position.
+    pub(crate) catch_up: bool,
+}
+
+impl PendingQuery {
+    /// Build the query for the page following `after`.
+    pub(crate) fn make_query(&self, id: &str, after: Option<String>) -> Query {
+        let mut fields = Vec::new();
+        if let History::Contact(ref with) = self.history {
+            fields.push(Field::text_single("with", &with.to_string()));
+        }
+        if let Some(start) = self.start {
+            fields.push(Field::text_single(
+                "start",
+                &start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+        }
+        let form = if fields.is_empty() {
+            None
+        } else {
+            Some(DataForm::new(DataFormType::Submit, ns::MAM, fields))
+        };
+
+        Query {
+            queryid: Some(QueryId(String::from(id))),
+            node: None,
+            form,
+            set: Some(SetQuery {
+                max: Some(self.page_size),
+                after,
+                before: None,
+                index: None,
+            }),
+            flip_page: false,
+        }
+    }
+}
+
+/// Load the position reached in each archive, saved by [`save_positions`].
+pub(crate) fn load_positions(state: &dyn StateStore) -> HashMap<BareJid, String> {
+    let Some(value) = store::load(state, store::ARCHIVES) else {
+        return HashMap::new();
+    };
+    let Some(positions) = value.as_object() else {
+        warn!("Wrong archive positions format in the state store");
+        return HashMap::new();
+    };
+    positions
+        .iter()
+        .filter_map(|(archive, id)| {
+            Some((BareJid::from_str(archive).ok()?, String::from(id.as_str()?)))
+        })
+        .collect()
+}
+
+/// Save the position reached in each archive.
+pub(crate) fn save_positions(agent: &Agent) {
+    let positions = agent
+        .archive_positions
+        .iter()
+        .map(|(archive, id)| (archive.to_string(), Value::from(id.as_str())))
+        .collect();
+    store::store(&*agent.state, store::ARCHIVES, &Value::Object(positions));
+}
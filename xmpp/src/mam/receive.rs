@@ -0,0 +1,185 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    delay::message_time_info,
+    jid::Jid,
+    mam::{save_positions, send::send_query},
+    minidom::Element,
+    parsers::{
+        mam::{Fin, Result_},
+        message::{Message, MessageType},
+        ns,
+        stanza_id::StanzaId,
+    },
+    Agent, Event, RoomNick,
+};
+
+/// Handle a message forwarded by an archive in answer to one of our
+/// queries.
+///
+/// Returns false if the message doesn't come from an archive, in which
+/// case it must be handled as a live message.
+pub async fn handle_archived_message(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    message: &mut Message,
+) -> bool {
+    let result = match message.extract_payload::<Result_>() {
+        Ok(Some(result)) => result,
+        Ok(None) => return false,
+        Err(e) => {
+            warn!("Failed to parse MAM <result/> payload: {e}");
+            return true;
+        }
+    };
+
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return true;
+    };
+    let Some(query) = result
+        .queryid
+        .as_ref()
+        .and_then(|queryid| agent.mam_queries.get(&queryid.0))
+    else {
+        warn!(
+            "Received a MAM result for an unknown query:\n{:#?}",
+            message
+        );
+        return true;
+    };
+    // Only the archive we queried may send us results for this query.
+    let sender = message.from.as_ref().map_or(own.clone(), Jid::to_bare);
+    if sender != query.archive {
+        warn!(
+            "Received a MAM result for {} from {sender}, ignoring",
+            query.archive
+        );
+        return true;
+    }
+    let archive = query.archive.clone();
+    if query.catch_up {
+        agent
+            .archive_positions
+            .insert(archive.clone(), result.id.clone());
+    }
+
+    let forwarded = result.forwarded;
+    let archived = forwarded.message;
+    let mut time_info = message_time_info(&archived);
+    // The delay added by the archive comes first, it is when the message
+    // got archived.
+    if let Some(delay) = forwarded.delay {
+        time_info.delays.insert(0, delay);
+    }
+
+    let langs: Vec<&str> = agent.lang.iter().map(String::as_str).collect();
+    let Some((_lang, body)) = archived.get_best_body_cloned(langs) else {
+        debug!("Archived message without body:\n{:#?}", archived);
+        return true;
+    };
+
+    if archived.type_ == MessageType::Groupchat {
+        let Some(nick) = archived.from.as_ref().and_then(Jid::resource) else {
+            debug!("Archived room message without sender:\n{:#?}", archived);
+            return true;
+        };
+        events.push(Event::ArchivedRoomMessage(
+            result.id,
+            archived.id.clone(),
+            archive,
+            RoomNick::from_resource_ref(nick),
+            body,
+            time_info,
+        ));
+    } else {
+        let from = archived.from.as_ref().map_or(own.clone(), Jid::to_bare);
+        let to = archived.to.as_ref().map_or(own, Jid::to_bare);
+        events.push(Event::ArchivedChatMessage(
+            result.id,
+            archived.id.clone(),
+            from,
+            to,
+            body,
+            time_info,
+        ));
+    }
+    true
+}
+
+/// Remember the stanza-id of a live message, to catch up from it after a
+/// reconnection.
+pub fn track_stanza_id(agent: &mut Agent, from: &Jid, message: &Message) {
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return;
+    };
+    // Only the archive storing the message may assign its stanza-id.
+    let archive = match message.type_ {
+        MessageType::Groupchat => from.to_bare(),
+        _ => own,
+    };
+    for payload in &message.payloads {
+        if !payload.is("stanza-id", ns::SID) {
+            continue;
+        }
+        match StanzaId::try_from(payload.clone()) {
+            Ok(stanza_id) if stanza_id.by == archive => {
+                agent
+                    .archive_positions
+                    .insert(archive.clone(), stanza_id.id);
+                save_positions(agent);
+            }
+            Ok(_) => (),
+            Err(e) => warn!("Wrong <stanza-id/> format from {from}: {e}"),
+        }
+    }
+}
+
+/// Handle the end of a page, requesting the next one until the archive
+/// has sent every message.
+pub async fn handle_fin(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &Jid,
+    id: String,
+    payload: Element,
+) {
+    let Some(query) = agent.mam_queries.remove(&id) else {
+        warn!("Received a MAM <fin/> for an unknown query from {from}");
+        return;
+    };
+    if from.to_bare() != query.archive {
+        warn!(
+            "Received a MAM <fin/> for {} from {from}, ignoring",
+            query.archive
+        );
+        agent.mam_queries.insert(id, query);
+        return;
+    }
+
+    let fin = match Fin::try_from(payload) {
+        Ok(fin) => fin,
+        Err(e) => {
+            warn!("Wrong MAM <fin/> format from {from}: {e}");
+            return;
+        }
+    };
+    // Positions only move during a catch-up, save them once per page.
+    if query.catch_up {
+        save_positions(agent);
+    }
+    match fin.set.last {
+        Some(last) if !fin.complete => send_query(agent, id, query, Some(last)).await,
+        _ => events.push(Event::HistoryFetched(query.history)),
+    }
+}
+
+/// Forget a query which the archive refused.
+pub fn handle_error(agent: &mut Agent, from: &Jid, id: &str) {
+    if let Some(query) = agent.mam_queries.remove(id) {
+        warn!("Fetching {:?} from {from} failed", query.history);
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    jid::{BareJid, Jid},
+    mam::{History, PendingQuery},
+    parsers::iq::Iq,
+    Agent,
+};
+
+#[derive(Clone, Debug)]
+pub struct FetchHistorySettings {
+    pub history: History,
+    /// Only fetch the messages archived after the one with this stanza-id.
+    pub after: Option<String>,
+    /// Only fetch the messages archived since this time.
+    pub start: Option<DateTime<Utc>>,
+    /// How many messages to request at once.
+    pub page_size: usize,
+}
+
+impl FetchHistorySettings {
+    pub fn new(history: History) -> Self {
+        Self {
+            history,
+            after: None,
+            start: None,
+            page_size: 50,
+        }
+    }
+
+    pub fn with_after(mut self, stanza_id: impl Into<String>) -> Self {
+        self.after = Some(stanza_id.into());
+        self
+    }
+
+    pub fn with_start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+}
+
+pub async fn fetch_history(agent: &mut Agent, settings: FetchHistorySettings) {
+    request_history(agent, settings, false).await
+}
+
+/// Fetch every message archived since the last one we have seen from each
+/// archive, after a reconnection.
+pub(crate) async fn catch_up(agent: &mut Agent) {
+    // The queries from the previous connection will never be answered.
+    agent.mam_queries.clear();
+
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return;
+    };
+    let positions: Vec<(BareJid, String)> = agent
+        .archive_positions
+        .iter()
+        .map(|(archive, id)| (archive.clone(), id.clone()))
+        .collect();
+    for (archive, id) in positions {
+        let history = if archive == own {
+            History::Account
+        } else {
+            History::Room(archive)
+        };
+        let settings = FetchHistorySettings::new(history).with_after(id);
+        request_history(agent, settings, true).await;
+    }
+}
+
+async fn request_history(agent: &mut Agent, settings: FetchHistorySettings, catch_up: bool) {
+    let FetchHistorySettings {
+        history,
+        after,
+        start,
+        page_size,
+    } = settings;
+
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        warn!("Cannot fetch history for {history:?} while offline");
+        return;
+    };
+    let archive = match history {
+        History::Account | History::Contact(_) => own,
+        History::Room(ref room) => room.clone(),
+    };
+
+    agent.mam_query_counter += 1;
+    let id = format!("mam-{}", agent.mam_query_counter);
+    let query = PendingQuery {
+        history,
+        archive,
+        start,
+        page_size,
+        catch_up,
+    };
+    send_query(agent, id, query, after).await;
+}
+
+/// Request the page following `after`, using `id` both as the iq id and
+/// as the queryid.
+pub(crate) async fn send_query(
+    agent: &mut Agent,
+    id: String,
+    query: PendingQuery,
+    after: Option<String>,
+) {
+    let mut iq = Iq::from_set(id.clone(), query.make_query(&id, after));
+    if let History::Room(ref room) = query.history {
+        iq = iq.with_to(room.clone().into());
+    }
+    agent.mam_queries.insert(id, query);
+    let _ = agent.client.send_stanza(iq.into()).await;
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! XEP-0280 (Message Carbons): the copies of the messages sent and received
+//! by our other clients.
+
+use crate::{
+    delay::message_time_info,
+    iq::{request, IqKind},
+    jid::Jid,
+    mam,
+    message::{chat_states, displayed, receipts},
+    parsers::{
+        carbons::{Enable, Received, Sent},
+        forwarding::Forwarded,
+        message::{Message, MessageType},
+        message_correct::Replace,
+        muc::user::MucUser,
+        ns,
+    },
+    Agent, Event,
+};
+
+/// Which client of ours a copied message went through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Carbon {
+    /// The message was sent by another of our clients.
+    Sent,
+    /// The message was received by another of our clients.
+    Received,
+}
+
+/// Whether carbons were enabled while binding our resource, using XEP-0386
+/// (Bind 2).  Only then are the features of the stream the ones offered
+/// before authentication.
+fn enabled_inline(agent: &Agent) -> bool {
+    agent
+        .client
+        .get_stream_features()
+        .and_then(|features| features.sasl2.as_ref())
+        .and_then(|authentication| authentication.inline.as_ref())
+        .and_then(|inline| inline.bind2.as_ref())
+        .is_some_and(|bind| bind.inline_features.iter().any(|f| f == ns::CARBONS))
+}
+
+/// Ask our server for the copies of the messages of our other clients,
+/// unless it was already done while binding.
+pub(crate) async fn enable(agent: &mut Agent) {
+    if !agent.carbons || enabled_inline(agent) {
+        return;
+    }
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return;
+    };
+    if let Err(error) = request::request_empty(agent, own.into(), IqKind::Set, Enable).await {
+        warn!(
+            "Failed to enable message carbons: {:?}",
+            error.defined_condition
+        );
+    }
+}
+
+fn forwarded(message: &Message) -> Option<Result<(Carbon, Forwarded), String>> {
+    message.payloads.iter().find_map(|payload| {
+        if payload.is("sent", ns::CARBONS) {
+            Some(
+                Sent::try_from(payload.clone())
+                    .map(|sent| (Carbon::Sent, sent.forwarded))
+                    .map_err(|e| e.to_string()),
+            )
+        } else if payload.is("received", ns::CARBONS) {
+            Some(
+                Received::try_from(payload.clone())
+                    .map(|received| (Carbon::Received, received.forwarded))
+                    .map_err(|e| e.to_string()),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Handle the copy of a message carried by `message`, returning false if it
+/// isn't one.
+pub(crate) fn handle_carbon(agent: &mut Agent, events: &mut Vec<Event>, message: &Message) -> bool {
+    let (carbon, forwarded) = match forwarded(message) {
+        None => return false,
+        Some(Ok(forwarded)) => forwarded,
+        Some(Err(e)) => {
+            warn!("Wrong carbon format: {e}");
+            return true;
+        }
+    };
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return true;
+    };
+    // Anyone could send us a forged copy of a message, only our own account
+    // may.
+    if message.from != Some(Jid::from(own)) {
+        warn!(
+            "Ignoring a carbon from {:?}, which isn't our own account",
+            message.from
+        );
+        return true;
+    }
+
+    let mut inner = forwarded.message;
+    let contact = match carbon {
+        Carbon::Sent => inner.to.as_ref(),
+        Carbon::Received => inner.from.as_ref(),
+    };
+    let Some(contact) = contact.map(Jid::to_bare) else {
+        debug!("Ignoring a carbon without a contact:\n{:#?}", inner);
+        return true;
+    };
+    if carbon == Carbon::Received {
+        let from = inner.from.clone().unwrap();
+        mam::receive::track_stanza_id(agent, &from, &inner);
+    }
+    if !matches!(inner.type_, MessageType::Chat | MessageType::Normal)
+        || inner.extract_valid_payload::<MucUser>().is_some()
+    {
+        debug!(
+            "Ignoring a carbon outside of a one-to-one chat:\n{:#?}",
+            inner
+        );
+        return true;
+    }
+
+    // What the contact told another client of ours concerns this one too.
+    if carbon == Carbon::Received {
+        receipts::handle_receipt(events, &contact, &mut inner);
+        displayed::handle_marker(events, &contact, &mut inner);
+        chat_states::handle_chat_state(events, &contact, &mut inner);
+    }
+
+    let langs: Vec<&str> = agent.lang.iter().map(String::as_str).collect();
+    let Some((_lang, body)) = inner.get_best_body_cloned(langs) else {
+        debug!("Received carbon without body:\n{:#?}", inner);
+        return true;
+    };
+    let time_info = message_time_info(&inner);
+    let event = match inner.extract_valid_payload::<Replace>() {
+        Some(correction) => {
+            Event::ChatMessageCorrectionCarbon(carbon, correction.id, contact, body, time_info)
+        }
+        None => Event::ChatMessageCarbon(carbon, inner.id.clone(), contact, body, time_info),
+    };
+    events.push(event);
+    if carbon == Carbon::Received {
+        let from = inner.from.clone().unwrap();
+        displayed::track(agent, &from, &inner);
+    }
+    true
+}
@@ -0,0 +1,28 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! XEP-0085 (Chat State Notifications): whether the participants of a chat
+//! are composing a message, paying attention, or gone.
+
+use crate::{
+    jid::BareJid,
+    parsers::{chatstates::ChatState, message::Message},
+    Agent, Event,
+};
+
+/// Notify a contact of our chat state, without a message.
+pub async fn send_chat_state(agent: &mut Agent, recipient: BareJid, state: ChatState) {
+    let mut message = Message::chat(Some(recipient.into()));
+    message.payloads.push(state.into());
+    let _ = agent.client.send_stanza(message.into()).await;
+}
+
+/// Produce the event of the chat state a contact notified, if any.
+pub(crate) fn handle_chat_state(events: &mut Vec<Event>, contact: &BareJid, message: &mut Message) {
+    if let Some(state) = message.extract_valid_payload::<ChatState>() {
+        events.push(Event::ChatStateChanged(contact.clone(), state));
+    }
+}
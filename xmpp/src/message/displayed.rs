@@ -0,0 +1,201 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! XEP-0333 (Displayed Markers) and XEP-0490 (Message Displayed
+//! Synchronization): telling our contacts which of their messages were
+//! displayed, and our other clients up to where each chat was read.
+
+use core::str::FromStr;
+
+use crate::{
+    jid::{BareJid, Jid},
+    message::send::make_id,
+    minidom::Element,
+    parsers::{
+        chat_markers,
+        message::Message,
+        message_displayed::Displayed,
+        ns,
+        pubsub::{event::Payload, ItemId},
+        stanza_error::{DefinedCondition, StanzaError},
+        stanza_id::StanzaId,
+    },
+    pubsub::{
+        self,
+        send::{fetch_items, own_jid, FetchItemsSettings, PublishSettings},
+    },
+    Agent, Event, MessageId,
+};
+
+/// The last message received from a contact, until it is displayed.
+#[derive(Debug, Clone)]
+pub(crate) struct Displayable {
+    from: Jid,
+    id: Option<MessageId>,
+    markable: bool,
+    // Assigned by our own archive.
+    stanza_id: Option<String>,
+}
+
+/// Remember the last message received from a contact, see
+/// [Agent::mark_displayed].
+pub(crate) fn track(agent: &mut Agent, from: &Jid, message: &Message) {
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return;
+    };
+    let stanza_id = message
+        .payloads
+        .iter()
+        .filter(|payload| payload.is("stanza-id", ns::SID))
+        .filter_map(|payload| StanzaId::try_from(payload.clone()).ok())
+        .find(|stanza_id| stanza_id.by == own)
+        .map(|stanza_id| stanza_id.id);
+    let displayable = Displayable {
+        from: from.clone(),
+        id: message.id.clone(),
+        markable: message
+            .payloads
+            .iter()
+            .any(|payload| payload.is("markable", ns::CHAT_MARKERS)),
+        stanza_id,
+    };
+    agent.displayable.insert(from.to_bare(), displayable);
+}
+
+/// Produce the event of a contact displaying our messages, if `message`
+/// tells so.
+pub(crate) fn handle_marker(events: &mut Vec<Event>, contact: &BareJid, message: &mut Message) {
+    if let Some(displayed) = message.extract_valid_payload::<chat_markers::Displayed>() {
+        events.push(Event::MessageDisplayed(
+            contact.clone(),
+            MessageId(displayed.id),
+        ));
+    }
+}
+
+/// Mark every message received from a contact as displayed.
+pub async fn mark_displayed(agent: &mut Agent, contact: BareJid) -> Result<(), StanzaError> {
+    let Some(displayable) = agent.displayable.remove(&contact) else {
+        return Ok(());
+    };
+
+    // Like receipts, markers are only sent to the contacts allowed to see
+    // our presence.
+    if let (true, Some(id)) = (displayable.markable, displayable.id) {
+        if agent.roster.has_subscription_from(&contact) {
+            let mut marker = Message::chat(Some(displayable.from));
+            marker.id = Some(make_id());
+            marker
+                .payloads
+                .push(chat_markers::Displayed { id: id.0 }.into());
+            let _ = agent.client.send_stanza(marker.into()).await;
+        }
+    }
+
+    let (true, Some(stanza_id)) = (agent.displayed_sync, displayable.stanza_id) else {
+        return Ok(());
+    };
+    let displayed = Displayed {
+        stanza_id: StanzaId {
+            id: stanza_id.clone(),
+            by: own_jid(agent)?.into(),
+        },
+    };
+    let settings = PublishSettings::new(ns::MDS, displayed)
+        .with_id(contact.as_str())
+        .private();
+    pubsub::send::publish(agent, settings).await?;
+    agent.displayed.insert(contact, stanza_id);
+    Ok(())
+}
+
+/// Request up to where each chat was displayed by our clients.
+pub(crate) async fn request_displayed(agent: &mut Agent, events: &mut Vec<Event>) {
+    if !agent.displayed_sync {
+        return;
+    }
+    let page = match fetch_items::<Displayed>(agent, FetchItemsSettings::new(ns::MDS)).await {
+        Ok(page) => page,
+        // Nothing has been displayed yet.
+        Err(error) if error.defined_condition == DefinedCondition::ItemNotFound => return,
+        Err(error) => {
+            warn!(
+                "Failed to retrieve the displayed messages: {:?}",
+                error.defined_condition
+            );
+            return;
+        }
+    };
+    for item in page.items {
+        if let Some(contact) = parse_contact(item.id.as_ref()) {
+            update(agent, events, contact, item.payload.stanza_id.id);
+        }
+    }
+}
+
+fn parse_contact(id: Option<&ItemId>) -> Option<BareJid> {
+    match id.map(|id| BareJid::from_str(&id.0)) {
+        Some(Ok(contact)) => Some(contact),
+        Some(Err(e)) => {
+            warn!("Ignoring displayed message of an invalid chat: {e}");
+            None
+        }
+        None => {
+            warn!("Ignoring displayed message without an id");
+            None
+        }
+    }
+}
+
+fn parse_item(id: Option<&ItemId>, payload: Option<Element>) -> Option<(BareJid, String)> {
+    let contact = parse_contact(id)?;
+    match payload.map(Displayed::try_from) {
+        Some(Ok(displayed)) => Some((contact, displayed.stanza_id.id)),
+        Some(Err(e)) => {
+            warn!("Wrong payload type in MDS item: {e}");
+            None
+        }
+        None => None,
+    }
+}
+
+fn update(agent: &mut Agent, events: &mut Vec<Event>, contact: BareJid, stanza_id: String) {
+    if agent.displayed.get(&contact) == Some(&stanza_id) {
+        return;
+    }
+    agent.displayed.insert(contact.clone(), stanza_id.clone());
+    events.push(Event::ChatDisplayed(contact, stanza_id));
+}
+
+/// Handle a message displayed by another of our clients, notified by our
+/// server.
+pub(crate) fn handle_event(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &Jid,
+    payload: Payload,
+) {
+    let Some(own) = agent.client.bound_jid().map(Jid::to_bare) else {
+        return;
+    };
+    // Only our own account may tell what we displayed.
+    if *from != own {
+        warn!("Received an MDS event from {from}, ignoring");
+        return;
+    }
+
+    match payload {
+        Payload::Items { published, .. } => {
+            for item in published {
+                if let Some((contact, stanza_id)) = parse_item(item.id.as_ref(), item.payload) {
+                    update(agent, events, contact, stanza_id);
+                }
+            }
+        }
+        Payload::Purge { .. } | Payload::Delete { .. } => agent.displayed.clear(),
+        _ => (),
+    }
+}
@@ -4,5 +4,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub mod carbons;
+pub mod chat_states;
+pub mod displayed;
+pub(crate) mod receipts;
 pub mod receive;
 pub mod send;
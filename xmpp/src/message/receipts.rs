@@ -0,0 +1,54 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! XEP-0184 (Message Delivery Receipts): acknowledging the delivery of
+//! messages, and being told of the delivery of ours.
+
+use crate::{
+    jid::{BareJid, Jid},
+    message::send::make_id,
+    parsers::{
+        chat_markers,
+        message::Message,
+        receipts::{Received, Request},
+    },
+    Agent, Event, MessageId,
+};
+
+/// Produce the event of the delivery of one of our messages, if `message`
+/// acknowledges it.  The received markers of XEP-0333 are accepted too.
+pub(crate) fn handle_receipt(events: &mut Vec<Event>, contact: &BareJid, message: &mut Message) {
+    let id = match message.extract_valid_payload::<Received>() {
+        Some(received) => received.id,
+        None => match message.extract_valid_payload::<chat_markers::Received>() {
+            Some(received) => received.id,
+            None => return,
+        },
+    };
+    events.push(Event::MessageDelivered(contact.clone(), MessageId(id)));
+}
+
+/// Acknowledge the delivery of `message` if its sender asked for it.
+pub(crate) async fn respond(agent: &mut Agent, from: &Jid, message: &mut Message) {
+    if message.extract_valid_payload::<Request>().is_none() {
+        return;
+    }
+    let Some(id) = message.id.clone() else {
+        debug!("Ignoring a receipt request without a message id from {from}");
+        return;
+    };
+    // Acknowledging a message would tell whether we are online, only the
+    // contacts allowed to see our presence may know it.
+    if !agent.roster.has_subscription_from(&from.to_bare()) {
+        debug!("Not acknowledging the message {} of {from}", id.0);
+        return;
+    }
+
+    let mut receipt = Message::new(Some(from.clone()));
+    receipt.id = Some(make_id());
+    receipt.payloads.push(Received { id: id.0 }.into());
+    let _ = agent.client.send_stanza(receipt.into()).await;
+}
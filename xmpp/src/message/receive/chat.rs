@@ -9,7 +9,11 @@ use tokio_xmpp::{
     parsers::{message::Message, message_correct::Replace, muc::user::MucUser},
 };
 
-use crate::{delay::StanzaTimeInfo, Agent, Event, RoomNick};
+use crate::{
+    delay::StanzaTimeInfo,
+    message::{chat_states, displayed, receipts},
+    Agent, Event, RoomNick,
+};
 
 pub async fn handle_message_chat(
     agent: &mut Agent,
@@ -19,13 +23,21 @@ pub async fn handle_message_chat(
     time_info: StanzaTimeInfo,
 ) {
     let langs: Vec<&str> = agent.lang.iter().map(String::as_str).collect();
+    let is_muc_pm = message.extract_valid_payload::<MucUser>().is_some();
+
+    // Receipts, markers and chat states usually come without a body.
+    if !is_muc_pm {
+        let contact = from.to_bare();
+        receipts::handle_receipt(events, &contact, message);
+        displayed::handle_marker(events, &contact, message);
+        chat_states::handle_chat_state(events, &contact, message);
+    }
 
     let Some((_lang, body)) = message.get_best_body_cloned(langs) else {
         debug!("Received normal/chat message without body:\n{:#?}", message);
         return;
     };
 
-    let is_muc_pm = message.extract_valid_payload::<MucUser>().is_some();
     let correction = message.extract_valid_payload::<Replace>();
 
     if is_muc_pm {
@@ -61,5 +73,7 @@ pub async fn handle_message_chat(
             Event::ChatMessage(message.id.clone(), from.to_bare(), body, time_info)
         };
         events.push(event);
+        receipts::respond(agent, &from, message).await;
+        displayed::track(agent, &from, message);
     }
 }
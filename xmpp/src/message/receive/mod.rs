@@ -9,14 +9,22 @@ use tokio_xmpp::parsers::{
     ns,
 };
 
-use crate::{delay::message_time_info, pubsub, Agent, Event};
+use crate::{delay::message_time_info, mam, message::carbons, pubsub, Agent, Event};
 
 pub mod chat;
 pub mod group_chat;
 
 pub async fn handle_message(agent: &mut Agent, mut message: Message) -> Vec<Event> {
     let mut events = vec![];
+    if mam::receive::handle_archived_message(agent, &mut events, &mut message).await {
+        return events;
+    }
+    if carbons::handle_carbon(agent, &mut events, &message) {
+        return events;
+    }
+
     let from = message.from.clone().unwrap();
+    mam::receive::track_stanza_id(agent, &from, &message);
     let time_info = message_time_info(&message);
 
     match message.type_ {
@@ -7,10 +7,21 @@
 use crate::{
     jid::{BareJid, Jid},
     minidom::Element,
-    parsers::message::{Body, Message, MessagePayload, MessageType},
+    parsers::{
+        carbons::Private,
+        chat_markers::Markable,
+        chatstates::ChatState,
+        message::{Body, Message, MessagePayload, MessageType},
+        receipts::Request,
+    },
 };
 
-use crate::Agent;
+use crate::{Agent, MessageId};
+
+/// A new identifier for a message we send.
+pub(crate) fn make_id() -> MessageId {
+    MessageId(uuid::Uuid::new_v4().to_string())
+}
 
 #[derive(Clone, Debug)]
 pub struct RawMessageSettings<'a> {
@@ -18,6 +29,7 @@ pub struct RawMessageSettings<'a> {
     pub message_type: MessageType,
     pub message: &'a str,
     pub lang: Option<&'a str>,
+    pub id: Option<MessageId>,
     pub payloads: Vec<Element>,
 }
 
@@ -28,6 +40,7 @@ impl<'a> RawMessageSettings<'a> {
             message_type,
             message,
             lang: None,
+            id: None,
             payloads: Vec::new(),
         }
     }
@@ -42,6 +55,11 @@ impl<'a> RawMessageSettings<'a> {
         self
     }
 
+    pub fn with_id(mut self, id: MessageId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
     pub fn with_payload(mut self, payload: impl MessagePayload) -> Self {
         self.payloads.push(payload.into());
         self
@@ -54,10 +72,12 @@ pub async fn send_raw_message<'a>(agent: &mut Agent, settings: RawMessageSetting
         message_type,
         message,
         lang,
+        id,
         payloads,
     } = settings;
 
     let mut stanza = Message::new(Some(recipient));
+    stanza.id = id;
 
     for payload in payloads {
         stanza.payloads.push(payload);
@@ -75,6 +95,16 @@ pub struct MessageSettings<'a> {
     pub recipient: BareJid,
     pub message: &'a str,
     pub lang: Option<&'a str>,
+    /// The identifier of the message, generated when None.
+    pub id: Option<MessageId>,
+    /// Whether our other clients shouldn't get a copy of this message.
+    pub private: bool,
+    /// Whether to ask for a receipt once the message is delivered.
+    pub receipt: bool,
+    /// Whether to ask for a marker once the message is displayed.
+    pub markable: bool,
+    /// Our chat state to notify along with the message, from XEP-0085.
+    pub chat_state: Option<ChatState>,
 }
 
 impl<'a> MessageSettings<'a> {
@@ -83,6 +113,11 @@ impl<'a> MessageSettings<'a> {
             recipient,
             message,
             lang: None,
+            id: None,
+            private: false,
+            receipt: false,
+            markable: false,
+            chat_state: None,
         }
     }
 
@@ -90,20 +125,74 @@ impl<'a> MessageSettings<'a> {
         self.lang = Some(lang);
         self
     }
+
+    /// Ask our server not to copy this message to our other clients, using
+    /// XEP-0280 (Message Carbons).
+    pub fn with_private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
+    pub fn with_id(mut self, id: MessageId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Ask the recipient to acknowledge the delivery of this message, using
+    /// XEP-0184 (Message Delivery Receipts), which produces an
+    /// [Event::MessageDelivered][crate::Event::MessageDelivered].
+    pub fn with_receipt(mut self) -> Self {
+        self.receipt = true;
+        self
+    }
+
+    /// Ask the recipient to tell when this message is displayed, using
+    /// XEP-0333 (Displayed Markers), which produces an
+    /// [Event::MessageDisplayed][crate::Event::MessageDisplayed].
+    pub fn with_markable(mut self) -> Self {
+        self.markable = true;
+        self
+    }
+
+    /// Notify our chat state along with this message, usually
+    /// [ChatState::Active].
+    pub fn with_chat_state(mut self, state: ChatState) -> Self {
+        self.chat_state = Some(state);
+        self
+    }
 }
 
-pub async fn send_message<'a>(agent: &mut Agent, settings: MessageSettings<'a>) {
+pub async fn send_message<'a>(agent: &mut Agent, settings: MessageSettings<'a>) -> MessageId {
     let MessageSettings {
         recipient,
         message,
         lang,
+        id,
+        private,
+        receipt,
+        markable,
+        chat_state,
     } = settings;
 
+    // Receipts and markers refer to the message by its id.
+    let id = id.unwrap_or_else(make_id);
+
     // TODO: check that recipient is not in agent.joined_rooms
-    agent
-        .send_raw_message(
-            RawMessageSettings::new(recipient.into(), MessageType::Chat, message)
-                .with_lang_option(lang),
-        )
-        .await;
+    let mut settings = RawMessageSettings::new(recipient.into(), MessageType::Chat, message)
+        .with_lang_option(lang)
+        .with_id(id.clone());
+    if private {
+        settings = settings.with_payload(Private);
+    }
+    if receipt {
+        settings = settings.with_payload(Request);
+    }
+    if markable {
+        settings = settings.with_payload(Markable);
+    }
+    if let Some(state) = chat_state {
+        settings = settings.with_payload(state);
+    }
+    agent.send_raw_message(settings).await;
+    id
 }
@@ -0,0 +1,178 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::{BareJid, Jid},
+    parsers::{
+        iq::Iq,
+        message::{Message, MessageType, Subject},
+        muc::{
+            admin::{AdminQuery, Item},
+            owner::{Destroy, OwnerQuery},
+            user::{Affiliation, Role},
+        },
+    },
+    presence, Agent, Event, RoomNick,
+};
+
+async fn send_admin_set(agent: &mut Agent, room: BareJid, items: Vec<Item>) {
+    agent.muc_admin_counter += 1;
+    let id = format!("muc-admin-{}", agent.muc_admin_counter);
+    let iq = Iq::from_set(id, AdminQuery { items }).with_to(room.into());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to send room administration request: {}", e);
+    }
+}
+
+/// Change the role of an occupant, e.g. to grant or revoke voice.
+pub async fn set_role(
+    agent: &mut Agent,
+    room: BareJid,
+    nick: RoomNick,
+    role: Role,
+    reason: Option<&str>,
+) {
+    let mut item = Item::with_role(nick.to_string(), role);
+    if let Some(reason) = reason {
+        item = item.with_reason(reason);
+    }
+    send_admin_set(agent, room, vec![item]).await
+}
+
+/// Kick an occupant out of a room, which revokes their role.
+pub async fn kick(agent: &mut Agent, room: BareJid, nick: RoomNick, reason: Option<&str>) {
+    set_role(agent, room, nick, Role::None, reason).await
+}
+
+/// Change the affiliation of a user with a room.
+pub async fn set_affiliation(
+    agent: &mut Agent,
+    room: BareJid,
+    jid: BareJid,
+    affiliation: Affiliation,
+    reason: Option<&str>,
+) {
+    let mut item = Item::with_affiliation(jid, affiliation);
+    if let Some(reason) = reason {
+        item = item.with_reason(reason);
+    }
+    send_admin_set(agent, room, vec![item]).await
+}
+
+/// Change the affiliations of several users at once, e.g. to edit the
+/// member list.
+pub async fn set_affiliations(
+    agent: &mut Agent,
+    room: BareJid,
+    affiliations: Vec<(BareJid, Affiliation)>,
+) {
+    let items = affiliations
+        .into_iter()
+        .map(|(jid, affiliation)| Item::with_affiliation(jid, affiliation))
+        .collect();
+    send_admin_set(agent, room, items).await
+}
+
+/// Ban a user from a room, which also kicks them out of it.
+pub async fn ban(agent: &mut Agent, room: BareJid, jid: BareJid, reason: Option<&str>) {
+    set_affiliation(agent, room, jid, Affiliation::Outcast, reason).await
+}
+
+/// Request the list of users with an affiliation, which produces an
+/// [Event::RoomAffiliations].
+pub async fn request_affiliations(agent: &mut Agent, room: BareJid, affiliation: Affiliation) {
+    agent.muc_admin_counter += 1;
+    let id = format!("muc-admin-{}", agent.muc_admin_counter);
+    let iq = Iq::from_get(
+        id.clone(),
+        AdminQuery::list_affiliation(affiliation.clone()),
+    )
+    .with_to(room.clone().into());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to request the affiliations of {}: {}", room, e);
+        return;
+    }
+    agent.muc_admin_queries.insert(id, (room, affiliation));
+}
+
+/// Change the subject of a room.
+pub async fn set_subject(agent: &mut Agent, room: BareJid, subject: &str, lang: Option<&str>) {
+    let mut message = Message::new_with_type(MessageType::Groupchat, Jid::from(room));
+    message.subjects.insert(
+        lang.unwrap_or_default().to_owned(),
+        Subject(subject.to_owned()),
+    );
+    if let Err(e) = agent.client.send_stanza(message.into()).await {
+        error!("Failed to send room subject: {}", e);
+    }
+}
+
+/// Change our nickname in a room.
+///
+/// Once the room accepted it, an [Event::OccupantNickChanged] will be
+/// produced.
+pub async fn change_nick(agent: &mut Agent, room: BareJid, nick: RoomNick) {
+    if !agent.rooms_joined.contains_key(&room) {
+        warn!("Requesting to change nickname in room {room} which is not joined...");
+        return;
+    }
+
+    let presence = presence::send::make_presence(agent).with_to(room.with_resource(&nick));
+    if let Err(e) = agent.client.send_stanza(presence.into()).await {
+        error!("Failed to send nickname change: {}", e);
+    }
+}
+
+/// Destroy a room we own, optionally pointing its occupants to another one.
+pub async fn destroy_room(
+    agent: &mut Agent,
+    room: BareJid,
+    reason: Option<&str>,
+    alternate: Option<BareJid>,
+) {
+    let destroy = Destroy {
+        jid: alternate,
+        password: None,
+        reason: reason.map(String::from),
+    };
+    agent.muc_admin_counter += 1;
+    let id = format!("muc-admin-{}", agent.muc_admin_counter);
+    let iq = Iq::from_set(id, OwnerQuery::destroy(destroy)).with_to(room.into());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to send room destruction request: {}", e);
+    }
+}
+
+pub(crate) fn handle_admin_result(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    id: &str,
+    query: AdminQuery,
+) {
+    let Some((room, affiliation)) = agent.muc_admin_queries.remove(id) else {
+        warn!("Received unexpected room administration result {id}");
+        return;
+    };
+    let jids = query
+        .items
+        .into_iter()
+        .filter_map(|item| item.jid)
+        .map(|jid| jid.to_bare())
+        .collect();
+    events.push(Event::RoomAffiliations(room, affiliation, jids));
+}
+
+/// Report a room administration request which has been refused.
+pub(crate) fn handle_error(agent: &mut Agent, from: &Jid, id: &str) {
+    if let Some((_, affiliation)) = agent.muc_admin_queries.remove(id) {
+        warn!("Requesting the {affiliation:?} list of {from} failed");
+    } else if let Some(room) = agent.muc_config_queries.remove(id) {
+        warn!("Configuring room {room} failed");
+        agent.rooms_configuring.remove(&room);
+    } else if id.starts_with("muc-admin-") {
+        warn!("Room administration request {id} to {from} failed");
+    }
+}
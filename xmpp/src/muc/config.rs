@@ -0,0 +1,321 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::BareJid,
+    muc::room::{join_room, JoinRoomSettings},
+    parsers::{
+        data_forms::{DataForm, DataFormType, Field, FieldType},
+        iq::Iq,
+        muc::OwnerQuery,
+        ns,
+    },
+    Agent, Event,
+};
+
+const NAME: &str = "muc#roomconfig_roomname";
+const DESCRIPTION: &str = "muc#roomconfig_roomdesc";
+const PERSISTENT: &str = "muc#roomconfig_persistentroom";
+const PUBLIC: &str = "muc#roomconfig_publicroom";
+const MEMBERS_ONLY: &str = "muc#roomconfig_membersonly";
+const MODERATED: &str = "muc#roomconfig_moderatedroom";
+const PASSWORD_PROTECTED: &str = "muc#roomconfig_passwordprotectedroom";
+const PASSWORD: &str = "muc#roomconfig_roomsecret";
+const MAX_USERS: &str = "muc#roomconfig_maxusers";
+const ALLOW_INVITES: &str = "muc#roomconfig_allowinvites";
+const ENABLE_LOGGING: &str = "muc#roomconfig_enablelogging";
+
+/// The configuration form of a room, as sent by the room to its owners.
+///
+/// The usual fields of XEP-0045 have typed accessors, and any other field
+/// can be read and changed by its var.  Changing a field the room didn't
+/// include adds it to the form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomConfig {
+    form: DataForm,
+}
+
+impl RoomConfig {
+    pub(crate) fn new(form: DataForm) -> Self {
+        Self { form }
+    }
+
+    /// The form as received, with its labels and options, to let a user
+    /// fill it.
+    pub fn form(&self) -> &DataForm {
+        &self.form
+    }
+
+    /// Get a field by its var.
+    pub fn field(&self, var: &str) -> Option<&Field> {
+        self.form
+            .fields
+            .iter()
+            .find(|field| field.var.as_deref() == Some(var))
+    }
+
+    /// Get the first value of a field.
+    pub fn value(&self, var: &str) -> Option<&str> {
+        self.field(var)?.values.first().map(String::as_str)
+    }
+
+    /// Get the value of a boolean field.
+    pub fn bool(&self, var: &str) -> Option<bool> {
+        self.value(var).map(|value| value == "1" || value == "true")
+    }
+
+    /// Replace the values of a field, adding it with this type if the room
+    /// didn't include it.
+    pub fn set_values(&mut self, var: &str, type_: FieldType, values: Vec<String>) {
+        match self
+            .form
+            .fields
+            .iter_mut()
+            .find(|field| field.var.as_deref() == Some(var))
+        {
+            Some(field) => field.values = values,
+            None => {
+                let mut field = Field::new(var, type_);
+                field.values = values;
+                self.form.fields.push(field);
+            }
+        }
+    }
+
+    /// Replace the value of a single-valued field.
+    pub fn set_value(&mut self, var: &str, type_: FieldType, value: &str) {
+        self.set_values(var, type_, vec![value.to_owned()])
+    }
+
+    /// Set the value of a boolean field.
+    pub fn set_bool(&mut self, var: &str, value: bool) {
+        let value = if value { "1" } else { "0" };
+        self.set_value(var, FieldType::Boolean, value)
+    }
+
+    /// The natural-language name of the room.
+    pub fn name(&self) -> Option<&str> {
+        self.value(NAME)
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.set_value(NAME, FieldType::TextSingle, name)
+    }
+
+    /// The short description of the room.
+    pub fn description(&self) -> Option<&str> {
+        self.value(DESCRIPTION)
+    }
+
+    pub fn set_description(&mut self, description: &str) {
+        self.set_value(DESCRIPTION, FieldType::TextSingle, description)
+    }
+
+    /// Whether the room stays once its last occupant left.
+    pub fn persistent(&self) -> Option<bool> {
+        self.bool(PERSISTENT)
+    }
+
+    pub fn set_persistent(&mut self, persistent: bool) {
+        self.set_bool(PERSISTENT, persistent)
+    }
+
+    /// Whether the room is listed in the directory of the service.
+    pub fn public(&self) -> Option<bool> {
+        self.bool(PUBLIC)
+    }
+
+    pub fn set_public(&mut self, public: bool) {
+        self.set_bool(PUBLIC, public)
+    }
+
+    /// Whether only members can enter the room.
+    pub fn members_only(&self) -> Option<bool> {
+        self.bool(MEMBERS_ONLY)
+    }
+
+    pub fn set_members_only(&mut self, members_only: bool) {
+        self.set_bool(MEMBERS_ONLY, members_only)
+    }
+
+    /// Whether only occupants with voice can speak.
+    pub fn moderated(&self) -> Option<bool> {
+        self.bool(MODERATED)
+    }
+
+    pub fn set_moderated(&mut self, moderated: bool) {
+        self.set_bool(MODERATED, moderated)
+    }
+
+    /// Whether occupants can invite other users.
+    pub fn allow_invites(&self) -> Option<bool> {
+        self.bool(ALLOW_INVITES)
+    }
+
+    pub fn set_allow_invites(&mut self, allow_invites: bool) {
+        self.set_bool(ALLOW_INVITES, allow_invites)
+    }
+
+    /// Whether the discussions are logged.
+    pub fn logging(&self) -> Option<bool> {
+        self.bool(ENABLE_LOGGING)
+    }
+
+    pub fn set_logging(&mut self, logging: bool) {
+        self.set_bool(ENABLE_LOGGING, logging)
+    }
+
+    /// The password required to enter the room, if it is protected.
+    pub fn password(&self) -> Option<&str> {
+        match self.bool(PASSWORD_PROTECTED) {
+            Some(false) => None,
+            _ => self.value(PASSWORD).filter(|password| !password.is_empty()),
+        }
+    }
+
+    /// Protect the room with a password, or remove the protection.
+    pub fn set_password(&mut self, password: Option<&str>) {
+        self.set_bool(PASSWORD_PROTECTED, password.is_some());
+        self.set_value(
+            PASSWORD,
+            FieldType::TextPrivate,
+            password.unwrap_or_default(),
+        )
+    }
+
+    /// The maximum number of occupants, `None` meaning unlimited.
+    pub fn max_users(&self) -> Option<u32> {
+        self.value(MAX_USERS)?.parse().ok()
+    }
+
+    pub fn set_max_users(&mut self, max_users: Option<u32>) {
+        let value = max_users.map_or_else(|| String::from("none"), |max| max.to_string());
+        self.set_value(MAX_USERS, FieldType::ListSingle, &value)
+    }
+
+    /// Make the form to submit, with only the values of each field.
+    pub(crate) fn into_submit(self) -> DataForm {
+        let fields = self
+            .form
+            .fields
+            .into_iter()
+            .filter(|field| field.type_ != FieldType::Fixed && field.var.is_some())
+            .map(|field| Field {
+                values: field.values,
+                ..Field::new(field.var.as_deref().unwrap(), field.type_)
+            })
+            .collect();
+        DataForm {
+            type_: DataFormType::Submit,
+            form_type: Some(String::from(ns::MUC_ROOMCONFIG)),
+            title: None,
+            instructions: None,
+            fields,
+        }
+    }
+}
+
+/// Join a room, expecting to create it, and produce an
+/// [Event::RoomConfigForm] once it has been created so that the caller can
+/// configure it.
+pub async fn create_room<'a>(agent: &mut Agent, settings: JoinRoomSettings<'a>) {
+    if !agent.rooms_joined.contains_key(&settings.room)
+        && !agent.rooms_joining.contains_key(&settings.room)
+    {
+        agent.rooms_configuring.insert(settings.room.clone());
+    }
+    join_room(agent, settings).await
+}
+
+/// Handle the self-presence telling us we just created a room, which stays
+/// locked until configured.
+pub(crate) async fn handle_room_created(agent: &mut Agent, events: &mut Vec<Event>, room: BareJid) {
+    events.push(Event::RoomCreated(room.clone()));
+    if agent.rooms_configuring.contains(&room) {
+        request_config(agent, room).await;
+    } else {
+        // Nobody asked to configure it, accept the default configuration
+        // to unlock the room, making it an instant room.
+        let form = DataForm {
+            type_: DataFormType::Submit,
+            form_type: None,
+            title: None,
+            instructions: None,
+            fields: vec![],
+        };
+        send_owner_set(agent, room, form, false).await;
+    }
+}
+
+/// Request the configuration form of a room we own, which produces an
+/// [Event::RoomConfigForm].
+pub async fn request_config(agent: &mut Agent, room: BareJid) {
+    agent.muc_admin_counter += 1;
+    let id = format!("muc-admin-{}", agent.muc_admin_counter);
+    let iq = Iq::from_get(id.clone(), OwnerQuery::default()).with_to(room.clone().into());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to request the configuration of {}: {}", room, e);
+        return;
+    }
+    agent.muc_config_queries.insert(id, room);
+}
+
+async fn send_owner_set(agent: &mut Agent, room: BareJid, form: DataForm, track: bool) {
+    agent.muc_admin_counter += 1;
+    let id = format!("muc-admin-{}", agent.muc_admin_counter);
+    let iq = Iq::from_set(id.clone(), OwnerQuery::config(form)).with_to(room.clone().into());
+    if let Err(e) = agent.client.send_stanza(iq.into()).await {
+        error!("Failed to send the configuration of {}: {}", room, e);
+        return;
+    }
+    if track {
+        agent.muc_config_queries.insert(id, room);
+    }
+}
+
+/// Submit the configuration of a room, which produces an
+/// [Event::RoomConfigured] once accepted.
+pub async fn submit_config(agent: &mut Agent, room: BareJid, config: RoomConfig) {
+    agent.rooms_configuring.remove(&room);
+    send_owner_set(agent, room, config.into_submit(), true).await
+}
+
+/// Cancel the configuration of a room.  If the room was just created, this
+/// destroys it.
+pub async fn cancel_config(agent: &mut Agent, room: BareJid) {
+    agent.rooms_configuring.remove(&room);
+    let form = DataForm {
+        type_: DataFormType::Cancel,
+        form_type: None,
+        title: None,
+        instructions: None,
+        fields: vec![],
+    };
+    send_owner_set(agent, room, form, false).await
+}
+
+pub(crate) fn handle_owner_result(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    id: &str,
+    query: OwnerQuery,
+) {
+    let Some(room) = agent.muc_config_queries.remove(id) else {
+        warn!("Received unexpected room configuration result {id}");
+        return;
+    };
+    match query.form {
+        Some(form) => events.push(Event::RoomConfigForm(room, RoomConfig::new(form))),
+        None => warn!("Room {room} sent no configuration form"),
+    }
+}
+
+/// Handle the empty result acknowledging a submitted configuration.
+pub(crate) fn handle_empty_result(agent: &mut Agent, events: &mut Vec<Event>, id: &str) {
+    if let Some(room) = agent.muc_config_queries.remove(id) {
+        events.push(Event::RoomConfigured(room));
+    }
+}
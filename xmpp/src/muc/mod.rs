@@ -4,5 +4,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub mod admin;
+pub mod config;
+pub mod occupant;
 pub mod private_message;
 pub mod room;
+
+pub use config::RoomConfig;
+pub use occupant::Occupant;
@@ -0,0 +1,129 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::FullJid,
+    parsers::{
+        muc::user::{Affiliation, Item, MucUser, Role, Status},
+        occupant_id::OccupantId,
+        presence::{Presence, Type as PresenceType},
+    },
+    presence::ResourcePresence,
+    Agent, Event, RoomNick,
+};
+
+/// A participant in a room we are in, including ourselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occupant {
+    pub nick: RoomNick,
+    /// The long-lived affiliation of this user with the room.
+    pub affiliation: Affiliation,
+    /// The role of this occupant, only valid while they are in the room.
+    pub role: Role,
+    /// The real JID of this occupant, if the room lets us see it.
+    pub jid: Option<FullJid>,
+    /// The stable identifier of XEP-0421, if the room supports it.
+    pub occupant_id: Option<String>,
+    /// The presence of this occupant, as broadcast by the room.
+    pub presence: ResourcePresence,
+}
+
+impl Occupant {
+    fn from_presence(nick: RoomNick, item: Option<&Item>, presence: &Presence) -> Self {
+        let occupant_id = presence
+            .payloads
+            .iter()
+            .find_map(|payload| OccupantId::try_from(payload.clone()).ok())
+            .map(|occupant_id| occupant_id.id);
+        Occupant {
+            nick,
+            affiliation: item.map_or(Affiliation::None, |item| item.affiliation.clone()),
+            role: item.map_or(Role::None, |item| item.role.clone()),
+            jid: item.and_then(|item| item.jid.clone()),
+            occupant_id,
+            presence: ResourcePresence::from_presence(presence),
+        }
+    }
+}
+
+/// Update the occupant list of a room from a presence it sent us.
+pub(crate) fn handle_occupant_presence(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &FullJid,
+    muc: &MucUser,
+    presence: &Presence,
+) {
+    let room = from.to_bare();
+    if !agent.rooms_joined.contains_key(&room) && !agent.rooms_joining.contains_key(&room) {
+        debug!(
+            "Ignoring occupant presence from {} which we aren't in",
+            from
+        );
+        return;
+    }
+
+    let nick = RoomNick::from_resource_ref(from.resource());
+    let item = muc.items.first();
+    let occupants = agent.room_occupants.entry(room.clone()).or_default();
+
+    match presence.type_ {
+        PresenceType::None => {
+            let occupant = Occupant::from_presence(nick.clone(), item, presence);
+            match occupants.insert(nick.clone(), occupant.clone()) {
+                None => events.push(Event::OccupantJoined(room, occupant)),
+                Some(old) => {
+                    if old.role != occupant.role {
+                        events.push(Event::OccupantRoleChanged(
+                            room.clone(),
+                            nick.clone(),
+                            occupant.role,
+                        ));
+                    }
+                    if old.affiliation != occupant.affiliation {
+                        events.push(Event::OccupantAffiliationChanged(
+                            room,
+                            nick,
+                            occupant.affiliation,
+                        ));
+                    }
+                }
+            }
+        }
+        PresenceType::Unavailable => {
+            if muc.status.contains(&Status::NewNick) {
+                // The occupant stays in the room, their next presence will
+                // come from the new nickname.
+                let Some(new_nick) = item
+                    .and_then(|item| item.nick.as_deref())
+                    .and_then(|new_nick| new_nick.parse::<RoomNick>().ok())
+                else {
+                    warn!("Nickname change from {} without a valid new nickname", from);
+                    return;
+                };
+                if let Some(mut occupant) = occupants.remove(&nick) {
+                    occupant.nick = new_nick.clone();
+                    occupants.insert(new_nick.clone(), occupant);
+                }
+                events.push(Event::OccupantNickChanged(room, nick, new_nick));
+                return;
+            }
+
+            occupants.remove(&nick);
+            let reason = item
+                .and_then(|item| item.reason.clone())
+                .map(|reason| reason.0);
+            if muc.status.contains(&Status::Banned) {
+                events.push(Event::OccupantBanned(room, nick, reason));
+            } else if muc.status.contains(&Status::Kicked) {
+                events.push(Event::OccupantKicked(room, nick, reason));
+            } else {
+                events.push(Event::OccupantLeft(room, nick));
+            }
+        }
+        _ => (),
+    }
+}
@@ -4,5 +4,186 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    jid::{BareJid, FullJid, ResourcePart},
+    parsers::{
+        caps::Caps,
+        date::DateTime,
+        ecaps2::ECaps2,
+        hashes::Hash,
+        idle::Idle,
+        presence::{Presence, Show},
+        vcard_update::VCardUpdate,
+    },
+    Event,
+};
+
 pub mod receive;
 pub mod send;
+
+/// The presence of one resource of a contact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourcePresence {
+    /// The availability of the resource, `None` meaning plainly available.
+    pub show: Option<Show>,
+    /// The status messages, by language.
+    pub statuses: BTreeMap<String, String>,
+    /// The priority of the resource, negative ones not receiving messages
+    /// sent to the bare JID.
+    pub priority: i8,
+    /// Since when the user has been idle, from XEP-0319.
+    pub idle_since: Option<DateTime>,
+    /// The node of the XEP-0115 entity capabilities.
+    pub caps_node: Option<String>,
+    /// The XEP-0115 verification string.
+    pub caps: Option<Hash>,
+    /// The XEP-0390 entity capabilities hashes.
+    pub ecaps2: Vec<Hash>,
+    /// The SHA-1 of the avatar advertised using XEP-0153, if any.
+    pub avatar_hash: Option<[u8; 20]>,
+}
+
+impl ResourcePresence {
+    pub(crate) fn from_presence(presence: &Presence) -> Self {
+        let mut resource = ResourcePresence {
+            show: presence.show.clone(),
+            statuses: presence.statuses.clone(),
+            priority: presence.priority.0,
+            idle_since: None,
+            caps_node: None,
+            caps: None,
+            ecaps2: Vec::new(),
+            avatar_hash: None,
+        };
+        for payload in &presence.payloads {
+            if let Ok(idle) = Idle::try_from(payload.clone()) {
+                resource.idle_since = Some(idle.since);
+            } else if let Ok(caps) = Caps::try_from(payload.clone()) {
+                resource.caps_node = Some(caps.node);
+                resource.caps = Some(Hash::new(caps.hash, caps.ver));
+            } else if let Ok(ecaps2) = ECaps2::try_from(payload.clone()) {
+                resource.ecaps2 = ecaps2.hashes;
+            } else if let Ok(update) = VCardUpdate::try_from(payload.clone()) {
+                resource.avatar_hash = update.photo.and_then(|photo| photo.data);
+            }
+        }
+        resource
+    }
+
+    /// The status message in the first of `langs` available, or any other.
+    pub fn status(&self, langs: &[&str]) -> Option<&str> {
+        langs
+            .iter()
+            .find_map(|lang| self.statuses.get(*lang))
+            .or_else(|| self.statuses.values().next())
+            .map(String::as_str)
+    }
+
+    /// How available the resource is, used to pick the best one when their
+    /// priorities are equal.
+    fn availability(&self) -> u8 {
+        match self.show {
+            Some(Show::Chat) => 4,
+            None => 3,
+            Some(Show::Away) => 2,
+            Some(Show::Xa) => 1,
+            Some(Show::Dnd) => 0,
+        }
+    }
+}
+
+/// The presences received from contacts, by bare JID and resource.
+///
+/// Room occupants aren't tracked here.
+#[derive(Clone, Debug, Default)]
+pub struct Presences {
+    contacts: HashMap<BareJid, HashMap<ResourcePart, ResourcePresence>>,
+}
+
+impl Presences {
+    /// Whether at least one resource of this contact is available.
+    pub fn is_online(&self, jid: &BareJid) -> bool {
+        self.contacts.contains_key(jid)
+    }
+
+    /// Iterate over the contacts which have at least one available
+    /// resource.
+    pub fn online_contacts(&self) -> impl Iterator<Item = &BareJid> {
+        self.contacts.keys()
+    }
+
+    /// Iterate over the available resources of a contact.
+    pub fn resources<'a>(
+        &'a self,
+        jid: &BareJid,
+    ) -> impl Iterator<Item = (&'a ResourcePart, &'a ResourcePresence)> {
+        self.contacts.get(jid).into_iter().flatten()
+    }
+
+    /// Get the presence of a single resource.
+    pub fn get(&self, jid: &FullJid) -> Option<&ResourcePresence> {
+        self.contacts
+            .get(&jid.to_bare())
+            .and_then(|resources| resources.get(jid.resource()))
+    }
+
+    /// The resource most likely to answer: the one with the highest
+    /// priority, then the most available one.
+    pub fn best_resource(&self, jid: &BareJid) -> Option<(FullJid, &ResourcePresence)> {
+        self.contacts
+            .get(jid)?
+            .iter()
+            .max_by_key(|(_, presence)| (presence.priority, presence.availability()))
+            .map(|(resource, presence)| (jid.with_resource(resource), presence))
+    }
+
+    /// Record the new presence of a resource, `None` meaning it became
+    /// unavailable, returning the event to produce if anything changed.
+    pub(crate) fn update(
+        &mut self,
+        jid: FullJid,
+        presence: Option<ResourcePresence>,
+    ) -> Option<Event> {
+        let bare = jid.to_bare();
+        let resource = jid.resource().to_owned();
+        match presence {
+            Some(presence) => {
+                let resources = self.contacts.entry(bare).or_default();
+                if resources.get(&resource) == Some(&presence) {
+                    return None;
+                }
+                resources.insert(resource, presence.clone());
+                Some(Event::PresenceChanged(jid, presence))
+            }
+            None => {
+                let resources = self.contacts.get_mut(&bare)?;
+                resources.remove(&resource)?;
+                if resources.is_empty() {
+                    self.contacts.remove(&bare);
+                }
+                Some(Event::PresenceUnavailable(jid))
+            }
+        }
+    }
+
+    /// Mark every resource of a contact unavailable.
+    pub(crate) fn remove_contact(&mut self, jid: &BareJid) -> Vec<Event> {
+        let Some(resources) = self.contacts.remove(jid) else {
+            return Vec::new();
+        };
+        resources
+            .into_keys()
+            .map(|resource| Event::PresenceUnavailable(jid.with_resource(&resource)))
+            .collect()
+    }
+
+    /// Forget every presence, e.g. once disconnected.
+    pub(crate) fn clear(&mut self) -> Vec<Event> {
+        let jids: Vec<BareJid> = self.contacts.keys().cloned().collect();
+        jids.iter()
+            .flat_map(|jid| self.remove_contact(jid))
+            .collect()
+    }
+}
@@ -9,7 +9,9 @@ use tokio_xmpp::parsers::{
     presence::{Presence, Type as PresenceType},
 };
 
-use crate::{Agent, Event};
+#[cfg(feature = "avatars")]
+use crate::pubsub;
+use crate::{muc, presence::ResourcePresence, Agent, Event, RoomNick};
 
 /// Translate a `Presence` stanza into a list of higher-level `Event`s.
 pub async fn handle_presence(agent: &mut Agent, presence: Presence) -> Vec<Event> {
@@ -17,7 +19,17 @@ pub async fn handle_presence(agent: &mut Agent, presence: Presence) -> Vec<Event
     let mut events = vec![];
 
     // Extract the JID of the sender (i.e. the one whose presence is being sent).
-    let from = presence.from.clone().unwrap().to_bare();
+    let Some(full_from) = presence.from.clone() else {
+        debug!("Ignoring presence without a sender: {:?}", presence);
+        return events;
+    };
+    let from = full_from.to_bare();
+
+    if presence.type_ == PresenceType::Subscribe {
+        let status = presence.statuses.values().next().cloned();
+        events.push(Event::SubscriptionRequest(from, status));
+        return events;
+    }
 
     // Search through the payloads for a MUC user status.
 
@@ -27,34 +39,126 @@ pub async fn handle_presence(agent: &mut Agent, presence: Presence) -> Vec<Event
         .filter_map(|p| MucUser::try_from(p.clone()).ok())
         .next()
     {
+        if let Ok(occupant) = full_from.try_as_full() {
+            muc::occupant::handle_occupant_presence(agent, &mut events, occupant, &muc, &presence);
+        }
+
         // If a MUC user status was found, search through the statuses for a self-presence.
         if muc.status.iter().any(|s| *s == Status::SelfPresence) {
             // If a self-presence was found, then the stanza is about the client's own presence.
 
             match presence.type_ {
                 PresenceType::None => {
+                    // The room may have changed our nickname.
+                    let nick = full_from.resource().map(RoomNick::from_resource_ref);
+                    if agent.rooms_joined.contains_key(&from) {
+                        // Our presence in the room changed, e.g. our role.
+                        if let Some(nick) = nick {
+                            agent.rooms_joined.insert(from.clone(), nick);
+                        }
+                        return events;
+                    }
+
                     // According to https://xmpp.org/extensions/xep-0045.html#enter-pres, no type should be seen as "available".
-                    if let Some(nick) = agent.rooms_joining.get(&from) {
-                        agent.rooms_joined.insert(from.clone(), nick.clone());
-                        agent.rooms_joining.remove(&from);
+                    if let Some(joining_nick) = agent.rooms_joining.remove(&from) {
+                        agent
+                            .rooms_joined
+                            .insert(from.clone(), nick.unwrap_or(joining_nick));
                     } else {
-                        warn!("Received self-presence from {} while the room was not marked as joining.", presence.from.unwrap());
+                        warn!("Received self-presence from {} while the room was not marked as joining.", full_from);
                     }
                     events.push(Event::RoomJoined(from.clone()));
+                    if muc.status.contains(&Status::RoomHasBeenCreated) {
+                        muc::config::handle_room_created(agent, &mut events, from.clone()).await;
+                    } else if agent.rooms_configuring.remove(&from) {
+                        warn!("Room {} already existed, it won't be configured.", from);
+                    }
+                }
+                PresenceType::Unavailable if muc.status.contains(&Status::NewNick) => {
+                    // We are still in the room, under the new nickname.
+                    if let Some(nick) = muc
+                        .items
+                        .first()
+                        .and_then(|item| item.nick.as_deref())
+                        .and_then(|nick| nick.parse::<RoomNick>().ok())
+                    {
+                        agent.rooms_joined.insert(from.clone(), nick);
+                    }
                 }
                 PresenceType::Unavailable => {
                     // According to https://xmpp.org/extensions/xep-0045.html#exit, the server will use type "unavailable" to notify the client that it has left the room/
-                    if agent.rooms_leaving.contains_key(&from) {
-                        agent.rooms_joined.remove(&from);
-                        agent.rooms_leaving.remove(&from);
-                    } else {
-                        warn!("Received self-presence unavailable from {} while the room was not marked as leaving.", presence.from.unwrap());
+                    // We may also have been kicked or banned, or the room destroyed.
+                    let removed = [
+                        Status::Kicked,
+                        Status::Banned,
+                        Status::RemovalFromRoom,
+                        Status::ConfigMembersOnly,
+                        Status::ServiceShutdown,
+                        Status::ServiceErrorKick,
+                    ]
+                    .iter()
+                    .any(|status| muc.status.contains(status));
+                    if agent.rooms_leaving.remove(&from).is_none()
+                        && !removed
+                        && muc.destroy.is_none()
+                    {
+                        warn!("Received self-presence unavailable from {} while the room was not marked as leaving.", full_from);
+                    }
+                    agent.rooms_joined.remove(&from);
+                    agent.room_occupants.remove(&from);
+                    if let Some(destroy) = muc.destroy {
+                        events.push(Event::RoomDestroyed(
+                            from.clone(),
+                            destroy.jid,
+                            destroy.reason,
+                        ));
                     }
                     events.push(Event::RoomLeft(from.clone()));
                 }
-                _ => unimplemented!("Presence type {:?}", presence.type_), // TODO: What to do here?
+                _ => debug!(
+                    "Ignoring self-presence of type {:?} from {}",
+                    presence.type_, full_from
+                ),
+            }
+        }
+
+        // Room occupants aren't contacts, don't track them.
+        return events;
+    }
+
+    match presence.type_ {
+        PresenceType::None => {
+            let Ok(jid) = full_from.try_into_full() else {
+                debug!("Ignoring available presence from bare JID {}", from);
+                return events;
+            };
+            let resource = ResourcePresence::from_presence(&presence);
+            #[cfg(feature = "avatars")]
+            if let Some(hash) = &resource.avatar_hash {
+                let previous = agent.presences.get(&jid).and_then(|p| p.avatar_hash);
+                if previous.as_ref() != Some(hash) {
+                    let from = jid.clone().into();
+                    pubsub::avatar::handle_presence_hash(agent, &mut events, &from, hash).await;
+                }
+            }
+            events.extend(agent.presences.update(jid, Some(resource)));
+        }
+        PresenceType::Unavailable => match full_from.try_into_full() {
+            Ok(jid) => events.extend(agent.presences.update(jid, None)),
+            Err(jid) => events.extend(agent.presences.remove_contact(&jid)),
+        },
+        PresenceType::Error => {
+            // A room we tried to join refused us.
+            if agent.rooms_joining.remove(&from).is_some() {
+                warn!("Failed to join room {}: {:?}", from, presence.payloads);
+            } else {
+                warn!("Received presence error from {}", full_from);
             }
         }
+        _ => debug!(
+            "Ignoring presence of type {:?} from {}",
+            presence.type_, full_from
+        ),
     }
 
     // Return the list of events.
@@ -4,13 +4,26 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use tokio_xmpp::parsers::{
-    caps::{compute_disco, hash_caps, Caps},
-    disco::DiscoInfoResult,
-    hashes::Algo,
-    presence::{Presence, Type as PresenceType},
+use tokio_xmpp::{
+    jid::Jid,
+    parsers::{
+        caps::{compute_disco, hash_caps, Caps},
+        disco::DiscoInfoResult,
+        hashes::Algo,
+        presence::{Presence, Priority, Show, Type as PresenceType},
+    },
 };
 
+use crate::Agent;
+
+/// The presence we broadcast, as last set with [Agent::set_presence].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OwnPresence {
+    pub show: Option<Show>,
+    pub status: Option<String>,
+    pub priority: i8,
+}
+
 pub(crate) fn make_initial_presence(disco: &DiscoInfoResult, node: &str) -> Presence {
     let caps_data = compute_disco(disco);
     let hash = hash_caps(&caps_data, Algo::Sha_1).unwrap();
@@ -20,3 +33,54 @@ pub(crate) fn make_initial_presence(disco: &DiscoInfoResult, node: &str) -> Pres
     presence.add_payload(caps);
     presence
 }
+
+/// Build our current presence, with our entity capabilities.
+pub(crate) fn make_presence(agent: &Agent) -> Presence {
+    let own = &agent.own_presence;
+    let mut presence = make_initial_presence(&agent.disco, &agent.node);
+    presence.show = own.show.clone();
+    presence.priority = Priority(own.priority);
+    if let Some(status) = &own.status {
+        presence.set_status("", status.clone());
+    }
+    #[cfg(feature = "avatars")]
+    if let Some(update) = &agent.own_avatar {
+        presence.add_payload(update.clone());
+    }
+    presence
+}
+
+/// Change our presence, broadcasting it to our contacts and to the rooms we
+/// are in.
+pub async fn set_presence(
+    agent: &mut Agent,
+    show: Option<Show>,
+    status: Option<&str>,
+    priority: i8,
+) {
+    agent.own_presence = OwnPresence {
+        show,
+        status: status.map(String::from),
+        priority,
+    };
+    broadcast_presence(agent).await;
+}
+
+/// Send our current presence to our contacts and to the rooms we are in.
+pub(crate) async fn broadcast_presence(agent: &mut Agent) {
+    let presence = make_presence(agent);
+    if let Err(e) = agent.client.send_stanza(presence.clone().into()).await {
+        error!("Failed to send presence: {}", e);
+        return;
+    }
+
+    let rooms: Vec<Jid> = agent
+        .rooms_joined
+        .iter()
+        .map(|(room, nick)| room.with_resource(nick.as_ref()).into())
+        .collect();
+    for room in rooms {
+        let presence = presence.clone().with_to(room);
+        let _ = agent.client.send_stanza(presence.into()).await;
+    }
+}
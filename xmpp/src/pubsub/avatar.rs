@@ -5,93 +5,314 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use super::Agent;
-use crate::Event;
-use std::fs::{self, File};
-use std::io::{self, Write};
+use crate::{
+    iq::{request, IqKind},
+    pubsub::send::{fetch_items, own_jid, publish, FetchItemsSettings, PublishSettings},
+    Event,
+};
+use core::str::FromStr;
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 use tokio_xmpp::parsers::{
-    avatar::{Data, Metadata},
-    iq::Iq,
+    avatar::{Data, Info, Metadata},
+    hashes::Sha1HexAttribute,
     jid::Jid,
     ns,
-    pubsub::{
-        event::Item,
-        pubsub::{Items, PubSub},
-        NodeName,
-    },
+    pubsub::{event::Item, ItemId},
+    stanza_error::StanzaError,
+    vcard::{Binval, Photo, Type, VCard, VCardQuery},
+    vcard_update::{self, VCardUpdate},
 };
 
+/// The avatars retrieved from other entities, each stored in a file named
+/// after the SHA-1 of its data.
+#[derive(Debug, Clone)]
+pub(crate) struct AvatarCache {
+    directory: PathBuf,
+}
+
+impl AvatarCache {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// The file of this avatar, if it has already been retrieved.
+    fn get(&self, hash: &str) -> Option<PathBuf> {
+        let path = self.directory.join(hash);
+        path.is_file().then_some(path)
+    }
+
+    fn store(&self, hash: &str, data: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(hash);
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+    to_hex(&Sha1::digest(data))
+}
+
+fn retrieved(from: &Jid, path: PathBuf) -> Event {
+    Event::AvatarRetrieved(from.clone(), path.to_string_lossy().into_owned())
+}
+
+/// Check that the data matches the hash it was advertised with, then store
+/// it in the cache.
+fn save_avatar(agent: &Agent, from: &Jid, hash: &str, data: &[u8]) -> Option<Event> {
+    let cache = agent.avatar_cache.as_ref()?;
+    if sha1_hex(data) != hash {
+        warn!("Avatar {hash} from {from} doesn't match its hash, ignoring");
+        return None;
+    }
+    match cache.store(hash, data) {
+        Ok(path) => Some(retrieved(from, path)),
+        Err(e) => {
+            error!("Failed to save avatar {hash} from {from}: {e}");
+            None
+        }
+    }
+}
+
+/// Produce the avatar with this hash if it is in the cache, otherwise
+/// request it, from the PEP data node or from the vCard of `from`.
+async fn retrieve_avatar(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &Jid,
+    hash: String,
+    from_vcard: bool,
+) {
+    let Some(cache) = &agent.avatar_cache else {
+        return;
+    };
+    if let Some(path) = cache.get(&hash) {
+        events.push(retrieved(from, path));
+        return;
+    }
+
+    let data = if from_vcard {
+        match request::request::<_, VCard>(agent, from.clone(), IqKind::Get, VCardQuery).await {
+            Ok(VCard {
+                photo: Some(photo), ..
+            }) => photo.binval.data,
+            Ok(_) => {
+                warn!("The vCard of {from} has no photo, despite advertising one");
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to retrieve avatar {hash} from {from}: {:?}",
+                    e.defined_condition
+                );
+                return;
+            }
+        }
+    } else {
+        let settings = FetchItemsSettings::new(ns::AVATAR_DATA)
+            .with_service(from.to_bare())
+            .with_id(&hash);
+        let page = match fetch_items::<Data>(agent, settings).await {
+            Ok(page) => page,
+            Err(e) => {
+                warn!(
+                    "Failed to retrieve avatar {hash} from {from}: {:?}",
+                    e.defined_condition
+                );
+                return;
+            }
+        };
+        let item = page
+            .items
+            .into_iter()
+            .find(|item| item.id.as_ref().is_some_and(|id| id.0 == hash));
+        match item {
+            Some(item) => item.payload.data,
+            None => {
+                warn!("Avatar {hash} is missing from {from}");
+                return;
+            }
+        }
+    };
+    events.extend(save_avatar(agent, from, &hash, &data));
+}
+
 pub(crate) async fn handle_metadata_pubsub_event(
     from: &Jid,
     agent: &mut Agent,
-    items: Vec<Item>,
+    published: Vec<Item>,
+    retracted: Vec<ItemId>,
 ) -> Vec<Event> {
     let mut events = Vec::new();
-    for item in items {
-        let payload = item.payload.clone().unwrap();
-        if payload.is("metadata", ns::AVATAR_METADATA) {
-            match Metadata::try_from(payload) {
-                Ok(metadata) => {
-                    for info in metadata.infos {
-                        let filename = format!("data/{}/{}", from, &*info.id.to_hex());
-                        let file_length = match fs::metadata(filename.clone()) {
-                            Ok(metadata) => metadata.len(),
-                            Err(_) => 0,
-                        };
-                        // TODO: Also check the hash.
-                        if info.bytes as u64 == file_length {
-                            events.push(Event::AvatarRetrieved(from.clone(), filename));
-                        } else {
-                            let iq = download_avatar(from);
-                            let _ = agent.client.send_stanza(iq.into()).await;
-                        }
-                    }
-                }
-
-                Err(e) => error!("Error parsing avatar metadata: {}", e),
+    if !retracted.is_empty() {
+        events.push(Event::AvatarRemoved(from.clone()));
+    }
+    for item in published {
+        let Some(payload) = item.payload else {
+            continue;
+        };
+        let metadata = match Metadata::try_from(payload) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                error!("Error parsing avatar metadata: {}", e);
+                continue;
+            }
+        };
+        // An empty list means the avatar has been disabled.
+        if metadata.infos.is_empty() {
+            events.push(Event::AvatarRemoved(from.clone()));
+            continue;
+        }
+        // Only the avatars published in the data node can be retrieved.
+        match metadata.infos.iter().find(|info| info.url.is_none()) {
+            Some(info) => {
+                let hash = info.id.to_hex();
+                retrieve_avatar(agent, &mut events, from, hash, false).await;
             }
+            None => debug!("Avatar of {from} is only available over HTTP, ignoring"),
         }
     }
     events
 }
 
-fn download_avatar(from: &Jid) -> Iq {
-    Iq::from_get(
-        "coucou",
-        PubSub::Items(Items {
-            max_items: None,
-            node: NodeName(String::from(ns::AVATAR_DATA)),
-            subid: None,
-            items: Vec::new(),
-        }),
-    )
-    .with_to(from.clone())
+/// Retrieve the avatar a contact advertises in its presence, in case it
+/// doesn't publish it using PEP.
+pub(crate) async fn handle_presence_hash(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: &Jid,
+    hash: &[u8; 20],
+) {
+    let from = Jid::from(from.to_bare());
+    retrieve_avatar(agent, events, &from, to_hex(hash), true).await;
 }
 
-// The return value of this function will be simply pushed to a Vec in the caller function,
-// so it makes no sense to allocate a Vec here - we're lazy instead
-pub(crate) fn handle_data_pubsub_iq<'a>(
-    from: &'a Jid,
-    items: &'a Items,
-) -> impl IntoIterator<Item = Event> + 'a {
-    let from = from.clone();
-    items
-        .items
-        .iter()
-        .filter_map(move |item| match (&item.id, &item.payload) {
-            (Some(id), Some(payload)) => {
-                let data = Data::try_from(payload.clone()).unwrap();
-                let filename = save_avatar(&from, id.0.clone(), &data.data).unwrap();
-                Some(Event::AvatarRetrieved(from.clone(), filename))
+/// Publish our avatar, in PEP and in our vCard, and advertise its hash in
+/// our presence.
+pub(crate) async fn set_avatar(
+    agent: &mut Agent,
+    data: &[u8],
+    mime: &str,
+) -> Result<(), StanzaError> {
+    let hash = sha1_hex(data);
+    let id = Sha1HexAttribute::from_str(&hash).unwrap();
+
+    // The data has to be published first, so that it can be retrieved as
+    // soon as the metadata notifications are received.
+    let settings = PublishSettings::new(
+        ns::AVATAR_DATA,
+        Data {
+            data: data.to_vec(),
+        },
+    )
+    .with_id(&hash);
+    let metadata = Metadata {
+        infos: vec![Info {
+            bytes: data.len() as u32,
+            width: None,
+            height: None,
+            id,
+            type_: String::from(mime),
+            url: None,
+        }],
+    };
+    let pep = match publish(agent, settings).await {
+        Ok(_) => publish(
+            agent,
+            PublishSettings::new(ns::AVATAR_METADATA, metadata).with_id(&hash),
+        )
+        .await
+        .map(|_| ()),
+        Err(err) => Err(err),
+    };
+
+    let photo = Photo {
+        type_: Type {
+            data: String::from(mime),
+        },
+        binval: Binval {
+            data: data.to_vec(),
+        },
+    };
+    let vcard = update_vcard(agent, Some(photo)).await;
+    let result = combine(pep, vcard);
+
+    if result.is_ok() {
+        if let Some(cache) = &agent.avatar_cache {
+            if let Err(e) = cache.store(&hash, data) {
+                warn!("Failed to cache our own avatar: {e}");
             }
-            _ => None,
-        })
+        }
+        let mut hash_bytes = [0; 20];
+        hash_bytes.copy_from_slice(&Sha1::digest(data));
+        advertise(agent, Some(hash_bytes)).await;
+    }
+    result
+}
+
+/// Stop publishing our avatar.
+pub(crate) async fn remove_avatar(agent: &mut Agent) -> Result<(), StanzaError> {
+    let settings = PublishSettings::new(ns::AVATAR_METADATA, Metadata { infos: Vec::new() });
+    let pep = publish(agent, settings).await.map(|_| ());
+    let vcard = update_vcard(agent, None).await;
+    let result = combine(pep, vcard);
+    if result.is_ok() {
+        advertise(agent, None).await;
+    }
+    result
+}
+
+/// Legacy contacts only see the vCard, and servers without PEP only have
+/// the vCard, so one of both succeeding is enough.
+#[allow(clippy::result_large_err)]
+fn combine(
+    pep: Result<(), StanzaError>,
+    vcard: Result<(), StanzaError>,
+) -> Result<(), StanzaError> {
+    match (pep, vcard) {
+        (Err(pep), Err(_)) => Err(pep),
+        (Err(e), Ok(())) => {
+            warn!(
+                "Failed to publish our avatar using PEP: {:?}",
+                e.defined_condition
+            );
+            Ok(())
+        }
+        (Ok(()), Err(e)) => {
+            warn!("Failed to update our vCard: {:?}", e.defined_condition);
+            Ok(())
+        }
+        (Ok(()), Ok(())) => Ok(()),
+    }
+}
+
+/// Replace the photo of our vCard, keeping everything else.
+async fn update_vcard(agent: &mut Agent, photo: Option<Photo>) -> Result<(), StanzaError> {
+    let own = Jid::from(own_jid(agent)?);
+    let query = request::make_request(IqKind::Get, &VCardQuery)?;
+    let mut vcard = match request::send_request(agent, own.clone(), query).await? {
+        Some(vcard) => request::parse_result::<VCard>(&own, Some(vcard))?,
+        // No vCard has been stored yet.
+        None => VCard {
+            photo: None,
+            payloads: Vec::new(),
+        },
+    };
+    vcard.photo = photo;
+    request::request_empty(agent, own, IqKind::Set, vcard).await
 }
 
-fn save_avatar(from: &Jid, id: String, data: &[u8]) -> io::Result<String> {
-    let directory = format!("data/{}", from);
-    let filename = format!("data/{}/{}", from, id);
-    fs::create_dir_all(directory)?;
-    let mut file = File::create(&filename)?;
-    file.write_all(data)?;
-    Ok(filename)
+/// Advertise the hash of our avatar in our presence, for XEP-0153.
+async fn advertise(agent: &mut Agent, hash: Option<[u8; 20]>) {
+    agent.own_avatar = Some(VCardUpdate {
+        photo: Some(vcard_update::Photo { data: hash }),
+    });
+    crate::presence::send::broadcast_presence(agent).await;
 }
@@ -0,0 +1,128 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::{
+    jid::Jid,
+    minidom::Element,
+    parsers::{
+        data_forms::DataForm,
+        pubsub::{event::Payload, ItemId, PubSubPayload, Subscription},
+    },
+};
+
+use super::PubSubItem;
+
+/// A change to a node we are interested in, with the payload of its items
+/// parsed as `P`.
+#[derive(Debug, Clone)]
+pub enum PubSubNotification<P> {
+    /// An item has been published, or was the last one when we became
+    /// interested in the node.
+    Published(PubSubItem<P>),
+    /// An item has been retracted.
+    Retracted(ItemId),
+    /// Every item of the node has been removed.
+    Purged,
+    /// The node has been deleted.
+    Deleted,
+    /// The configuration of the node changed.
+    Configured(Option<DataForm>),
+    /// Our subscription to the node changed.
+    SubscriptionChanged(Option<Subscription>),
+}
+
+type Handler = Box<dyn Fn(&Jid, &Payload) + Send + Sync>;
+
+/// The handlers for the events of each node, registered by the application.
+#[derive(Default)]
+pub(crate) struct PubSubHandlers {
+    handlers: HashMap<String, Vec<Handler>>,
+}
+
+impl PubSubHandlers {
+    pub fn register<P, F>(&mut self, node: &str, handler: F)
+    where
+        P: PubSubPayload + 'static,
+        P::Error: fmt::Display,
+        F: Fn(&Jid, PubSubNotification<P>) + Send + Sync + 'static,
+    {
+        let node_name = String::from(node);
+        let handler = move |from: &Jid, payload: &Payload| match payload {
+            Payload::Items {
+                published,
+                retracted,
+                ..
+            } => {
+                for item in published {
+                    let Some(payload) = item.payload.clone() else {
+                        continue;
+                    };
+                    match parse_payload::<P>(from, &node_name, payload) {
+                        Some(payload) => handler(
+                            from,
+                            PubSubNotification::Published(PubSubItem {
+                                id: item.id.clone(),
+                                publisher: item.publisher.clone(),
+                                payload,
+                            }),
+                        ),
+                        None => continue,
+                    }
+                }
+                for id in retracted {
+                    handler(from, PubSubNotification::Retracted(id.clone()));
+                }
+            }
+            Payload::Purge { .. } => handler(from, PubSubNotification::Purged),
+            Payload::Delete { .. } => handler(from, PubSubNotification::Deleted),
+            Payload::Configuration { form, .. } => {
+                handler(from, PubSubNotification::Configured(form.clone()))
+            }
+            Payload::Subscription { subscription, .. } => handler(
+                from,
+                PubSubNotification::SubscriptionChanged(subscription.clone()),
+            ),
+        };
+        self.handlers
+            .entry(String::from(node))
+            .or_default()
+            .push(Box::new(handler));
+    }
+
+    /// The nodes whose events we are interested in.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Pass an event to the handlers of its node, returning false if there
+    /// are none.
+    pub fn handle(&self, from: &Jid, payload: &Payload) -> bool {
+        let Some(handlers) = self.handlers.get(&payload.node_name().0) else {
+            return false;
+        };
+        for handler in handlers {
+            handler(from, payload);
+        }
+        true
+    }
+}
+
+pub(crate) fn parse_payload<P>(from: &Jid, node: &str, payload: Element) -> Option<P>
+where
+    P: PubSubPayload,
+    P::Error: fmt::Display,
+{
+    match P::try_from(payload) {
+        Ok(payload) => Some(payload),
+        Err(err) => {
+            warn!("Wrong payload in an item of {node} from {from}: {err}");
+            None
+        }
+    }
+}
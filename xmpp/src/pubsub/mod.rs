@@ -5,34 +5,59 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::{
-    jid::{BareJid, Jid},
+    bookmarks,
+    jid::Jid,
+    message,
     minidom::Element,
-    muc::room::{JoinRoomSettings, LeaveRoomSettings},
     parsers::{
-        bookmarks2, ns,
-        pubsub::{self, pubsub::PubSub},
+        ns,
+        pubsub::{self, pubsub::PubSub, ItemId},
+        rsm::SetResult,
     },
-    Agent, Event, RoomNick,
+    Agent, Event,
 };
 
-use std::str::FromStr;
-
 #[cfg(feature = "avatars")]
 pub(crate) mod avatar;
+pub mod handlers;
+pub mod send;
 
-pub(crate) async fn handle_event(
-    #[cfg_attr(not(feature = "avatars"), allow(unused_variables))] from: &Jid,
-    elem: Element,
-    #[cfg_attr(not(feature = "avatars"), allow(unused_variables))] agent: &mut Agent,
-) -> Vec<Event> {
-    // We allow the useless mut warning for no-default-features,
-    // since for now only avatars pushes events here.
-    #[allow(unused_mut)]
+pub use handlers::PubSubNotification;
+pub use send::{FetchItemsSettings, PublishSettings};
+
+/// An item of a node, with its payload parsed as `P`.
+#[derive(Debug, Clone)]
+pub struct PubSubItem<P> {
+    pub id: Option<ItemId>,
+    pub publisher: Option<Jid>,
+    pub payload: P,
+}
+
+/// A page of the items of a node, see [Agent::fetch_items].
+#[derive(Debug, Clone)]
+pub struct ItemsPage<P> {
+    pub items: Vec<PubSubItem<P>>,
+    /// Where this page is in the whole list, to request the next one.
+    pub set: Option<SetResult>,
+}
+
+pub(crate) async fn handle_event(from: &Jid, elem: Element, agent: &mut Agent) -> Vec<Event> {
     let mut events = Vec::new();
 
     let event = pubsub::Event::try_from(elem);
     trace!("PubSub event: {:#?}", event);
+    let handled = match event {
+        Ok(ref event) => agent.pubsub_handlers.handle(from, &event.payload),
+        Err(_) => false,
+    };
     match event {
+        Ok(pubsub::Event { payload }) if payload.node_name().0 == ns::BOOKMARKS2 => {
+            bookmarks::receive::handle_event(agent, &mut events, from, payload).await;
+        }
+        Ok(pubsub::Event { payload }) if payload.node_name().0 == ns::MDS => {
+            message::displayed::handle_event(agent, &mut events, from, payload);
+        }
+        #[cfg_attr(not(feature = "avatars"), allow(unused_variables))]
         Ok(pubsub::Event {
             payload:
                 pubsub::event::Payload::Items {
@@ -40,135 +65,66 @@ pub(crate) async fn handle_event(
                     published,
                     retracted,
                 },
-        }) => {
-            match node.0 {
-                #[cfg(feature = "avatars")]
-                ref node if node == ns::AVATAR_METADATA => {
-                    // TODO: Also handle retracted!
-                    let new_events =
-                        avatar::handle_metadata_pubsub_event(&from, agent, published).await;
-                    events.extend(new_events);
-                }
-                ref node if node == ns::BOOKMARKS2 => {
-                    // TODO: Check that our bare JID is the sender.
-                    if let [item] = &published[..] {
-                        let jid = BareJid::from_str(&item.id.clone().unwrap().0).unwrap();
-                        let payload = item.payload.clone().unwrap();
-                        match bookmarks2::Conference::try_from(payload) {
-                            Ok(conference) => {
-                                if conference.autojoin {
-                                    if !agent.rooms_joined.contains_key(&jid) {
-                                        agent
-                                            .join_room(JoinRoomSettings {
-                                                room: jid,
-                                                nick: conference.nick.map(RoomNick::new),
-                                                password: conference.password,
-                                                status: None,
-                                            })
-                                            .await;
-                                    }
-                                } else {
-                                    // So maybe another client of ours left the room... let's leave it too
-                                    agent.leave_room(LeaveRoomSettings::new(jid)).await;
-                                }
-                            }
-                            Err(err) => println!("not bookmark: {}", err),
-                        }
-                    } else if let [item] = &retracted[..] {
-                        let jid = BareJid::from_str(&item.0).unwrap();
-
-                        agent.leave_room(LeaveRoomSettings::new(jid)).await;
-                    } else {
-                        error!("No published or retracted item in pubsub event!");
-                    }
+        }) => match node.0 {
+            #[cfg(feature = "avatars")]
+            ref node if node == ns::AVATAR_METADATA => {
+                let new_events =
+                    avatar::handle_metadata_pubsub_event(from, agent, published, retracted).await;
+                events.extend(new_events);
+            }
+            ref node => {
+                if !handled {
+                    debug!("Ignoring PubSub event for node {node}");
                 }
-                ref node => unimplemented!("node {}", node),
             }
-        }
+        },
         Ok(pubsub::Event {
             payload: pubsub::event::Payload::Purge { node },
         }) => match node.0 {
-            ref node if node == ns::BOOKMARKS2 => {
-                warn!("The bookmarks2 PEP node was deleted!");
+            #[cfg(feature = "avatars")]
+            ref node if node == ns::AVATAR_METADATA => {
+                events.push(Event::AvatarRemoved(from.clone()));
+            }
+            ref node => {
+                if !handled {
+                    debug!("Ignoring PubSub purge for node {node}");
+                }
             }
-            ref node => unimplemented!("node {}", node),
         },
         Err(e) => {
             error!("Error parsing PubSub event: {}", e);
         }
-        _ => unimplemented!("PubSub event: {:#?}", event),
+        Ok(event) => {
+            if !handled {
+                debug!("Ignoring PubSub event: {:#?}", event);
+            }
+        }
     }
     events
 }
 
 pub(crate) async fn handle_iq_result(
-    #[cfg_attr(not(feature = "avatars"), allow(unused_variables))] from: &Jid,
+    from: &Jid,
     elem: Element,
     agent: &mut Agent,
 ) -> impl IntoIterator<Item = Event> {
-    // We allow the useless mut warning for no-default-features,
-    // since for now only avatars pushes events here.
-    #[allow(unused_mut)]
     let mut events = Vec::new();
 
-    let pubsub = PubSub::try_from(elem).unwrap();
+    let pubsub = match PubSub::try_from(elem) {
+        Ok(pubsub) => pubsub,
+        Err(e) => {
+            warn!("Wrong PubSub format from {from}: {e}");
+            return events;
+        }
+    };
     trace!("PubSub: {:#?}", pubsub);
-    if let PubSub::Items(items) = pubsub {
+    if let PubSub::Items { items, .. } = pubsub {
         match items.node.0.clone() {
-            #[cfg(feature = "avatars")]
-            ref node if node == ns::AVATAR_DATA => {
-                let new_events = avatar::handle_data_pubsub_iq(&from, &items);
-                events.extend(new_events);
-            }
             ref node if node == ns::BOOKMARKS2 => {
-                // Keep track of the new added/removed rooms in the bookmarks2 list.
-                // The rooms we joined which are no longer in the list should be left ASAP.
-                let mut new_room_list: Vec<BareJid> = Vec::new();
-
-                for item in items.items {
-                    let jid = BareJid::from_str(&item.id.clone().unwrap().0).unwrap();
-                    let payload = item.payload.clone().unwrap();
-                    match bookmarks2::Conference::try_from(payload) {
-                        Ok(conference) => {
-                            // This room was either marked for join or leave, but it was still in the bookmarks.
-                            // Keep track in new_room_list.
-                            new_room_list.push(jid.clone());
-
-                            if conference.autojoin {
-                                if !agent.rooms_joined.contains_key(&jid) {
-                                    agent
-                                        .join_room(JoinRoomSettings {
-                                            room: jid,
-                                            nick: conference.nick.map(RoomNick::new),
-                                            password: conference.password,
-                                            status: None,
-                                        })
-                                        .await;
-                                }
-                            } else {
-                                // Leave the room that is no longer autojoin
-                                agent.leave_room(LeaveRoomSettings::new(jid)).await;
-                            }
-                        }
-                        Err(err) => {
-                            warn!("Wrong payload type in bookmarks2 item: {}", err);
-                        }
-                    }
-                }
-
-                // Now we leave the rooms that are no longer in the bookmarks
-                let mut rooms_to_leave: Vec<BareJid> = Vec::new();
-                for (room, _nick) in &agent.rooms_joined {
-                    if !new_room_list.contains(&room) {
-                        rooms_to_leave.push(room.clone());
-                    }
-                }
-
-                for room in rooms_to_leave {
-                    agent.leave_room(LeaveRoomSettings::new(room)).await;
-                }
+                bookmarks::receive::handle_bookmarks2_result(agent, &mut events, items).await;
             }
-            _ => unimplemented!(),
+            // Most likely the late response to a request which timed out.
+            node => warn!("Ignored PubSub items of node {node} from {from}"),
         }
     }
     events
@@ -0,0 +1,446 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::fmt;
+use tokio_xmpp::IqRequest;
+
+use crate::{
+    iq::{request, IqKind},
+    jid::{BareJid, Jid},
+    minidom::Element,
+    parsers::{
+        data_forms::{DataForm, DataFormType, Field},
+        ns,
+        pubsub::{
+            owner::{self, Affiliation},
+            pubsub::{Item, Items, Publish, PublishOptions, Retract, Subscribe, Unsubscribe},
+            ItemId, NodeName, PubSub, PubSubOwner, PubSubPayload, Subscription,
+        },
+        rsm::SetQuery,
+        stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    },
+    Agent,
+};
+
+use super::{handlers::parse_payload, ItemsPage, PubSubItem};
+
+/// A request to publish an item to a node.
+#[derive(Clone, Debug)]
+pub struct PublishSettings<P> {
+    /// The service hosting the node, or our own account (PEP) if None.
+    pub service: Option<BareJid>,
+    pub node: String,
+    /// The id of the item, replacing any item with the same id; the service
+    /// generates one if None.
+    pub id: Option<String>,
+    pub payload: P,
+    /// The node configuration the publication is conditioned on, as
+    /// `pubsub#…` fields and their values.
+    pub options: Vec<(String, String)>,
+}
+
+impl<P: PubSubPayload> PublishSettings<P> {
+    pub fn new(node: impl Into<String>, payload: P) -> Self {
+        Self {
+            service: None,
+            node: node.into(),
+            id: None,
+            payload,
+            options: Vec::new(),
+        }
+    }
+
+    pub fn with_service(mut self, service: BareJid) -> Self {
+        self.service = Some(service);
+        self
+    }
+
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Only publish if the node has this configuration value, for instance
+    /// `pubsub#access_model` set to `whitelist`.
+    ///
+    /// A PEP node which doesn't exist yet gets created with it; otherwise, a
+    /// mismatch makes the service refuse the publication with a conflict
+    /// error.
+    pub fn with_option(mut self, var: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.push((var.into(), value.into()));
+        self
+    }
+
+    /// Only publish to a node readable by us alone, which keeps every item,
+    /// as XEP-0223 (Persistent Storage of Private Data via PubSub) requires.
+    pub(crate) fn private(self) -> Self {
+        self.with_option("pubsub#persist_items", "true")
+            .with_option("pubsub#max_items", "max")
+            .with_option("pubsub#send_last_published_item", "never")
+            .with_option("pubsub#access_model", "whitelist")
+    }
+}
+
+/// A request for the items of a node.
+#[derive(Clone, Debug)]
+pub struct FetchItemsSettings {
+    /// The service hosting the node, or our own account (PEP) if None.
+    pub service: Option<BareJid>,
+    pub node: String,
+    /// Only fetch these items, instead of every one.
+    pub ids: Vec<String>,
+    /// Only fetch this many of the most recent items.
+    pub max_items: Option<u32>,
+    /// Which page of items to fetch, using Result Set Management
+    /// (XEP-0059).
+    pub set: Option<SetQuery>,
+}
+
+impl FetchItemsSettings {
+    pub fn new(node: impl Into<String>) -> Self {
+        Self {
+            service: None,
+            node: node.into(),
+            ids: Vec::new(),
+            max_items: None,
+            set: None,
+        }
+    }
+
+    pub fn with_service(mut self, service: BareJid) -> Self {
+        self.service = Some(service);
+        self
+    }
+
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.ids.push(id.into());
+        self
+    }
+
+    pub fn with_max_items(mut self, max_items: u32) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Fetch the page of at most `max` items following the item `after`,
+    /// or the first one if None.
+    pub fn with_page(mut self, max: usize, after: Option<String>) -> Self {
+        self.set = Some(SetQuery {
+            max: Some(max),
+            after,
+            before: None,
+            index: None,
+        });
+        self
+    }
+}
+
+fn local_error(text: &str) -> StanzaError {
+    StanzaError::new(
+        ErrorType::Cancel,
+        DefinedCondition::UndefinedCondition,
+        "en",
+        text,
+    )
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn own_jid(agent: &Agent) -> Result<BareJid, StanzaError> {
+    agent
+        .client
+        .bound_jid()
+        .map(Jid::to_bare)
+        .ok_or_else(|| local_error("Not connected."))
+}
+
+#[allow(clippy::result_large_err)]
+fn service_jid(agent: &Agent, service: Option<BareJid>) -> Result<Jid, StanzaError> {
+    match service {
+        Some(service) => Ok(Jid::from(service)),
+        None => own_jid(agent).map(Jid::from),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_result<T>(to: &Jid, result: Option<Element>) -> Result<T, StanzaError>
+where
+    T: TryFrom<Element>,
+    T::Error: fmt::Display,
+{
+    let Some(result) = result else {
+        warn!("Empty PubSub result from {to}, while a payload was expected");
+        return Err(local_error("Empty result."));
+    };
+    T::try_from(result).map_err(|err| {
+        warn!("Wrong PubSub result format from {to}: {err}");
+        local_error(&format!("{}", err))
+    })
+}
+
+async fn send(
+    agent: &mut Agent,
+    to: Jid,
+    kind: IqKind,
+    payload: Element,
+) -> Result<Option<Element>, StanzaError> {
+    let request = match kind {
+        IqKind::Get => IqRequest::Get(payload),
+        IqKind::Set => IqRequest::Set(payload),
+    };
+    request::send_request(agent, to, request).await
+}
+
+/// Publish an item, returning the id it got.
+pub async fn publish<P: PubSubPayload>(
+    agent: &mut Agent,
+    settings: PublishSettings<P>,
+) -> Result<ItemId, StanzaError> {
+    let PublishSettings {
+        service,
+        node,
+        id,
+        payload,
+        options,
+    } = settings;
+    let to = service_jid(agent, service)?;
+    let publish_options = if options.is_empty() {
+        None
+    } else {
+        let fields = options
+            .iter()
+            .map(|(var, value)| Field::text_single(var, value))
+            .collect();
+        Some(PublishOptions {
+            form: Some(DataForm::new(
+                DataFormType::Submit,
+                ns::PUBSUB_PUBLISH_OPTIONS,
+                fields,
+            )),
+        })
+    };
+    let pubsub = PubSub::Publish {
+        publish: Publish {
+            node: NodeName(node),
+            items: vec![Item::new(id.clone().map(ItemId), None, Some(payload))],
+        },
+        publish_options,
+    };
+    let result = send(agent, to.clone(), IqKind::Set, pubsub.into()).await?;
+
+    // The service only has to tell us the id if it generated it.
+    let published = match result {
+        Some(result) => match parse_result(&to, Some(result))? {
+            PubSub::Publish { publish, .. } => publish.items.into_iter().find_map(|item| item.id),
+            _ => None,
+        },
+        None => None,
+    };
+    published
+        .or(id.map(ItemId))
+        .ok_or_else(|| local_error("No id for the published item."))
+}
+
+/// Fetch the items of a node, parsing their payload as `P`.
+pub async fn fetch_items<P>(
+    agent: &mut Agent,
+    settings: FetchItemsSettings,
+) -> Result<ItemsPage<P>, StanzaError>
+where
+    P: PubSubPayload,
+    P::Error: fmt::Display,
+{
+    let FetchItemsSettings {
+        service,
+        node,
+        ids,
+        max_items,
+        set,
+    } = settings;
+    let to = service_jid(agent, service)?;
+    let mut items = Items::new(&node);
+    items.max_items = max_items;
+    items.items = ids
+        .into_iter()
+        .map(|id| Item {
+            id: Some(ItemId(id)),
+            publisher: None,
+            payload: None,
+        })
+        .collect();
+    let pubsub = PubSub::Items {
+        items,
+        set_query: set,
+        set_result: None,
+    };
+    let result = send(agent, to.clone(), IqKind::Get, pubsub.into()).await?;
+    let PubSub::Items {
+        items, set_result, ..
+    } = parse_result(&to, result)?
+    else {
+        return Err(local_error("Unexpected PubSub result."));
+    };
+
+    let items = items
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let payload = parse_payload(&to, &node, item.payload?)?;
+            Some(PubSubItem {
+                id: item.id,
+                publisher: item.publisher,
+                payload,
+            })
+        })
+        .collect();
+    Ok(ItemsPage {
+        items,
+        set: set_result,
+    })
+}
+
+/// Retract an item from a node.
+pub async fn retract_item(
+    agent: &mut Agent,
+    service: Option<BareJid>,
+    node: &str,
+    id: &str,
+    notify: bool,
+) -> Result<(), StanzaError> {
+    let to = service_jid(agent, service)?;
+    let pubsub = PubSub::Retract(Retract {
+        node: NodeName(String::from(node)),
+        notify,
+        items: vec![Item {
+            id: Some(ItemId(String::from(id))),
+            publisher: None,
+            payload: None,
+        }],
+    });
+    send(agent, to, IqKind::Set, pubsub.into())
+        .await
+        .map(|_| ())
+}
+
+/// Subscribe our account to a node, returning the state of the
+/// subscription.
+pub async fn subscribe(
+    agent: &mut Agent,
+    service: BareJid,
+    node: &str,
+) -> Result<Subscription, StanzaError> {
+    let jid = Jid::from(own_jid(agent)?);
+    let to = Jid::from(service);
+    let pubsub = PubSub::Subscribe {
+        subscribe: Some(Subscribe {
+            jid,
+            node: Some(NodeName(String::from(node))),
+        }),
+        options: None,
+    };
+    let result = send(agent, to.clone(), IqKind::Set, pubsub.into()).await?;
+    match result {
+        Some(result) => match parse_result(&to, Some(result))? {
+            PubSub::Subscription(subscription) => Ok(subscription
+                .subscription
+                .unwrap_or(Subscription::Subscribed)),
+            _ => Err(local_error("Unexpected PubSub result.")),
+        },
+        // The service accepted without any detail.
+        None => Ok(Subscription::Subscribed),
+    }
+}
+
+/// Unsubscribe our account from a node.
+pub async fn unsubscribe(
+    agent: &mut Agent,
+    service: BareJid,
+    node: &str,
+) -> Result<(), StanzaError> {
+    let jid = Jid::from(own_jid(agent)?);
+    let pubsub = PubSub::Unsubscribe(Unsubscribe {
+        jid,
+        node: Some(NodeName(String::from(node))),
+        subid: None,
+    });
+    send(agent, Jid::from(service), IqKind::Set, pubsub.into())
+        .await
+        .map(|_| ())
+}
+
+/// Fetch the configuration form of a node.
+pub async fn node_config(
+    agent: &mut Agent,
+    service: Option<BareJid>,
+    node: &str,
+) -> Result<DataForm, StanzaError> {
+    let to = service_jid(agent, service)?;
+    let pubsub = PubSubOwner::Configure(owner::Configure {
+        node: Some(NodeName(String::from(node))),
+        form: None,
+    });
+    let result = send(agent, to.clone(), IqKind::Get, pubsub.into()).await?;
+    match parse_result(&to, result)? {
+        PubSubOwner::Configure(owner::Configure {
+            form: Some(form), ..
+        }) => Ok(form),
+        _ => Err(local_error("No configuration form in the PubSub result.")),
+    }
+}
+
+/// Change the configuration of a node, with a form filled from the one of
+/// [node_config].
+pub async fn configure_node(
+    agent: &mut Agent,
+    service: Option<BareJid>,
+    node: &str,
+    mut form: DataForm,
+) -> Result<(), StanzaError> {
+    let to = service_jid(agent, service)?;
+    form.type_ = DataFormType::Submit;
+    let pubsub = PubSubOwner::Configure(owner::Configure {
+        node: Some(NodeName(String::from(node))),
+        form: Some(form),
+    });
+    send(agent, to, IqKind::Set, pubsub.into())
+        .await
+        .map(|_| ())
+}
+
+/// Fetch the affiliations of every entity to a node.
+pub async fn node_affiliations(
+    agent: &mut Agent,
+    service: Option<BareJid>,
+    node: &str,
+) -> Result<Vec<Affiliation>, StanzaError> {
+    let to = service_jid(agent, service)?;
+    let pubsub = PubSubOwner::Affiliations(owner::Affiliations {
+        node: NodeName(String::from(node)),
+        affiliations: Vec::new(),
+    });
+    let result = send(agent, to.clone(), IqKind::Get, pubsub.into()).await?;
+    match parse_result(&to, result)? {
+        PubSubOwner::Affiliations(affiliations) => Ok(affiliations.affiliations),
+        _ => Err(local_error("Unexpected PubSub result.")),
+    }
+}
+
+/// Change the affiliations of some entities to a node, `none` removing
+/// them.
+pub async fn set_node_affiliations(
+    agent: &mut Agent,
+    service: Option<BareJid>,
+    node: &str,
+    affiliations: Vec<Affiliation>,
+) -> Result<(), StanzaError> {
+    let to = service_jid(agent, service)?;
+    let pubsub = PubSubOwner::Affiliations(owner::Affiliations {
+        node: NodeName(String::from(node)),
+        affiliations,
+    });
+    send(agent, to, IqKind::Set, pubsub.into())
+        .await
+        .map(|_| ())
+}
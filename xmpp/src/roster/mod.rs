@@ -0,0 +1,180 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::str::FromStr;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::{
+    jid::BareJid,
+    parsers::roster::{Ask, Group, Item, Roster as RosterQuery, Subscription},
+    store::{self, StateStore},
+    Event,
+};
+
+pub mod receive;
+pub mod send;
+
+pub use send::ContactSettings;
+
+/// The contact list of the account, as last received from the server.
+#[derive(Clone, Debug, Default)]
+pub struct Roster {
+    ver: Option<String>,
+    items: HashMap<BareJid, Item>,
+}
+
+impl Roster {
+    /// The version of this contact list, if the server supports roster
+    /// versioning.
+    pub fn ver(&self) -> Option<&str> {
+        self.ver.as_deref()
+    }
+
+    pub fn get(&self, jid: &BareJid) -> Option<&Item> {
+        self.items.get(jid)
+    }
+
+    pub fn contains(&self, jid: &BareJid) -> bool {
+        self.items.contains_key(jid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.values()
+    }
+
+    /// Iterate over the contacts which are part of `group`.
+    pub fn group<'a>(&'a self, group: &'a str) -> impl Iterator<Item = &'a Item> {
+        self.items
+            .values()
+            .filter(move |item| item.groups.iter().any(|g| g.0 == group))
+    }
+
+    /// Whether we receive the presence of this contact.
+    pub fn is_subscribed_to(&self, jid: &BareJid) -> bool {
+        matches!(
+            self.items.get(jid).map(|item| &item.subscription),
+            Some(Subscription::To | Subscription::Both)
+        )
+    }
+
+    /// Whether this contact receives our presence.
+    pub fn has_subscription_from(&self, jid: &BareJid) -> bool {
+        matches!(
+            self.items.get(jid).map(|item| &item.subscription),
+            Some(Subscription::From | Subscription::Both)
+        )
+    }
+
+    /// Replace the whole contact list, returning the events describing the
+    /// differences with the previous one.
+    pub(crate) fn replace(&mut self, roster: RosterQuery) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut old = core::mem::take(&mut self.items);
+        for item in roster.items {
+            match old.remove(&item.jid) {
+                None => events.push(Event::ContactAdded(item.clone())),
+                Some(previous) if previous != item => {
+                    events.push(Event::ContactChanged(item.clone()))
+                }
+                Some(_) => (),
+            }
+            self.items.insert(item.jid.clone(), item);
+        }
+        events.extend(old.into_values().map(Event::ContactRemoved));
+        self.ver = roster.ver;
+        events
+    }
+
+    /// Apply a roster push, returning the event it produces.
+    pub(crate) fn push(&mut self, item: Item, ver: Option<String>) -> Option<Event> {
+        if ver.is_some() {
+            self.ver = ver;
+        }
+        if item.subscription == Subscription::Remove {
+            return self.items.remove(&item.jid).map(Event::ContactRemoved);
+        }
+        let event = match self.items.get(&item.jid) {
+            None => Event::ContactAdded(item.clone()),
+            Some(previous) if *previous != item => Event::ContactChanged(item.clone()),
+            Some(_) => return None,
+        };
+        self.items.insert(item.jid.clone(), item);
+        Some(event)
+    }
+}
+
+/// Load the contact list saved by [`save`].
+pub(crate) fn load(state: &dyn StateStore) -> Option<RosterQuery> {
+    let value = store::load(state, store::ROSTER)?;
+    let roster = value
+        .get("items")
+        .and_then(Value::as_array)
+        .and_then(|items| {
+            Some(RosterQuery {
+                ver: value.get("ver").and_then(Value::as_str).map(String::from),
+                items: items.iter().map(item_from_json).collect::<Option<_>>()?,
+            })
+        });
+    if roster.is_none() {
+        warn!("Wrong roster format in the state store");
+    }
+    roster
+}
+
+/// Save the contact list along with its version, so that only the changes
+/// are sent by the server on the next connection.
+pub(crate) fn save(state: &dyn StateStore, roster: &Roster) {
+    // Without a version, the server sends the whole list anyway.
+    let Some(ver) = roster.ver.as_deref() else {
+        return;
+    };
+    let items: Vec<Value> = roster.items.values().map(item_to_json).collect();
+    store::store(state, store::ROSTER, &json!({ "ver": ver, "items": items }));
+}
+
+fn item_to_json(item: &Item) -> Value {
+    let subscription = match item.subscription {
+        Subscription::None => "none",
+        Subscription::From => "from",
+        Subscription::To => "to",
+        Subscription::Both => "both",
+        Subscription::Remove => "remove",
+    };
+    let groups: Vec<&str> = item.groups.iter().map(|group| group.0.as_str()).collect();
+    json!({
+        "jid": item.jid.as_str(),
+        "name": item.name,
+        "subscription": subscription,
+        "ask": item.ask == Ask::Subscribe,
+        "groups": groups,
+    })
+}
+
+fn item_from_json(value: &Value) -> Option<Item> {
+    let groups = value.get("groups")?.as_array()?;
+    Some(Item {
+        jid: BareJid::new(value.get("jid")?.as_str()?).ok()?,
+        name: value.get("name").and_then(Value::as_str).map(String::from),
+        subscription: Subscription::from_str(value.get("subscription")?.as_str()?).ok()?,
+        ask: match value.get("ask")?.as_bool()? {
+            true => Ask::Subscribe,
+            false => Ask::None,
+        },
+        groups: groups
+            .iter()
+            .map(|group| Some(Group(String::from(group.as_str()?))))
+            .collect::<Option<_>>()?,
+    })
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::Jid,
+    minidom::Element,
+    parsers::{
+        iq::Iq,
+        roster::Roster as RosterQuery,
+        stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    },
+    roster, Agent, Event,
+};
+
+/// Handle the answer to our roster request, when the server sent the whole
+/// contact list instead of pushing the changes since our version.
+pub async fn handle_roster_result(agent: &mut Agent, events: &mut Vec<Event>, roster: RosterQuery) {
+    events.extend(agent.roster.replace(roster));
+    roster::save(&*agent.state, &agent.roster);
+}
+
+/// Handle a roster push, sent by the server whenever the contact list
+/// changes.
+pub async fn handle_roster_push(
+    agent: &mut Agent,
+    events: &mut Vec<Event>,
+    from: Jid,
+    id: String,
+    payload: Element,
+) {
+    // Only our own server may modify our contact list.
+    if from != agent.client.bound_jid().unwrap().to_bare() {
+        warn!("Received a roster push from {from}, ignoring");
+        let error = StanzaError::new(
+            ErrorType::Cancel,
+            DefinedCondition::ServiceUnavailable,
+            "en",
+            "Roster pushes are only accepted from our own account.",
+        );
+        let iq = Iq::from_error(id, error).with_to(from).into();
+        let _ = agent.client.send_stanza(iq).await;
+        return;
+    }
+
+    let mut push = match RosterQuery::try_from(payload) {
+        Ok(push) if push.items.len() == 1 => push,
+        Ok(_) | Err(_) => {
+            let error = StanzaError::new(
+                ErrorType::Modify,
+                DefinedCondition::BadRequest,
+                "en",
+                "A roster push must contain exactly one item.",
+            );
+            let iq = Iq::from_error(id, error).with_to(from).into();
+            let _ = agent.client.send_stanza(iq).await;
+            return;
+        }
+    };
+
+    let item = push.items.pop().unwrap();
+    events.extend(agent.roster.push(item, push.ver));
+    roster::save(&*agent.state, &agent.roster);
+
+    let iq = Iq::empty_result(from, id).into();
+    let _ = agent.client.send_stanza(iq).await;
+}
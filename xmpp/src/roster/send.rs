@@ -0,0 +1,157 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    jid::BareJid,
+    parsers::{
+        iq::Iq,
+        ns,
+        presence::{Presence, Type as PresenceType},
+        roster::{Ask, Group, Item, Roster as RosterQuery, Subscription},
+    },
+    roster, Agent, Event,
+};
+
+#[derive(Clone, Debug)]
+pub struct ContactSettings {
+    pub jid: BareJid,
+    pub name: Option<String>,
+    pub groups: Vec<String>,
+}
+
+impl ContactSettings {
+    pub fn new(jid: BareJid) -> Self {
+        Self {
+            jid,
+            name: None,
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.groups.push(group.into());
+        self
+    }
+
+    pub fn with_groups(mut self, groups: Vec<String>) -> Self {
+        self.groups = groups;
+        self
+    }
+}
+
+/// Start from an existing contact, e.g. to only rename it.
+impl From<&Item> for ContactSettings {
+    fn from(item: &Item) -> Self {
+        Self {
+            jid: item.jid.clone(),
+            name: item.name.clone(),
+            groups: item.groups.iter().map(|group| group.0.clone()).collect(),
+        }
+    }
+}
+
+/// Request the contact list, loading it from the state store first if this
+/// is the first connection.
+pub(crate) async fn request_roster(agent: &mut Agent, events: &mut Vec<Event>) {
+    if agent.roster.ver().is_none() && agent.roster.is_empty() {
+        if let Some(saved) = roster::load(&*agent.state) {
+            events.extend(agent.roster.replace(saved));
+        }
+    }
+
+    // When our resource was bound inline using XEP-0386 (Bind 2), there was
+    // no stream restart, and these are the features offered before
+    // authentication.  The version must only be sent if they include roster
+    // versioning.
+    let versioning = agent.client.get_stream_features().is_some_and(|features| {
+        features
+            .others
+            .iter()
+            .any(|feature| feature.is("ver", ns::ROSTER_VER))
+    });
+    let iq = Iq::from_get(
+        "roster",
+        RosterQuery {
+            // Only the changes will be sent if this version is still known.
+            ver: versioning.then(|| agent.roster.ver().unwrap_or("").to_owned()),
+            items: vec![],
+        },
+    )
+    .into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+async fn send_roster_set(agent: &mut Agent, item: Item) {
+    agent.roster_set_counter += 1;
+    let id = format!("roster-set-{}", agent.roster_set_counter);
+    let iq = Iq::from_set(
+        id,
+        RosterQuery {
+            ver: None,
+            items: vec![item],
+        },
+    )
+    .into();
+    let _ = agent.client.send_stanza(iq).await;
+}
+
+pub async fn set_contact(agent: &mut Agent, settings: ContactSettings) {
+    let ContactSettings { jid, name, groups } = settings;
+
+    let item = Item {
+        jid,
+        name,
+        subscription: Subscription::None,
+        ask: Ask::None,
+        groups: groups.into_iter().map(Group).collect(),
+    };
+    send_roster_set(agent, item).await;
+}
+
+pub async fn remove_contact(agent: &mut Agent, jid: BareJid) {
+    let item = Item {
+        jid,
+        name: None,
+        subscription: Subscription::Remove,
+        ask: Ask::None,
+        groups: vec![],
+    };
+    send_roster_set(agent, item).await;
+}
+
+async fn send_subscription(
+    agent: &mut Agent,
+    jid: BareJid,
+    type_: PresenceType,
+    status: Option<&str>,
+) {
+    let mut presence = Presence::new(type_).with_to(jid);
+    if let Some(status) = status {
+        presence.set_status("", status);
+    }
+    let _ = agent.client.send_stanza(presence.into()).await;
+}
+
+pub async fn subscribe(agent: &mut Agent, jid: BareJid, status: Option<&str>) {
+    send_subscription(agent, jid, PresenceType::Subscribe, status).await
+}
+
+pub async fn unsubscribe(agent: &mut Agent, jid: BareJid) {
+    send_subscription(agent, jid, PresenceType::Unsubscribe, None).await
+}
+
+pub async fn approve_subscription(agent: &mut Agent, jid: BareJid) {
+    send_subscription(agent, jid, PresenceType::Subscribed, None).await
+}
+
+pub async fn deny_subscription(agent: &mut Agent, jid: BareJid) {
+    send_subscription(agent, jid, PresenceType::Unsubscribed, None).await
+}
@@ -0,0 +1,393 @@
+// Copyright (c) 2025 xmpp-rs contributors.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Persistent storage of the state of an [Agent][crate::Agent], so that it
+//! doesn't have to fetch everything again after a restart.
+//!
+//! Avatars aren't part of it, they are cached as files of their own.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde_json::{json, Value};
+use tokio_xmpp::{
+    jid::{BareJid, Jid},
+    parsers::date::DateTime,
+    stanzastream::{ResumptionStore, SmResumption},
+    {FastToken, TokenStore},
+};
+
+/// Key of the contact list and its version.
+pub(crate) const ROSTER: &str = "roster";
+/// Key of the last stanza-id received from each archive.
+pub(crate) const ARCHIVES: &str = "archives";
+/// Key of the XEP-0484 (FAST) tokens.
+pub(crate) const FAST_TOKENS: &str = "fast-tokens";
+/// Key of the XEP-0198 (Stream Management) state of the last stream.
+pub(crate) const SM_RESUMPTION: &str = "sm-resumption";
+
+/// Storage for the state of an [Agent][crate::Agent], as JSON values.
+///
+/// Each value is stored under a short key made of ASCII lowercase letters,
+/// digits and dashes, and is replaced entirely on each change; an agent
+/// should have its own store.  Implementations are called from async code
+/// and must not block for long.
+pub trait StateStore: fmt::Debug + Send + Sync + 'static {
+    /// Retrieve the value stored under `key`, if any.
+    fn load(&self, key: &str) -> io::Result<Option<Value>>;
+
+    /// Store `value` under `key`, replacing any previous one.
+    fn store(&self, key: &str, value: &Value) -> io::Result<()>;
+
+    /// Forget the value stored under `key`.
+    fn remove(&self, key: &str) -> io::Result<()>;
+}
+
+/// A [`StateStore`] which only keeps values in memory.
+///
+/// The state survives reconnections, but not restarts of the application.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    values: Mutex<BTreeMap<String, Value>>,
+}
+
+impl MemoryStateStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    fn load(&self, key: &str) -> io::Result<Option<Value>> {
+        Ok(self.values.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, value: &Value) -> io::Result<()> {
+        self.values
+            .lock()
+            .unwrap()
+            .insert(String::from(key), value.clone());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// A [`StateStore`] which keeps each value in a JSON file of a directory,
+/// named after its key.
+///
+/// The directory is created on the first write if it does not exist.  It
+/// contains authentication tokens, and should be protected from other users.
+#[derive(Debug)]
+pub struct JsonDirStateStore {
+    directory: PathBuf,
+}
+
+impl JsonDirStateStore {
+    /// Create a store backed by the directory at `directory`.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let valid = !key.is_empty()
+            && key
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid state store key {key:?}"),
+            ));
+        }
+        Ok(self.directory.join(format!("{key}.json")))
+    }
+}
+
+impl StateStore for JsonDirStateStore {
+    fn load(&self, key: &str) -> io::Result<Option<Value>> {
+        let path = self.path(key)?;
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_slice(&contents)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn store(&self, key: &str, value: &Value) -> io::Result<()> {
+        let path = self.path(key)?;
+        fs::create_dir_all(&self.directory)?;
+        // Write to a temporary file first, so that a crash never leaves a
+        // truncated value behind.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(value)?)?;
+        fs::rename(&tmp, &path)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Load a value, logging any failure.
+pub(crate) fn load(store: &dyn StateStore, key: &str) -> Option<Value> {
+    match store.load(key) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Failed to load {key} from the state store: {e}");
+            None
+        }
+    }
+}
+
+/// Store a value, logging any failure.
+pub(crate) fn store(store: &dyn StateStore, key: &str, value: &Value) {
+    if let Err(e) = store.store(key, value) {
+        warn!("Failed to save {key} to the state store: {e}");
+    }
+}
+
+/// Keeps the FAST tokens of tokio-xmpp in a [`StateStore`].
+#[derive(Debug)]
+pub(crate) struct StateTokenStore {
+    store: Arc<dyn StateStore>,
+    lock: Mutex<()>,
+}
+
+impl StateTokenStore {
+    pub fn new(store: Arc<dyn StateStore>) -> Self {
+        Self {
+            store,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read_all(&self) -> io::Result<serde_json::Map<String, Value>> {
+        match self.store.load(FAST_TOKENS)? {
+            Some(Value::Object(tokens)) => Ok(tokens),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "FAST tokens aren't an object",
+            )),
+            None => Ok(serde_json::Map::new()),
+        }
+    }
+}
+
+fn token_from_json(value: &Value) -> Option<FastToken> {
+    Some(FastToken {
+        mechanism: String::from(value.get("mechanism")?.as_str()?),
+        token: String::from(value.get("token")?.as_str()?),
+        expiry: DateTime::from_str(value.get("expiry")?.as_str()?).ok()?,
+        count: u32::try_from(value.get("count")?.as_u64()?).ok()?,
+    })
+}
+
+impl TokenStore for StateTokenStore {
+    fn load(&self, jid: &BareJid) -> io::Result<Option<FastToken>> {
+        let _guard = self.lock.lock().unwrap();
+        let tokens = self.read_all()?;
+        let Some(value) = tokens.get(jid.as_str()) else {
+            return Ok(None);
+        };
+        match token_from_json(value) {
+            Some(token) => Ok(Some(token)),
+            None => {
+                warn!("Ignoring malformed FAST token for {jid}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn store(&self, jid: &BareJid, token: &FastToken) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read_all()?;
+        tokens.insert(
+            String::from(jid.as_str()),
+            json!({
+                "mechanism": token.mechanism,
+                "token": token.token,
+                "expiry": token.expiry.0.to_rfc3339(),
+                "count": token.count,
+            }),
+        );
+        self.store.store(FAST_TOKENS, &Value::Object(tokens))
+    }
+
+    fn remove(&self, jid: &BareJid) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut tokens = self.read_all()?;
+        if tokens.remove(jid.as_str()).is_some() {
+            self.store.store(FAST_TOKENS, &Value::Object(tokens))?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the stream management state of tokio-xmpp in a [`StateStore`].
+#[derive(Debug)]
+pub(crate) struct StateResumptionStore {
+    store: Arc<dyn StateStore>,
+}
+
+impl StateResumptionStore {
+    pub fn new(store: Arc<dyn StateStore>) -> Self {
+        Self { store }
+    }
+}
+
+fn resumption_from_json(value: &Value) -> Option<SmResumption> {
+    let timestamp = Duration::from_secs(value.get("timestamp")?.as_u64()?);
+    Some(SmResumption {
+        id: String::from(value.get("id")?.as_str()?),
+        bound_jid: Jid::new(value.get("bound-jid")?.as_str()?).ok()?,
+        inbound: u32::try_from(value.get("inbound")?.as_u64()?).ok()?,
+        outbound: u32::try_from(value.get("outbound")?.as_u64()?).ok()?,
+        max: match value.get("max")? {
+            Value::Null => None,
+            max => Some(u32::try_from(max.as_u64()?).ok()?),
+        },
+        timestamp: SystemTime::UNIX_EPOCH.checked_add(timestamp)?,
+    })
+}
+
+impl ResumptionStore for StateResumptionStore {
+    fn load(&self, jid: &BareJid) -> io::Result<Option<SmResumption>> {
+        let Some(value) = self.store.load(SM_RESUMPTION)? else {
+            return Ok(None);
+        };
+        // The store belongs to another account.
+        if value.get("jid").and_then(Value::as_str) != Some(jid.as_str()) {
+            return Ok(None);
+        }
+        match resumption_from_json(&value) {
+            Some(resumption) => Ok(Some(resumption)),
+            None => {
+                warn!("Ignoring malformed stream management state for {jid}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn store(&self, jid: &BareJid, resumption: &SmResumption) -> io::Result<()> {
+        let timestamp = resumption
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let value = json!({
+            "jid": jid.as_str(),
+            "id": resumption.id,
+            "bound-jid": resumption.bound_jid.as_str(),
+            "inbound": resumption.inbound,
+            "outbound": resumption.outbound,
+            "max": resumption.max,
+            "timestamp": timestamp.as_secs(),
+        });
+        self.store.store(SM_RESUMPTION, &value)
+    }
+
+    fn remove(&self, _jid: &BareJid) -> io::Result<()> {
+        self.store.remove(SM_RESUMPTION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("xmpp-store-test-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn json_dir_roundtrip() {
+        let dir = directory("roundtrip");
+        let store = JsonDirStateStore::new(&dir);
+        assert_eq!(store.load(ROSTER).unwrap(), None);
+        store.remove(ROSTER).unwrap();
+
+        let value = json!({"ver": "ver7", "items": []});
+        store.store(ROSTER, &value).unwrap();
+        assert!(dir.join("roster.json").is_file());
+        assert!(!dir.join("roster.json.tmp").exists());
+
+        let reopened = JsonDirStateStore::new(&dir);
+        assert_eq!(reopened.load(ROSTER).unwrap(), Some(value));
+        store.store(ROSTER, &json!([])).unwrap();
+        assert_eq!(reopened.load(ROSTER).unwrap(), Some(json!([])));
+
+        reopened.remove(ROSTER).unwrap();
+        assert_eq!(store.load(ROSTER).unwrap(), None);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_dir_rejects_keys() {
+        let dir = directory("keys");
+        let store = JsonDirStateStore::new(&dir);
+        for key in ["", "../roster", "Roster", "a/b", "a.b"] {
+            let error = store.store(key, &json!(null)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            assert!(store.load(key).is_err());
+            assert!(store.remove(key).is_err());
+        }
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn resumption_roundtrip() {
+        let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+        let store = StateResumptionStore::new(state.clone());
+        let juliet = BareJid::new("juliet@capulet.lit").unwrap();
+        let romeo = BareJid::new("romeo@montague.lit").unwrap();
+        let resumption = SmResumption {
+            id: String::from("some-long-sm-id"),
+            bound_jid: Jid::new("juliet@capulet.lit/balcony").unwrap(),
+            inbound: 12,
+            outbound: 4,
+            max: None,
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        };
+        store.store(&juliet, &resumption).unwrap();
+        assert_eq!(store.load(&juliet).unwrap(), Some(resumption));
+        assert_eq!(store.load(&romeo).unwrap(), None);
+
+        store.remove(&juliet).unwrap();
+        assert_eq!(state.load(SM_RESUMPTION).unwrap(), None);
+    }
+
+    #[test]
+    fn json_dir_invalid_data() {
+        let dir = directory("invalid");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("archives.json"), b"{").unwrap();
+        let store = JsonDirStateStore::new(&dir);
+        let error = store.load(ARCHIVES).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(load(&store, ARCHIVES), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}